@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use bevy::prelude::*;
+use crate::enums::Direction;
+
+pub const IDLE: &str = "idle";
+pub const WALK_UP: &str = "walk-up";
+pub const WALK_RIGHT: &str = "walk-right";
+pub const WALK_DOWN: &str = "walk-down";
+pub const WALK_LEFT: &str = "walk-left";
+pub const SCARED: &str = "scared";
+pub const FLASHING: &str = "flashing";
+pub const DEATH: &str = "death";
+pub const EYES_UP: &str = "eyes-up";
+pub const EYES_RIGHT: &str = "eyes-right";
+pub const EYES_DOWN: &str = "eyes-down";
+pub const EYES_LEFT: &str = "eyes-left";
+pub const REVIVING: &str = "reviving";
+pub const BLINK: &str = "blink";
+
+/// A named run of frames from a sprite sheet. Clips that don't loop hold their last frame.
+#[derive(Clone, Debug)]
+pub struct AnimationClip {
+    pub frames: Vec<u32>,
+    pub frame_seconds: f32,
+    pub looping: bool
+}
+
+impl AnimationClip {
+    pub fn new(frames: &[u32], frame_seconds: f32, looping: bool) -> Self {
+        Self {
+            frames: frames.to_vec(),
+            frame_seconds,
+            looping
+        }
+    }
+}
+
+/// Plays one of an entity's clips by stepping the index of its `TextureAtlasSprite`.
+pub struct Animation {
+    clips: HashMap<&'static str, AnimationClip>,
+    clip: &'static str,
+    frame: usize,
+    timer: Timer
+}
+
+impl Animation {
+    /// Starts out playing `initial`, which must be one of `clips`.
+    pub fn new(clips: Vec<(&'static str, AnimationClip)>, initial: &'static str) -> Self {
+        let mut animation = Self {
+            clips: clips.into_iter().collect(),
+            clip: initial,
+            frame: 0,
+            timer: Timer::from_seconds(1., false)
+        };
+        animation.restart(initial);
+        animation
+    }
+
+    pub fn clip(&self) -> &'static str {
+        self.clip
+    }
+
+    /// Switches to `clip` unless it is already playing.
+    pub fn play(&mut self, clip: &'static str) {
+        if self.clip != clip {
+            self.restart(clip);
+        }
+    }
+
+    /// Plays `clip` from its first frame.
+    pub fn restart(&mut self, clip: &'static str) {
+        let frame_seconds = self.clips[clip].frame_seconds;
+        self.clip = clip;
+        self.frame = 0;
+        self.timer = Timer::from_seconds(frame_seconds, true);
+    }
+
+    pub fn sprite_index(&self) -> u32 {
+        self.clips[self.clip].frames[self.frame]
+    }
+
+    fn advance(&mut self, delta: std::time::Duration) {
+        let clip = &self.clips[self.clip];
+        self.timer.tick(delta);
+        for _ in 0..self.timer.times_finished() {
+            if self.frame + 1 < clip.frames.len() {
+                self.frame += 1;
+            } else if clip.looping {
+                self.frame = 0;
+            }
+        }
+    }
+}
+
+/// Loads a sprite sheet laid out as a single row of equally sized frames.
+pub fn load_sprite_sheet(
+    asset_server: &AssetServer,
+    texture_atlases: &mut Assets<TextureAtlas>,
+    path: &str,
+    frame_size: Vec2,
+    columns: usize
+) -> Handle<TextureAtlas> {
+    let texture = asset_server.load(path);
+    texture_atlases.add(TextureAtlas::from_grid(texture, frame_size, columns, 1))
+}
+
+pub fn animation_system(
+    mut query: Query<(&mut Animation, &mut TextureAtlasSprite)>,
+    time: Res<Time>
+) {
+    for (mut animation, mut sprite) in query.iter_mut() {
+        animation.advance(time.delta());
+        let index = animation.sprite_index();
+        if sprite.index != index {
+            sprite.index = index;
+        }
+    }
+}
+
+pub fn walk_clip(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => WALK_UP,
+        Direction::Right => WALK_RIGHT,
+        Direction::Down => WALK_DOWN,
+        Direction::Left => WALK_LEFT
+    }
+}
+
+pub fn eyes_clip(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => EYES_UP,
+        Direction::Right => EYES_RIGHT,
+        Direction::Down => EYES_DOWN,
+        Direction::Left => EYES_LEFT
+    }
+}
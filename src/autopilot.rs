@@ -0,0 +1,398 @@
+use std::collections::VecDeque;
+use bevy::prelude::*;
+use crate::ben::{Ben, BenDirection, BenNextDirection};
+use crate::board::{Board, BoardTile};
+use crate::dot::Dot;
+use crate::enums::Direction;
+use crate::ghost::{Ghost, AttackState, ReleaseState, GhostPath};
+use crate::movement::{self, TilePosition};
+use crate::power_up::PowerUp;
+use crate::sim::{Sim, SimConfig, SimEvent};
+
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+// Tiles Ben wants to reach ahead of any ghost before he goes there
+const SAFETY_MARGIN: u32 = 2;
+// How close a ghost has to get before `Bait` goes for a power-up
+const BAIT_RADIUS: u32 = 6;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GhostView {
+    pub position: TilePosition,
+    /// Tiles the ghost is about to walk through, in order. It only picks a new path once it gets to the end of this one.
+    pub path: Vec<(usize, usize)>,
+    pub attack_state: AttackState,
+    pub release_state: ReleaseState
+}
+
+impl GhostView {
+    /// Whether the ghost can catch Ben right now.
+    pub fn is_threat(&self) -> bool {
+        self.attack_state == AttackState::Attacking && matches!(self.release_state, ReleaseState::Releasing | ReleaseState::Released)
+    }
+
+    pub fn is_edible(&self) -> bool {
+        self.attack_state == AttackState::Scared && self.release_state == ReleaseState::Released
+    }
+}
+
+/// Everything a strategy gets to see: the maze, where Ben and the ghosts are (as the `TilePosition`s their transforms
+/// follow), and what is left to eat.
+pub struct View<'a> {
+    pub board: &'a Board,
+    pub ben: TilePosition,
+    pub direction: Direction,
+    pub ghosts: Vec<GhostView>,
+    pub dots: Vec<(usize, usize)>,
+    pub power_ups: Vec<(usize, usize)>
+}
+
+impl<'a> View<'a> {
+    pub fn from_sim(sim: &Sim, board: &'a Board, player: usize) -> Self {
+        let ben = &sim.bens[player];
+        let pickups = |tile: BoardTile| -> Vec<(usize, usize)> {
+            sim.pickups.iter()
+                .enumerate()
+                .flat_map(|(i, row)| row.iter()
+                    .enumerate()
+                    .filter(move |(_, pickup)| **pickup == tile)
+                    .map(move |(j, _)| (i, j)))
+                .collect()
+        };
+        Self {
+            board,
+            ben: ben.position,
+            direction: ben.direction,
+            ghosts: sim.ghosts.iter()
+                .map(|ghost| GhostView {
+                    position: ghost.position,
+                    path: ghost.path.waypoints().map(|waypoint| waypoint.tile()).collect(),
+                    attack_state: ghost.attack_state,
+                    release_state: ghost.release_state
+                })
+                .collect(),
+            dots: pickups(BoardTile::Dot),
+            power_ups: pickups(BoardTile::PowerUp)
+        }
+    }
+
+    /// The tile Ben decides his next turn at: the one he is in, or the next one once he has passed its centre.
+    pub fn decision_tile(&self) -> (usize, usize) {
+        if self.ben.offset_along(self.direction) > 0 {
+            self.board.neighbour(self.ben.i, self.ben.j, self.direction)
+        } else {
+            self.ben.tile()
+        }
+    }
+
+    /// Walking distance in tiles from Ben to every tile he can reach.
+    pub fn ben_distances(&self) -> Vec<Vec<Option<u32>>> {
+        distances(self.board, &[self.decision_tile()], |_| true)
+    }
+
+    /// How many tiles the nearest threatening ghost has to walk to get to every tile. Ghosts keep to the path they
+    /// are following, so a tile on it is reached when the ghost gets there, and any other tile by the shortest way
+    /// from the end of it, where the ghost picks a new path.
+    pub fn ghost_distances(&self) -> Vec<Vec<Option<u32>>> {
+        let mut nearest: Vec<Vec<Option<u32>>> = vec![vec![None; self.board.width()]; self.board.height()];
+        for ghost in self.ghosts.iter().filter(|ghost| ghost.is_threat()) {
+            let mut route = vec![ghost.position.tile()];
+            for tile in ghost.path.iter() {
+                if route.last() != Some(tile) {
+                    route.push(*tile);
+                }
+            }
+
+            let route_end = route.len() as u32 - 1;
+            let mut arrivals = distances(self.board, &route[route.len() - 1..], |_| true);
+            for row in arrivals.iter_mut() {
+                for arrival in row.iter_mut() {
+                    *arrival = arrival.map(|distance| distance + route_end);
+                }
+            }
+            // Backwards, so that a tile the route passes twice keeps the earlier time
+            for (step, (i, j)) in route.iter().enumerate().rev() {
+                arrivals[*i][*j] = Some(step as u32);
+            }
+
+            for (nearest_row, arrival_row) in nearest.iter_mut().zip(arrivals) {
+                for (nearest, arrival) in nearest_row.iter_mut().zip(arrival_row) {
+                    *nearest = match (*nearest, arrival) {
+                        (Some(nearest), Some(arrival)) => Some(nearest.min(arrival)),
+                        (nearest, arrival) => nearest.or(arrival)
+                    };
+                }
+            }
+        }
+        nearest
+    }
+}
+
+/// Drives Ben in place of the keyboard. Implement it to try out a strategy of your own.
+pub trait Strategy: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// The direction Ben should take next, if any. It is queued like a key press, so it's taken at the next tile
+    /// centre where it is possible, or straight away when turning around.
+    fn choose(&mut self, view: &View) -> Option<Direction>;
+}
+
+/// Heads for the nearest dot or power-up and ignores the ghosts.
+pub struct Greedy;
+
+impl Strategy for Greedy {
+    fn name(&self) -> &'static str {
+        "greedy"
+    }
+
+    fn choose(&mut self, view: &View) -> Option<Direction> {
+        let targets: Vec<(usize, usize)> = view.dots.iter().chain(view.power_ups.iter()).copied().collect();
+        steer(view, &targets, |_| true)
+    }
+}
+
+/// Only goes where he gets to well before any ghost could, and runs from the ghosts when there is nowhere like that
+/// left to eat.
+pub struct Cautious;
+
+impl Strategy for Cautious {
+    fn name(&self) -> &'static str {
+        "cautious"
+    }
+
+    fn choose(&mut self, view: &View) -> Option<Direction> {
+        let targets: Vec<(usize, usize)> = view.dots.iter().chain(view.power_ups.iter()).copied().collect();
+        steer_safely(view, &targets).or_else(|| flee(view))
+    }
+}
+
+/// Saves the power-ups until a ghost comes close, then eats one and hunts the scared ghosts down. Cautious otherwise.
+pub struct Bait;
+
+impl Strategy for Bait {
+    fn name(&self) -> &'static str {
+        "bait"
+    }
+
+    fn choose(&mut self, view: &View) -> Option<Direction> {
+        let edible: Vec<(usize, usize)> = view.ghosts.iter()
+            .filter(|ghost| ghost.is_edible())
+            .map(|ghost| ghost.position.tile())
+            .collect();
+        if !edible.is_empty() {
+            if let Some(direction) = steer_safely(view, &edible) {
+                return Some(direction);
+            }
+        }
+
+        let (i, j) = view.decision_tile();
+        let nearest_ghost = view.ghost_distances()[i][j];
+        let targets = if nearest_ghost.is_some_and(|distance| distance <= BAIT_RADIUS) || view.dots.is_empty() {
+            &view.power_ups
+        } else {
+            &view.dots
+        };
+        steer_safely(view, targets)
+            .or_else(|| steer_safely(view, &view.power_ups))
+            .or_else(|| flee(view))
+    }
+}
+
+pub const STRATEGY_NAMES: [&str; 3] = ["greedy", "cautious", "bait"];
+
+pub fn by_name(name: &str) -> Option<Box<dyn Strategy>> {
+    match name {
+        "greedy" => Some(Box::new(Greedy)),
+        "cautious" => Some(Box::new(Cautious)),
+        "bait" => Some(Box::new(Bait)),
+        _ => None
+    }
+}
+
+/// Breadth-first walking distances from the nearest of `sources`, only passing through tiles `through` allows.
+pub fn distances(board: &Board, sources: &[(usize, usize)], through: impl Fn((usize, usize)) -> bool) -> Vec<Vec<Option<u32>>> {
+    let mut distances = vec![vec![None; board.width()]; board.height()];
+    let mut queue = VecDeque::new();
+    for &(i, j) in sources {
+        if distances[i][j].is_none() {
+            distances[i][j] = Some(0);
+            queue.push_back((i, j));
+        }
+    }
+
+    while let Some((i, j)) = queue.pop_front() {
+        let distance = distances[i][j].unwrap();
+        for direction in DIRECTIONS {
+            if !TilePosition::centred(i, j).can_move(board, direction) {
+                continue;
+            }
+
+            let (next_i, next_j) = board.neighbour(i, j, direction);
+            if distances[next_i][next_j].is_none() && through((next_i, next_j)) {
+                distances[next_i][next_j] = Some(distance + 1);
+                queue.push_back((next_i, next_j));
+            }
+        }
+    }
+    distances
+}
+
+/// The direction out of Ben's decision tile that leads down the shortest path to one of `targets`, only passing
+/// through tiles `through` allows. Keeps going straight on ties, so Ben doesn't dither.
+pub fn steer(view: &View, targets: &[(usize, usize)], through: impl Fn((usize, usize)) -> bool) -> Option<Direction> {
+    if targets.is_empty() {
+        return None;
+    }
+
+    let to_targets = distances(view.board, targets, through);
+    let (i, j) = view.decision_tile();
+    let position = TilePosition::centred(i, j);
+    let options = [view.direction].into_iter().chain(DIRECTIONS);
+    options
+        .filter(|direction| position.can_move(view.board, *direction))
+        .filter_map(|direction| {
+            let (next_i, next_j) = view.board.neighbour(i, j, direction);
+            to_targets[next_i][next_j].map(|distance| (direction, distance))
+        })
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(direction, _)| direction)
+}
+
+/// Like `steer`, but only to targets Ben gets to well ahead of the ghosts, and through tiles like that.
+pub fn steer_safely(view: &View, targets: &[(usize, usize)]) -> Option<Direction> {
+    let ben_distances = view.ben_distances();
+    let ghost_distances = view.ghost_distances();
+    let is_safe = |(i, j): (usize, usize)| match (ben_distances[i][j], ghost_distances[i][j]) {
+        (_, None) => true,
+        (Some(ben), Some(ghost)) => ben + SAFETY_MARGIN < ghost,
+        (None, Some(_)) => false
+    };
+
+    let safe_targets: Vec<(usize, usize)> = targets.iter().copied().filter(|tile| is_safe(*tile)).collect();
+    steer(view, &safe_targets, is_safe)
+}
+
+/// Puts as much maze between Ben and the ghosts as possible, for when there is nowhere safe to go.
+pub fn flee(view: &View) -> Option<Direction> {
+    let ghost_distances = view.ghost_distances();
+    let (i, j) = view.decision_tile();
+    let position = TilePosition::centred(i, j);
+    let options = [view.direction].into_iter().chain(DIRECTIONS);
+    options
+        .filter(|direction| position.can_move(view.board, *direction))
+        .max_by_key(|direction| {
+            let (next_i, next_j) = view.board.neighbour(i, j, *direction);
+            ghost_distances[next_i][next_j].unwrap_or(u32::MAX)
+        })
+        .or_else(|| Some(movement::opposite(view.direction)))
+}
+
+/// Runs one tick of the sim with every Ben driven by his strategy.
+pub fn step(sim: &mut Sim, board: &Board, config: &SimConfig, strategies: &mut [Box<dyn Strategy>]) -> Vec<SimEvent> {
+    let inputs: Vec<Option<Direction>> = strategies.iter_mut()
+        .enumerate()
+        .map(|(player, strategy)| if sim.bens[player].lives > 0 {
+            strategy.choose(&View::from_sim(sim, board, player))
+        } else {
+            None
+        })
+        .collect();
+    sim.step(board, config, &inputs)
+}
+
+/// Puts a Ben on autopilot, in place of `ben_controller_system`.
+pub struct Autopilot(pub Box<dyn Strategy>);
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Self(Box::new(Bait))
+    }
+}
+
+pub fn autopilot_system(
+    mut ben_query: Query<(&TilePosition, &BenDirection, &mut BenNextDirection, &mut Autopilot), With<Ben>>,
+    ghost_query: Query<(&TilePosition, &GhostPath, &AttackState, &ReleaseState), With<Ghost>>,
+    dot_query: Query<&TilePosition, With<Dot>>,
+    power_up_query: Query<&TilePosition, With<PowerUp>>,
+    board: Res<Board>
+) {
+    for (position, ben_direction, mut ben_next_direction, mut autopilot) in ben_query.iter_mut() {
+        let view = View {
+            board: &board,
+            ben: *position,
+            direction: ben_direction.0,
+            ghosts: ghost_query.iter()
+                .map(|(position, ghost_path, attack_state, release_state)| GhostView {
+                    position: *position,
+                    path: ghost_path.0.waypoints().map(|waypoint| waypoint.tile()).collect(),
+                    attack_state: *attack_state,
+                    release_state: *release_state
+                })
+                .collect(),
+            dots: dot_query.iter().map(|position| position.tile()).collect(),
+            power_ups: power_up_query.iter().map(|position| position.tile()).collect()
+        };
+        ben_next_direction.0 = autopilot.0.choose(&view);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants;
+
+    // A ring of corridor around a block of wall
+    const RING: &str = "@ben_spawn 3 3\n@ghost_house 3 3\n@ghost_exit 1 3\n#########\n#_______#\n#_#####_#\n#_______#\n#########\n";
+
+    fn ring() -> Board {
+        Board::from_level_str(RING, constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET).unwrap()
+    }
+
+    fn ghost(tile: (usize, usize), attack_state: AttackState, path: &[(usize, usize)]) -> GhostView {
+        GhostView {
+            position: TilePosition::centred(tile.0, tile.1),
+            path: path.to_vec(),
+            attack_state,
+            release_state: ReleaseState::Released
+        }
+    }
+
+    fn view(board: &Board, ben: (usize, usize), direction: Direction, ghosts: Vec<GhostView>, dots: Vec<(usize, usize)>) -> View<'_> {
+        View {
+            board,
+            ben: TilePosition::centred(ben.0, ben.1),
+            direction,
+            ghosts,
+            dots,
+            power_ups: Vec::new()
+        }
+    }
+
+    #[test]
+    fn greedy_turns_back_for_the_nearer_dot() {
+        let board = ring();
+        let view = view(&board, (1, 3), Direction::Right, Vec::new(), vec![(1, 1)]);
+        assert_eq!(Greedy.choose(&view), Some(Direction::Left));
+    }
+
+    #[test]
+    fn cautious_turns_away_from_a_ghost_one_tile_ahead() {
+        let board = ring();
+        let view = view(&board, (1, 3), Direction::Right, vec![ghost((1, 4), AttackState::Attacking, &[(1, 3)])], vec![(1, 5), (1, 6)]);
+        assert_eq!(Cautious.choose(&view), Some(Direction::Left));
+    }
+
+    #[test]
+    fn cautious_follows_a_ghost_walking_away() {
+        // The ghost is right next to the dot, but its path takes it the other way round the ring
+        let board = ring();
+        let view = view(&board, (1, 2), Direction::Right, vec![ghost((1, 5), AttackState::Attacking, &[(1, 6), (1, 7), (2, 7)])], vec![(1, 4)]);
+        assert_eq!(Cautious.choose(&view), Some(Direction::Right));
+    }
+
+    #[test]
+    fn bait_hunts_a_scared_ghost() {
+        let board = ring();
+        let view = view(&board, (1, 3), Direction::Right, vec![ghost((1, 1), AttackState::Scared, &[(1, 2)])], vec![(1, 6)]);
+        assert_eq!(Bait.choose(&view), Some(Direction::Left));
+    }
+}
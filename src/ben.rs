@@ -1,49 +1,292 @@
 use bevy::prelude::*;
+use crate::board::Board;
 use crate::enums::Direction;
 use crate::constants;
+use crate::movement::{self, TilePosition, PreviousTilePosition};
+use crate::animation::{self, Animation, AnimationClip};
+use crate::score::Score;
+use crate::utils;
 
 pub struct Ben;
 
+/// Which player controls a Ben. There is a second Ben only in co-op.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlayerId(pub usize);
+
+/// A Ben with no lives left sits out the rest of a co-op game.
 pub struct BenLives(pub u8);
 
-pub struct BenAnimationTimer(pub Timer);
+/// Marks the Bens a ghost caught, until the death sequence has played out.
+pub struct Caught;
 
 pub struct BenSpeed(pub f32);
 
 pub struct BenDirection(pub Direction);
 
 pub struct BenNextDirection(pub Option<Direction>);
-pub struct BenMaterials {
-    pub ben_default: Handle<ColorMaterial>,
-    pub ben_up: Handle<ColorMaterial>,
-    pub ben_right: Handle<ColorMaterial>,
-    pub ben_down: Handle<ColorMaterial>,
-    pub ben_left: Handle<ColorMaterial>,
+
+/// Runs down while a buffered direction waits to be taken; the direction is dropped once it finishes.
+pub struct BenInputTimer(pub Timer);
+
+pub struct BenSpriteSheet {
+    pub atlas: Handle<TextureAtlas>
+}
+
+/// Frames of `ben/ben_sheet.png`: mouth closed, then open towards each direction, then shrinking away.
+const FRAME_CLOSED: u32 = 0;
+const FRAME_UP: u32 = 1;
+const FRAME_RIGHT: u32 = 2;
+const FRAME_DOWN: u32 = 3;
+const FRAME_LEFT: u32 = 4;
+const FRAMES_DEATH: [u32; 8] = [5, 6, 7, 8, 9, 10, 11, 12];
+pub const SHEET_COLUMNS: usize = 13;
+
+pub fn ben_animation() -> Animation {
+    let walk = |frame: u32| AnimationClip::new(&[frame, FRAME_CLOSED], 0.2, true);
+    Animation::new(vec![
+        (animation::IDLE, AnimationClip::new(&[FRAME_CLOSED], 1., true)),
+        (animation::WALK_UP, walk(FRAME_UP)),
+        (animation::WALK_RIGHT, walk(FRAME_RIGHT)),
+        (animation::WALK_DOWN, walk(FRAME_DOWN)),
+        (animation::WALK_LEFT, walk(FRAME_LEFT)),
+        (animation::DEATH, AnimationClip::new(&FRAMES_DEATH, 0.15, false))
+    ], animation::IDLE)
+}
+
+/// Tints a player's Ben (and life icons) so co-op players can tell themselves apart.
+pub fn player_color(player: usize) -> Color {
+    match player {
+        0 => Color::WHITE,
+        _ => Color::rgb(0.6, 1., 0.6)
+    }
+}
+
+/// In co-op the Bens start back to back.
+pub fn initial_direction(player: usize) -> Direction {
+    match player {
+        0 => constants::BEN_DIRECTION_DEFAULT,
+        _ => movement::opposite(constants::BEN_DIRECTION_DEFAULT)
+    }
+}
+
+/// On his own Ben answers to both WASD and the arrow keys; in co-op player one gets WASD and player two the arrows.
+pub fn direction_keys(player: usize, coop: bool) -> Vec<(Direction, KeyCode)> {
+    let wasd = [
+        (Direction::Up, KeyCode::W),
+        (Direction::Right, KeyCode::D),
+        (Direction::Down, KeyCode::S),
+        (Direction::Left, KeyCode::A)
+    ];
+    let arrows = [
+        (Direction::Up, KeyCode::Up),
+        (Direction::Right, KeyCode::Right),
+        (Direction::Down, KeyCode::Down),
+        (Direction::Left, KeyCode::Left)
+    ];
+    match (coop, player) {
+        (false, _) => wasd.iter().chain(arrows.iter()).copied().collect(),
+        (true, 0) => wasd.to_vec(),
+        (true, _) => arrows.to_vec()
+    }
+}
+
+/// Moves Ben `distance` units, taking `next_direction` as soon as it's allowed: at a tile centre, or before one when
+/// cornering or reversing. Returns the direction he ends up going in. Shared by `ben_movement_system` and the sim.
+pub fn advance(
+    board: &Board,
+    position: &mut TilePosition,
+    mut direction: Direction,
+    next_direction: &mut Option<Direction>,
+    distance: i32,
+    cornering: bool,
+    instant_reversal: bool
+) -> Direction {
+    if let Some(turn) = *next_direction {
+        if !position.is_centered() && turn == movement::opposite(direction) {
+            // Between tile centres Ben can always reverse, since he just came from there
+            if instant_reversal && position.is_in_lane(direction) {
+                direction = turn;
+                *next_direction = None;
+            }
+        } else if cornering
+            && turn != direction
+            && !position.is_centered()
+            && position.offset_along(direction).abs() <= movement::CORNERING_UNITS
+            && position.can_move(board, turn)
+        {
+            // Close enough to the centre to cut the corner; `travel` closes the remaining offset diagonally
+            direction = turn;
+            *next_direction = None;
+        }
+    }
+
+    // Take the queued turn at the first tile centre where it is possible
+    position.travel(board, direction, distance, |position, direction| {
+        if let Some(turn) = *next_direction {
+            if position.can_move(board, turn) {
+                *next_direction = None;
+                return Some(turn);
+            }
+        }
+
+        if position.can_move(board, direction) {
+            Some(direction)
+        } else {
+            None
+        }
+    })
+}
+
+pub fn spawn_ben(commands: &mut Commands, board: &Board, atlas: Handle<TextureAtlas>, player: usize) {
+    let (x, y) = utils::get_ben_spawn_coordinates(board);
+    commands.spawn_bundle(BenBundle {
+        player_id: PlayerId(player),
+        position: TilePosition::from_coordinates(board, x, y),
+        direction: BenDirection(initial_direction(player)),
+        sprite_sheet_bundle: SpriteSheetBundle {
+            texture_atlas: atlas,
+            sprite: TextureAtlasSprite {
+                color: player_color(player),
+                ..Default::default()
+            },
+            transform: Transform {
+                translation: Vec3::new(x, y, 10.),
+                scale: Vec3::new(1./6., 1./6., 1.),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    });
 }
 
 #[derive(Bundle)]
 pub struct BenBundle {
     pub ben: Ben,
+    pub player_id: PlayerId,
+    pub score: Score,
     pub lives: BenLives,
+    pub position: TilePosition,
+    pub previous_position: PreviousTilePosition,
     pub direction: BenDirection,
-    pub animation_timer: BenAnimationTimer,
+    pub animation: Animation,
     pub speed: BenSpeed,
     pub next_direction: BenNextDirection,
+    pub input_timer: BenInputTimer,
 
     #[bundle]
-    pub sprite_bundle: SpriteBundle,
+    pub sprite_sheet_bundle: SpriteSheetBundle,
 }
 
 impl Default for BenBundle {
     fn default() -> Self {
         Self {
             ben: Ben,
-            lives: BenLives(3),
+            player_id: PlayerId(0),
+            score: Score(0),
+            lives: BenLives(constants::BEN_LIVES),
+            position: TilePosition::default(),
+            previous_position: PreviousTilePosition::default(),
             direction: BenDirection(Direction::Right),
-            animation_timer: BenAnimationTimer(Timer::from_seconds(0.2, true)),
+            animation: ben_animation(),
             speed: BenSpeed(constants::BEN_SPEED_DEFAULT),
             next_direction: BenNextDirection(None),
-            sprite_bundle: SpriteBundle::default(),
+            input_timer: BenInputTimer(Timer::from_seconds(0., false)),
+            sprite_sheet_bundle: SpriteSheetBundle::default(),
         } 
     }
 } 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A corridor with a single turning up off it, at (2, 3)
+    const JUNCTION: &str = "@ben_spawn 2 1\n@ghost_house 2 4\n@ghost_exit 2 4\n#######\n###_###\n#_____#\n#######\n";
+
+    fn board() -> Board {
+        Board::from_level_str(JUNCTION, constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET).unwrap()
+    }
+
+    #[test]
+    fn buffered_turn_waits_for_the_next_opening() {
+        let board = board();
+        let mut position = TilePosition::centred(2, 1);
+        let mut next_direction = Some(Direction::Up);
+
+        // Walls above the first tiles don't stop Ben, and the turn stays queued
+        let direction = advance(&board, &mut position, Direction::Right, &mut next_direction, movement::TILE_UNITS + 300, true, true);
+        assert_eq!(direction, Direction::Right);
+        assert_eq!(next_direction, Some(Direction::Up));
+        assert_eq!(position, TilePosition { offset_x: 300, ..TilePosition::centred(2, 2) });
+
+        let direction = advance(&board, &mut position, Direction::Right, &mut next_direction, movement::TILE_UNITS, true, true);
+        assert_eq!(direction, Direction::Up);
+        assert_eq!(next_direction, None);
+        assert_eq!(position, TilePosition { offset_y: 300, ..TilePosition::centred(2, 3) });
+    }
+
+    #[test]
+    fn early_turn_cuts_the_corner() {
+        let board = board();
+        let mut position = TilePosition { offset_x: -200, ..TilePosition::centred(2, 3) };
+        let mut next_direction = Some(Direction::Up);
+        let direction = advance(&board, &mut position, Direction::Right, &mut next_direction, 160, true, true);
+        assert_eq!(direction, Direction::Up);
+        assert_eq!(next_direction, None);
+        assert_eq!(position, TilePosition { offset_x: -40, offset_y: 160, ..TilePosition::centred(2, 3) });
+    }
+
+    #[test]
+    fn late_turn_cuts_the_corner() {
+        let board = board();
+        let mut position = TilePosition { offset_x: 200, ..TilePosition::centred(2, 3) };
+        let mut next_direction = Some(Direction::Up);
+        let direction = advance(&board, &mut position, Direction::Right, &mut next_direction, 160, true, true);
+        assert_eq!(direction, Direction::Up);
+        assert_eq!(position, TilePosition { offset_x: 40, offset_y: 160, ..TilePosition::centred(2, 3) });
+    }
+
+    #[test]
+    fn without_cornering_turns_wait_for_the_centre() {
+        let board = board();
+        let mut position = TilePosition { offset_x: -200, ..TilePosition::centred(2, 3) };
+        let mut next_direction = Some(Direction::Up);
+        let direction = advance(&board, &mut position, Direction::Right, &mut next_direction, 160, false, true);
+        assert_eq!(direction, Direction::Right);
+        assert_eq!(next_direction, Some(Direction::Up));
+        assert_eq!(position, TilePosition { offset_x: -40, ..TilePosition::centred(2, 3) });
+
+        let direction = advance(&board, &mut position, Direction::Right, &mut next_direction, 160, false, true);
+        assert_eq!(direction, Direction::Up);
+        assert_eq!(position, TilePosition { offset_y: 120, ..TilePosition::centred(2, 3) });
+    }
+
+    #[test]
+    fn early_turn_into_a_wall_stays_queued() {
+        let board = board();
+        let mut position = TilePosition { offset_x: -200, ..TilePosition::centred(2, 2) };
+        let mut next_direction = Some(Direction::Up);
+        let direction = advance(&board, &mut position, Direction::Right, &mut next_direction, 160, true, true);
+        assert_eq!(direction, Direction::Right);
+        assert_eq!(next_direction, Some(Direction::Up));
+        assert_eq!(position, TilePosition { offset_x: -40, ..TilePosition::centred(2, 2) });
+    }
+
+    #[test]
+    fn reversal_is_instant_between_centres() {
+        let board = board();
+        let mut position = TilePosition { offset_x: 300, ..TilePosition::centred(2, 2) };
+        let mut next_direction = Some(Direction::Left);
+        let direction = advance(&board, &mut position, Direction::Right, &mut next_direction, 160, true, true);
+        assert_eq!(direction, Direction::Left);
+        assert_eq!(position, TilePosition { offset_x: 140, ..TilePosition::centred(2, 2) });
+
+        // Otherwise Ben carries on to the next centre before turning round
+        let mut position = TilePosition { offset_x: 300, ..TilePosition::centred(2, 2) };
+        let mut next_direction = Some(Direction::Left);
+        let direction = advance(&board, &mut position, Direction::Right, &mut next_direction, 160, true, false);
+        assert_eq!(direction, Direction::Right);
+        assert_eq!(next_direction, Some(Direction::Left));
+    }
+}
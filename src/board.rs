@@ -1,5 +1,10 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::hash::Hasher;
+use std::fs;
 use crate::enums::Direction;
+use crate::utils::StableHasher;
+
+const DEFAULT_LEVEL: &str = include_str!("../assets/levels/default.txt");
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum BoardTile {
@@ -11,85 +16,245 @@ pub enum BoardTile {
     GhostGate
 }
 
+impl BoardTile {
+    pub fn from_key(key: char) -> Option<Self> {
+        match key {
+            '_' => Some(BoardTile::Empty),
+            '.' => Some(BoardTile::Dot),
+            '/' => Some(BoardTile::Fruit),
+            '#' => Some(BoardTile::Wall),
+            'o' => Some(BoardTile::PowerUp),
+            '|' => Some(BoardTile::GhostGate),
+            _ => None
+        }
+    }
+
+    pub fn key(&self) -> char {
+        match self {
+            BoardTile::Empty => '_',
+            BoardTile::Dot => '.',
+            BoardTile::Fruit => '/',
+            BoardTile::Wall => '#',
+            BoardTile::PowerUp => 'o',
+            BoardTile::GhostGate => '|'
+        }
+    }
+
+    /// Whether Ben can walk onto the tile. Ghost gates only let ghosts through, so they count as closed.
+    pub fn is_walkable(self) -> bool {
+        self != BoardTile::Wall && self != BoardTile::GhostGate
+    }
+}
+
+/// Spawn points stored alongside the tile matrix. Each anchor names the left tile of a pair, so the actor
+/// spawns on the seam between `(i, j)` and `(i, j + 1)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SpawnAnchor {
+    Ben,
+    GhostHouse,
+    GhostExit
+}
+
+impl SpawnAnchor {
+    fn property(&self) -> &'static str {
+        match self {
+            SpawnAnchor::Ben => "ben_spawn",
+            SpawnAnchor::GhostHouse => "ghost_house",
+            SpawnAnchor::GhostExit => "ghost_exit"
+        }
+    }
+}
+
 pub struct Board {
     matrix: Vec<Vec<BoardTile>>,
+    ben_spawn: (usize, usize),
+    ghost_house: (usize, usize),
+    ghost_exit: (usize, usize),
+    properties: BTreeMap<String, String>,
     cell_size: f32,
     offset: f32,
 }
 
 impl Board {
     pub fn new(cell_size: f32, offset: f32) -> Self {
-        let key_map = HashMap::from([
-            ('_', BoardTile::Empty),
-            ('.', BoardTile::Dot),
-            ('/', BoardTile::Fruit),
-            ('#', BoardTile::Wall),
-            ('o', BoardTile::PowerUp),
-            ('|', BoardTile::GhostGate),
-        ]);
-
-        let mock_matrix = [
-            ['#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#'],
-            ['#', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '#', '#', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '#'],
-            ['#', '.', '#', '#', '#', '#', '.', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '.', '#', '#', '#', '#', '.', '#'],
-            ['#', 'o', '#', '_', '_', '#', '.', '#', '_', '_', '_', '#', '.', '#', '#', '.', '#', '_', '_', '_', '#', '.', '#', '_', '_', '#', 'o', '#'],
-            ['#', '.', '#', '#', '#', '#', '.', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '.', '#', '#', '#', '#', '.', '#'],
-            ['#', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '#'],
-            ['#', '.', '#', '#', '#', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '#', '#', '#', '.', '#'],
-            ['#', '.', '#', '#', '#', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '#', '#', '#', '.', '#'],
-            ['#', '.', '.', '.', '.', '.', '.', '#', '#', '.', '.', '.', '.', '#', '#', '.', '.', '.', '.', '#', '#', '.', '.', '.', '.', '.', '.', '#'],
-            ['#', '#', '#', '#', '#', '#', '.', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '.', '#', '#', '#', '#', '#', '#'],
-            ['_', '_', '_', '_', '_', '#', '.', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '.', '#', '_', '_', '_', '_', '_'],
-            ['_', '_', '_', '_', '_', '#', '.', '#', '#', '.', '.', '.', '.', '_', '_', '.', '.', '.', '.', '#', '#', '.', '#', '_', '_', '_', '_', '_'],
-            ['_', '_', '_', '_', '_', '#', '.', '#', '#', '.', '#', '#', '#', '|', '|', '#', '#', '#', '.', '#', '#', '.', '#', '_', '_', '_', '_', '_'],
-            ['#', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '_', '_', '_', '_', '_', '_', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '#'],
-            ['.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '#', '_', '_', '_', '_', '_', '_', '#', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.'],
-            ['#', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '_', '_', '_', '_', '_', '_', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '#'],
-            ['_', '_', '_', '_', '_', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '_', '_', '_', '_', '_'],
-            ['_', '_', '_', '_', '_', '#', '.', '#', '#', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '#', '#', '.', '#', '_', '_', '_', '_', '_'],
-            ['_', '_', '_', '_', '_', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '_', '_', '_', '_', '_'],
-            ['#', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '#'],
-            ['#', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '#', '#', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '#'],
-            ['#', '.', '#', '#', '#', '#', '.', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '.', '#', '#', '#', '#', '.', '#'],
-            ['#', '.', '#', '#', '#', '#', '.', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '.', '#', '#', '#', '#', '.', '#'],
-            ['#', 'o', '.', '.', '#', '#', '.', '.', '.', '.', '.', '.', '.', '_', '_', '.', '.', '.', '.', '.', '.', '.', '#', '#', '.', '.', 'o', '#'],
-            ['#', '#', '#', '.', '#', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '#', '.', '#', '#', '#'],
-            ['#', '#', '#', '.', '#', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '#', '.', '#', '#', '#'],
-            ['#', '.', '.', '.', '.', '.', '.', '#', '#', '.', '.', '.', '.', '#', '#', '.', '.', '.', '.', '#', '#', '.', '.', '.', '.', '.', '.', '#'],
-            ['#', '.', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '.', '#'],
-            ['#', '.', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '.', '#', '#', '.', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '.', '#'],
-            ['#', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '#'],
-            ['#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#', '#'],
-        ];
+        Self::from_level_str(DEFAULT_LEVEL, cell_size, offset).unwrap()
+    }
 
+    pub fn from_file(path: &str, cell_size: f32, offset: f32) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| format!("Failed to read level {}: {}", path, err))?;
+        Self::from_level_str(&contents, cell_size, offset)
+    }
+
+    /// Parses the text level format: `;` starts a comment line, `@key value...` lines hold the spawn anchors
+    /// and any per-level properties, and every other non-empty line is a row of tile keys.
+    pub fn from_level_str(contents: &str, cell_size: f32, offset: f32) -> Result<Self, String> {
         let mut matrix: Vec<Vec<BoardTile>> = Vec::new();
-        for row in mock_matrix.iter() {
-            let mut curr_row = Vec::new();
-            for val in row.iter() {
-                curr_row.push(*key_map.get(val).unwrap());
+        let mut properties = BTreeMap::new();
+        for (line_number, line) in contents.lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(property) = line.strip_prefix('@') {
+                let mut parts = property.splitn(2, char::is_whitespace);
+                let key = parts.next().unwrap_or("").to_string();
+                let value = parts.next().unwrap_or("").trim().to_string();
+                properties.insert(key, value);
+                continue;
+            }
+
+            let mut row = Vec::new();
+            for key in line.chars() {
+                match BoardTile::from_key(key) {
+                    Some(tile) => row.push(tile),
+                    None => return Err(format!("Unknown tile '{}' on line {}", key, line_number + 1))
+                }
+            }
+            if !matrix.is_empty() && row.len() != matrix[0].len() {
+                return Err(format!("Row on line {} has {} tiles, expected {}", line_number + 1, row.len(), matrix[0].len()));
             }
-            matrix.push(curr_row);
+            matrix.push(row);
         }
 
-        Self {
+        if matrix.is_empty() {
+            return Err("Level contains no rows".to_string());
+        }
+
+        let mut board = Self {
             matrix,
+            ben_spawn: (0, 0),
+            ghost_house: (0, 0),
+            ghost_exit: (0, 0),
+            properties,
             cell_size,
             offset
+        };
+
+        for anchor in [SpawnAnchor::Ben, SpawnAnchor::GhostHouse, SpawnAnchor::GhostExit] {
+            let value = board.properties.remove(anchor.property())
+                .ok_or_else(|| format!("Missing @{} property", anchor.property()))?;
+            let indeces: Vec<usize> = value.split_whitespace().filter_map(|part| part.parse().ok()).collect();
+            if indeces.len() != 2 || !board.indeces_valid(indeces[0], indeces[1]) {
+                return Err(format!("Invalid @{} property: {}", anchor.property(), value));
+            }
+            board.set_anchor(anchor, (indeces[0], indeces[1]));
+        }
+
+        Ok(board)
+    }
+
+    pub fn to_level_string(&self) -> String {
+        let mut contents = String::from("; Benja-Man level\n");
+        for anchor in [SpawnAnchor::Ben, SpawnAnchor::GhostHouse, SpawnAnchor::GhostExit] {
+            let (i, j) = self.anchor(anchor);
+            contents.push_str(&format!("@{} {} {}\n", anchor.property(), i, j));
+        }
+        for (key, value) in self.properties.iter() {
+            contents.push_str(&format!("@{} {}\n", key, value));
+        }
+        for row in self.matrix.iter() {
+            contents.extend(row.iter().map(|tile| tile.key()));
+            contents.push('\n');
+        }
+        contents
+    }
+
+    /// Fingerprint of the level, so that two copies of the game can tell whether they are playing the same one: FNV-1a
+    /// over the bytes of `to_level_string`, which stays the same across builds.
+    pub fn hash(&self) -> u64 {
+        let mut hasher = StableHasher::default();
+        hasher.write(self.to_level_string().as_bytes());
+        hasher.finish()
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_level_string()).map_err(|err| format!("Failed to write level {}: {}", path, err))
+    }
+
+    pub fn set(&mut self, i: usize, j: usize, tile: BoardTile) {
+        self.validate_indeces(i, j);
+        self.matrix[i][j] = tile;
+    }
+
+    pub fn anchor(&self, anchor: SpawnAnchor) -> (usize, usize) {
+        match anchor {
+            SpawnAnchor::Ben => self.ben_spawn,
+            SpawnAnchor::GhostHouse => self.ghost_house,
+            SpawnAnchor::GhostExit => self.ghost_exit
         }
     }
 
+    pub fn set_anchor(&mut self, anchor: SpawnAnchor, indeces: (usize, usize)) {
+        self.validate_indeces(indeces.0, indeces.1);
+        match anchor {
+            SpawnAnchor::Ben => self.ben_spawn = indeces,
+            SpawnAnchor::GhostHouse => self.ghost_house = indeces,
+            SpawnAnchor::GhostExit => self.ghost_exit = indeces
+        }
+    }
+
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(|value| value.as_str())
+    }
+
     pub fn try_get(&self, i: usize, j: usize) -> Option<BoardTile> {
         if self.indeces_valid(i, j) {
             Some(self.matrix[i][j])
-        } else if j == self.width() {
-            Some(self.matrix[i][0])
-        } else if j == 0 {
-            Some(self.matrix[i][self.width() - 1])
         } else {
             None
         }
     }
 
+    /// The board is a torus: indeces past one edge continue from the opposite edge. Only tunnel rows and columns
+    /// are open on both edges, so in practice this is how tunnels connect.
+    pub fn wrap_indeces(&self, i: isize, j: isize) -> (usize, usize) {
+        (i.rem_euclid(self.height() as isize) as usize, j.rem_euclid(self.width() as isize) as usize)
+    }
+
+    pub fn neighbour(&self, i: usize, j: usize, direction: Direction) -> (usize, usize) {
+        let (i, j) = (i as isize, j as isize);
+        match direction {
+            Direction::Up => self.wrap_indeces(i - 1, j),
+            Direction::Right => self.wrap_indeces(i, j + 1),
+            Direction::Down => self.wrap_indeces(i + 1, j),
+            Direction::Left => self.wrap_indeces(i, j - 1)
+        }
+    }
+
+    pub fn is_tunnel_row(&self, i: usize) -> bool {
+        self.matrix[i][0].is_walkable() && self.matrix[i][self.width() - 1].is_walkable()
+    }
+
+    pub fn is_tunnel_column(&self, j: usize) -> bool {
+        self.matrix[0][j].is_walkable() && self.matrix[self.height() - 1][j].is_walkable()
+    }
+
+    /// Tunnel tiles are the stretch of a tunnel row (or column) that runs from the edge of the board up to the
+    /// first tile where the corridor opens up sideways.
+    pub fn is_tunnel_tile(&self, i: usize, j: usize) -> bool {
+        let is_open = |i: usize, j: usize| self.matrix[i][j].is_walkable();
+        let in_segment = |len: usize, index: usize, is_corridor: &dyn Fn(usize) -> bool| {
+            let from_start = (0..=index).all(|k| is_corridor(k));
+            let from_end = (index..len).all(|k| is_corridor(k));
+            from_start || from_end
+        };
+
+        let row_corridor = |k: usize| {
+            let (up, _) = self.neighbour(i, k, Direction::Up);
+            let (down, _) = self.neighbour(i, k, Direction::Down);
+            is_open(i, k) && !is_open(up, k) && !is_open(down, k)
+        };
+        let column_corridor = |k: usize| {
+            let (_, left) = self.neighbour(k, j, Direction::Left);
+            let (_, right) = self.neighbour(k, j, Direction::Right);
+            is_open(k, j) && !is_open(k, left) && !is_open(k, right)
+        };
+
+        (self.is_tunnel_row(i) && in_segment(self.width(), j, &row_corridor))
+            || (self.is_tunnel_column(j) && in_segment(self.height(), i, &column_corridor))
+    }
+
     pub fn width(&self) -> usize {
         self.matrix[0].len()
     }
@@ -113,43 +278,19 @@ impl Board {
         (x, y)
     }
 
+    /// Exact tile centres map to their own tile. Any other point maps to the tile it is heading into when moving up or
+    /// left from a centre, and to the tile it is leaving when moving down or right.
     pub fn coordinates_to_indeces(&self, x: f32, y: f32) -> (usize, usize) {
-        let i = ((self.offset - y) / self.cell_size + self.height() as f32 - 1.) as usize;
-        let j = ((x - self.offset) / self.cell_size) as usize;
-        (i, j)
+        let (x, y) = self.wrap_coordinates(x, y);
+        let i = (self.height() as f32 - 1. - (y - self.offset) / self.cell_size).floor() as isize;
+        let j = ((x - self.offset) / self.cell_size).floor() as isize;
+        self.wrap_indeces(i, j)
     }
 
-    pub fn get_coordinates(&self, x: f32, y: f32, direction: Direction, speed: f32) -> (f32, f32) {
-        match direction {
-            Direction::Up => {
-                let mut target_y = y + speed;
-                if target_y > self.height() as f32 * self.cell_size() {
-                    target_y = 0.;
-                }
-                (x, target_y)
-            },
-            Direction::Right => {
-                let mut target_x = x + speed;
-                if target_x > self.width() as f32 * self.cell_size() {
-                    target_x = 0.;
-                }
-                (target_x, y)
-            },
-            Direction::Down => {
-                let mut target_y = y - speed;
-                if target_y < 0. {
-                    target_y = self.width() as f32 * self.cell_size();
-                }
-                (x, target_y)
-            },
-            Direction::Left => {
-                let mut target_x = x - speed;
-                if target_x < 0. {
-                    target_x = self.width() as f32 * self.cell_size();
-                }
-                (target_x, y)
-            }
-        }
+    pub fn wrap_coordinates(&self, x: f32, y: f32) -> (f32, f32) {
+        let width = self.width() as f32 * self.cell_size;
+        let height = self.height() as f32 * self.cell_size;
+        (x.rem_euclid(width), y.rem_euclid(height))
     }
 
     fn validate_indeces(&self, i: usize, j: usize) {
@@ -162,3 +303,77 @@ impl Board {
         i < self.matrix.len() && j < self.matrix[0].len()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants;
+
+    // Row 3 and column 3 are tunnels, each a single tile deep on either edge
+    const CROSS: &str = "@ben_spawn 1 2\n@ghost_house 5 2\n@ghost_exit 1 2\n###_###\n#_____#\n#_#_#_#\n___#___\n#_#_#_#\n#_____#\n###_###\n";
+
+    fn cross() -> Board {
+        Board::from_level_str(CROSS, constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET).unwrap()
+    }
+
+    #[test]
+    fn default_level_hash_is_pinned() {
+        // Compared between peers and stored in saves, so it must never change for the same level
+        assert_eq!(Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET).hash(), 0x5836a92505b87a78);
+    }
+
+    #[test]
+    fn tunnels_are_open_on_both_edges() {
+        let board = cross();
+        let rows: Vec<usize> = (0..board.height()).filter(|i| board.is_tunnel_row(*i)).collect();
+        let columns: Vec<usize> = (0..board.width()).filter(|j| board.is_tunnel_column(*j)).collect();
+        assert_eq!(rows, vec![3]);
+        assert_eq!(columns, vec![3]);
+    }
+
+    #[test]
+    fn tunnel_tiles_stop_where_the_corridor_opens_up() {
+        let board = cross();
+        let mut tunnel_tiles = Vec::new();
+        for i in 0..board.height() {
+            for j in 0..board.width() {
+                if board.is_tunnel_tile(i, j) {
+                    tunnel_tiles.push((i, j));
+                }
+            }
+        }
+        assert_eq!(tunnel_tiles, vec![(0, 3), (3, 0), (3, 6), (6, 3)]);
+    }
+
+    #[test]
+    fn indeces_wrap_through_the_edges() {
+        let board = cross();
+        assert_eq!(board.wrap_indeces(-1, 7), (6, 0));
+        assert_eq!(board.wrap_indeces(7, -1), (0, 6));
+        assert_eq!(board.wrap_indeces(-8, 15), (6, 1));
+        assert_eq!(board.neighbour(3, 0, Direction::Left), (3, 6));
+        assert_eq!(board.neighbour(3, 6, Direction::Right), (3, 0));
+        assert_eq!(board.neighbour(0, 3, Direction::Up), (6, 3));
+        assert_eq!(board.neighbour(6, 3, Direction::Down), (0, 3));
+    }
+
+    #[test]
+    fn coordinates_wrap_through_the_edges() {
+        let board = cross();
+        let width = board.width() as f32 * board.cell_size();
+        let height = board.height() as f32 * board.cell_size();
+        let (left_x, left_y) = board.indeces_to_coordinates(3, 0);
+        let (right_x, right_y) = board.indeces_to_coordinates(3, 6);
+        assert_eq!(board.coordinates_to_indeces(left_x, left_y), (3, 0));
+        assert_eq!(board.coordinates_to_indeces(right_x + width, right_y), (3, 6));
+        assert_eq!(board.coordinates_to_indeces(left_x + width, left_y), (3, 0));
+        assert_eq!(board.coordinates_to_indeces(right_x - width, right_y), (3, 6));
+        // Just left of the first tile's centre is already heading into the last one
+        assert_eq!(board.coordinates_to_indeces(left_x - 1., left_y), (3, 6));
+
+        let (top_x, top_y) = board.indeces_to_coordinates(0, 3);
+        assert_eq!(board.coordinates_to_indeces(top_x, top_y + height), (0, 3));
+        assert_eq!(board.coordinates_to_indeces(top_x, top_y - height), (0, 3));
+        assert_eq!(board.coordinates_to_indeces(top_x, top_y + 1.), (6, 3));
+    }
+}
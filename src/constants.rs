@@ -2,9 +2,37 @@ use crate::enums::Direction;
 
 pub const BOARD_CELL_SIZE: f32 = 32.;
 pub const BOARD_OFFSET: f32 = 16.;
-pub const GHOST_SPEED_DEFAULT: f32 = 2.;
-pub const GHOST_SPEED_RESPAWNING: f32 = 16.;
-pub const BEN_SPEED_DEFAULT: f32 = 4.;
+// Heights of the HUD bars above and below the maze
+pub const HUD_TOP_HEIGHT: f32 = 56.;
+pub const HUD_BOTTOM_HEIGHT: f32 = 44.;
+// The tallest the window opens at, so it fits on laptop screens
+pub const WINDOW_MAX_HEIGHT: f32 = 800.;
+// Speeds are percentages of `movement::FULL_SPEED_UNITS`
+pub const GHOST_SPEED_DEFAULT: f32 = 75.;
+pub const GHOST_SPEED_RESPAWNING: f32 = 300.;
+pub const GHOST_SPEED_TUNNEL: f32 = 40.;
+pub const GHOST_REVIVE_SECONDS: f32 = 1.;
+pub const GHOST_REVIVE_BLINK_SECONDS: f32 = 0.125;
+pub const GHOST_SCARE_SECONDS: f32 = 10.;
+// Scared ghosts flash for this long before turning back
+pub const GHOST_FLASH_SECONDS: f32 = 2.;
+pub const BEN_SPEED_DEFAULT: f32 = 80.;
+pub const BEN_LIVES: u8 = 3;
 pub const BEN_DIRECTION_DEFAULT: Direction = Direction::Right;
+// In tiles: how close Ben and a ghost have to come to touch
+pub const COLLISION_RADIUS_TILES: f32 = 0.5;
+
+pub const ELROY_DOTS_REMAINING: [usize; 2] = [20, 10];
+pub const ELROY_SPEEDS: [f32; 2] = [80., 85.];
+pub const GHOST_RELEASE_TIMEOUT_SECONDS: f32 = 4.;
+pub const GHOST_DOT_LIMITS: [u32; 4] = [0, 0, 30, 60];
+pub const GHOST_GLOBAL_DOT_LIMITS: [u32; 4] = [0, 7, 17, 32];
 pub const BACKGROUND_MUSIC_DURATION_SECONDS: f32 = 215.;
 pub const MAX_FRAMERATE: f64 = 60.;
+// How far back rewinding can go
+pub const REWIND_SECONDS: f32 = 10.;
+pub const VERSUS_BEST_OF: u32 = 3;
+// What the ghost player earns for each of Ben's lives
+pub const VERSUS_CATCH_POINTS: u64 = 1000;
+// Reward the training environment takes off for each life lost
+pub const ENV_DEATH_PENALTY: f32 = 1000.;
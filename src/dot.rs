@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use crate::movement::TilePosition;
 
 pub struct DotMaterial {
     pub handle: Handle<ColorMaterial>
@@ -9,6 +10,7 @@ pub struct Dot;
 #[derive(Bundle)]
 pub struct DotBundle {
     pub dot: Dot,
+    pub position: TilePosition,
 
     #[bundle]
     pub sprite_bundle: SpriteBundle
@@ -18,6 +20,7 @@ impl Default for DotBundle {
     fn default() -> Self {
         Self {
             dot: Dot,
+            position: TilePosition::default(),
             sprite_bundle: SpriteBundle::default()
         }
     }
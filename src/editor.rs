@@ -0,0 +1,428 @@
+use std::collections::HashSet;
+use bevy::prelude::*;
+use crate::ben::Ben;
+use crate::board::{Board, BoardTile, SpawnAnchor};
+use crate::dot::{Dot, DotMaterial};
+use crate::enums::GameState;
+use crate::ghost::Ghost;
+use crate::maze::{self, MazeTheme};
+use crate::misc;
+use crate::power_up::{PowerUp, PowerUpSpriteSheet};
+use crate::utils;
+use crate::validate;
+use crate::view::BoardView;
+use crate::wall::{Wall, WallMaterials};
+
+pub const DEFAULT_LEVEL_PATH: &str = "assets/levels/custom.txt";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Brush {
+    Tile(BoardTile),
+    Anchor(SpawnAnchor)
+}
+
+impl Brush {
+    fn name(&self) -> &'static str {
+        match self {
+            Brush::Tile(BoardTile::Wall) => "Wall",
+            Brush::Tile(BoardTile::Dot) => "Dot",
+            Brush::Tile(BoardTile::PowerUp) => "Power-up",
+            Brush::Tile(BoardTile::Fruit) => "Fruit",
+            Brush::Tile(BoardTile::GhostGate) => "Ghost gate",
+            Brush::Tile(BoardTile::Empty) => "Empty",
+            Brush::Anchor(SpawnAnchor::Ben) => "Ben spawn",
+            Brush::Anchor(SpawnAnchor::GhostHouse) => "Ghost house",
+            Brush::Anchor(SpawnAnchor::GhostExit) => "Ghost exit"
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditorChange {
+    Tile { i: usize, j: usize, from: BoardTile, to: BoardTile },
+    Anchor { anchor: SpawnAnchor, from: (usize, usize), to: (usize, usize) }
+}
+
+impl EditorChange {
+    fn apply(&self, board: &mut Board) {
+        match *self {
+            EditorChange::Tile { i, j, to, .. } => board.set(i, j, to),
+            EditorChange::Anchor { anchor, to, .. } => board.set_anchor(anchor, to)
+        }
+    }
+
+    fn revert(&self, board: &mut Board) {
+        match *self {
+            EditorChange::Tile { i, j, from, .. } => board.set(i, j, from),
+            EditorChange::Anchor { anchor, from, .. } => board.set_anchor(anchor, from)
+        }
+    }
+}
+
+/// Undo and redo stacks. Every mouse stroke is recorded as a single entry so that one undo reverts the whole drag.
+pub struct EditorHistory {
+    undo: Vec<Vec<EditorChange>>,
+    redo: Vec<Vec<EditorChange>>
+}
+
+impl EditorHistory {
+    pub fn new() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new()
+        }
+    }
+
+    pub fn push(&mut self, stroke: Vec<EditorChange>) {
+        if stroke.is_empty() {
+            return;
+        }
+
+        self.undo.push(stroke);
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self, board: &mut Board) -> bool {
+        if let Some(stroke) = self.undo.pop() {
+            for change in stroke.iter().rev() {
+                change.revert(board);
+            }
+            self.redo.push(stroke);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn redo(&mut self, board: &mut Board) -> bool {
+        if let Some(stroke) = self.redo.pop() {
+            for change in stroke.iter() {
+                change.apply(board);
+            }
+            self.undo.push(stroke);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+pub struct EditorSession {
+    pub brush: Brush,
+    pub history: EditorHistory,
+    pub stroke: Vec<EditorChange>,
+    pub level_path: String,
+    pub playtesting: bool,
+    pub needs_redraw: bool,
+    pub unsaved: bool,
+    pub status: String
+}
+
+impl Default for EditorSession {
+    fn default() -> Self {
+        Self {
+            brush: Brush::Tile(BoardTile::Wall),
+            history: EditorHistory::new(),
+            stroke: Vec::new(),
+            level_path: DEFAULT_LEVEL_PATH.to_string(),
+            playtesting: false,
+            needs_redraw: true,
+            unsaved: false,
+            status: String::new()
+        }
+    }
+}
+
+pub struct EditorMaterials {
+    pub fruit: Handle<ColorMaterial>,
+    pub ben_spawn: Handle<ColorMaterial>,
+    pub ghost_house: Handle<ColorMaterial>,
+    pub ghost_exit: Handle<ColorMaterial>
+}
+
+/// Sprites that only exist while the editor is open, such as fruit and spawn anchor markers.
+pub struct EditorMarker;
+
+pub struct EditorStatusText;
+
+pub fn enter_editor_system(
+    mut commands: Commands,
+    mut session: ResMut<EditorSession>,
+    mut query_set: QuerySet<(
+        Query<&mut Visible, Or<(With<Ben>, With<Ghost>)>>,
+        Query<Entity, Or<(With<misc::StartMessage>, With<misc::EndMessage>, With<misc::RestartMessage>)>>
+    )>,
+    font_material: Res<misc::FontMaterial>,
+    board: Res<Board>
+) {
+    for mut visible in query_set.q0_mut().iter_mut() {
+        visible.is_visible = false;
+    }
+    for message_entity in query_set.q1().iter() {
+        commands.entity(message_entity).despawn();
+    }
+
+    session.playtesting = false;
+    session.needs_redraw = true;
+
+    let text_style = TextStyle {
+        font: font_material.handle.clone(),
+        font_size: 24.,
+        color: Color::WHITE
+    };
+    let text_alignment = TextAlignment {
+        vertical: VerticalAlign::Top,
+        horizontal: HorizontalAlign::Center
+    };
+    commands.spawn_bundle(Text2dBundle {
+        text: Text::with_section("", text_style, text_alignment),
+        transform: Transform {
+            translation: Vec3::new(board.width() as f32 * board.cell_size() / 2., board.height() as f32 * board.cell_size(), 100.),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .insert(EditorStatusText);
+}
+
+pub fn exit_editor_system(
+    mut commands: Commands,
+    mut query_set: QuerySet<(
+        Query<&mut Visible, Or<(With<Ben>, With<Ghost>)>>,
+        Query<Entity, Or<(With<EditorMarker>, With<EditorStatusText>)>>
+    )>
+) {
+    for mut visible in query_set.q0_mut().iter_mut() {
+        visible.is_visible = true;
+    }
+    for marker_entity in query_set.q1().iter() {
+        commands.entity(marker_entity).despawn();
+    }
+}
+
+pub fn editor_brush_system(
+    mut session: ResMut<EditorSession>,
+    keys: Res<Input<KeyCode>>
+) {
+    let brushes = [
+        (KeyCode::Key1, Brush::Tile(BoardTile::Wall)),
+        (KeyCode::Key2, Brush::Tile(BoardTile::Dot)),
+        (KeyCode::Key3, Brush::Tile(BoardTile::PowerUp)),
+        (KeyCode::Key4, Brush::Tile(BoardTile::Fruit)),
+        (KeyCode::Key5, Brush::Tile(BoardTile::GhostGate)),
+        (KeyCode::Key6, Brush::Tile(BoardTile::Empty)),
+        (KeyCode::Key7, Brush::Anchor(SpawnAnchor::Ben)),
+        (KeyCode::Key8, Brush::Anchor(SpawnAnchor::GhostHouse)),
+        (KeyCode::Key9, Brush::Anchor(SpawnAnchor::GhostExit)),
+    ];
+
+    for (key, brush) in brushes.iter() {
+        if keys.just_pressed(*key) {
+            session.brush = *brush;
+        }
+    }
+}
+
+pub fn editor_paint_system(
+    mut session: ResMut<EditorSession>,
+    mut board: ResMut<Board>,
+    mouse: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    board_view: Res<BoardView>
+) {
+    // A stroke ends when the button is released, at which point it becomes a single undo step
+    if !mouse.pressed(MouseButton::Left) && !mouse.pressed(MouseButton::Right) {
+        if !session.stroke.is_empty() {
+            let stroke = std::mem::take(&mut session.stroke);
+            session.history.push(stroke);
+        }
+        return;
+    }
+
+    let cursor_position = match windows.get_primary().and_then(|window| window.cursor_position()) {
+        Some(position) => position,
+        None => return
+    };
+    let cursor_position = board_view.to_board(cursor_position);
+    let (i, j) = match utils::pick_indeces(cursor_position.x, cursor_position.y, &board) {
+        Some(indeces) => indeces,
+        None => return
+    };
+
+    // The right mouse button always erases
+    let brush = if mouse.pressed(MouseButton::Right) {
+        Brush::Tile(BoardTile::Empty)
+    } else {
+        session.brush
+    };
+
+    let change = match brush {
+        Brush::Tile(tile) => {
+            let current = board.try_get(i, j).unwrap();
+            if current == tile {
+                return;
+            }
+            EditorChange::Tile { i, j, from: current, to: tile }
+        },
+        Brush::Anchor(anchor) => {
+            let current = board.anchor(anchor);
+            if current == (i, j) {
+                return;
+            }
+            EditorChange::Anchor { anchor, from: current, to: (i, j) }
+        }
+    };
+
+    change.apply(&mut board);
+    session.stroke.push(change);
+    session.needs_redraw = true;
+    session.unsaved = true;
+}
+
+pub fn editor_history_system(
+    mut session: ResMut<EditorSession>,
+    mut board: ResMut<Board>,
+    keys: Res<Input<KeyCode>>
+) {
+    let control = keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl);
+    let shift = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+    if !control || !session.stroke.is_empty() {
+        return;
+    }
+
+    let changed = if keys.just_pressed(KeyCode::Z) && !shift {
+        session.history.undo(&mut board)
+    } else if keys.just_pressed(KeyCode::Y) || (keys.just_pressed(KeyCode::Z) && shift) {
+        session.history.redo(&mut board)
+    } else {
+        false
+    };
+
+    if changed {
+        session.needs_redraw = true;
+        session.unsaved = true;
+    }
+}
+
+pub fn editor_save_system(
+    mut session: ResMut<EditorSession>,
+    board: Res<Board>,
+    keys: Res<Input<KeyCode>>
+) {
+    let control = keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl);
+    if !control || !keys.just_pressed(KeyCode::S) {
+        return;
+    }
+
+    match board.save(&session.level_path) {
+        Ok(()) => {
+            let report = validate::validate(&board);
+            session.unsaved = false;
+            session.status = if report.is_valid() {
+                format!("Saved {}", session.level_path)
+            } else {
+                format!("Saved {} with {} problem(s), run `validate` for details", session.level_path, report.problem_count())
+            };
+        },
+        Err(err) => session.status = err
+    }
+}
+
+pub fn editor_playtest_system(
+    mut session: ResMut<EditorSession>,
+    mut game_state: ResMut<State<GameState>>,
+    keys: Res<Input<KeyCode>>
+) {
+    if keys.just_pressed(KeyCode::P) && session.stroke.is_empty() {
+        session.playtesting = true;
+        game_state.set(GameState::Reset).unwrap();
+    }
+}
+
+pub fn playtest_return_system(
+    mut game_state: ResMut<State<GameState>>,
+    session: Res<EditorSession>,
+    keys: Res<Input<KeyCode>>
+) {
+    // Leaving the playtest wins over a death or a cleared maze on the same frame, since none of it matters anymore
+    if session.playtesting && keys.just_pressed(KeyCode::P) {
+        let _ = game_state.overwrite_set(GameState::Editor);
+    }
+}
+
+pub fn editor_redraw_system(
+    mut commands: Commands,
+    mut session: ResMut<EditorSession>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut maze_theme: ResMut<MazeTheme>,
+    query: Query<Entity, Or<(With<Wall>, With<Dot>, With<PowerUp>, With<EditorMarker>)>>,
+    board: Res<Board>,
+    wall_materials: Res<WallMaterials>,
+    dot_material: Res<DotMaterial>,
+    power_up_sprite_sheet: Res<PowerUpSpriteSheet>,
+    editor_materials: Res<EditorMaterials>
+) {
+    if !session.needs_redraw {
+        return;
+    }
+    session.needs_redraw = false;
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    *maze_theme = MazeTheme::from_board(&board);
+    maze::update_wall_materials(&board, &maze_theme, &wall_materials, &mut materials, &mut textures);
+    utils::init_walls(&mut commands, &board, &wall_materials);
+    utils::init_dots_and_power_ups(&mut commands, &board, dot_material.handle.clone(), power_up_sprite_sheet.atlas.clone(), &HashSet::new());
+
+    // Fruit has no in-game sprite of its own, so it only gets a marker in the editor
+    for i in 0..board.height() {
+        for j in 0..board.width() {
+            if board.try_get(i, j).unwrap() != BoardTile::Fruit {
+                continue;
+            }
+
+            let (x, y) = board.indeces_to_coordinates(i, j);
+            spawn_marker(&mut commands, editor_materials.fruit.clone(), x, y, board.cell_size() / 2.);
+        }
+    }
+
+    for (anchor, material) in [
+        (SpawnAnchor::Ben, editor_materials.ben_spawn.clone()),
+        (SpawnAnchor::GhostHouse, editor_materials.ghost_house.clone()),
+        (SpawnAnchor::GhostExit, editor_materials.ghost_exit.clone()),
+    ] {
+        let (x, y) = utils::get_anchor_coordinates(&board, anchor);
+        spawn_marker(&mut commands, material, x, y, board.cell_size());
+    }
+}
+
+pub fn editor_status_system(
+    mut query: Query<&mut Text, With<EditorStatusText>>,
+    session: Res<EditorSession>
+) {
+    // The status text is spawned through commands when the editor opens, so it may not exist for the first frame
+    if let Ok(mut text) = query.single_mut() {
+        text.sections[0].value = format!(
+            "Brush: {}{}\n1-9 brush  Ctrl+Z/Y undo  Ctrl+S save  P play (Backspace rewinds)\n{}",
+            session.brush.name(),
+            if session.unsaved { " [unsaved]" } else { "" },
+            session.status
+        );
+    }
+}
+
+fn spawn_marker(commands: &mut Commands, material: Handle<ColorMaterial>, x: f32, y: f32, size: f32) {
+    commands.spawn_bundle(SpriteBundle {
+        material,
+        sprite: Sprite::new(Vec2::new(size, size)),
+        transform: Transform {
+            translation: Vec3::new(x, y, 3.),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .insert(EditorMarker);
+}
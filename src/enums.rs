@@ -13,9 +13,19 @@ pub enum Label {
     BenControllerSystem,
     BenMovementSystem,
     BenGhostCollisionSystem,
+    RecordPositionsSystem,
+    GhostMovementSystem,
+    GhostReleaseSystem,
+    WinSystem,
+    GhostRespawnSystem,
     RestartGameSystem,
     WaitForRestartSystem,
-    ThrottleFramerateSystem
+    ThrottleFramerateSystem,
+    SequenceSystem,
+    OnlineSystem,
+    OnlineRenderSystem,
+    RewindSystem,
+    NextLevelSystem
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -23,11 +33,17 @@ pub enum GameState {
     Wait,
     Default,
     End,
-    Reset
+    Reset,
+    Editor,
+    Settings,
+    Sequence,
+    Online,
+    Rewind,
+    NextLevel
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum CollisionType {
-    Approximate,
-    Exact
+    // Radius in units
+    Swept(i32)
 }
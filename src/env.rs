@@ -0,0 +1,357 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, Write};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use crate::board::{Board, BoardTile};
+use crate::constants;
+use crate::enums::Direction;
+use crate::ghost::{AttackState, ReleaseState};
+use crate::sim::{self, Sim, SimConfig, SimEvent};
+
+/// Names of the observation's channels, in order. Each is a `height` x `width` grid of 0s and 1s.
+pub const CHANNELS: [&str; 14] = [
+    "wall",
+    "ghost_gate",
+    "dot",
+    "power_up",
+    "fruit",
+    "ben",
+    "caleb",
+    "claflin",
+    "harris",
+    "samson",
+    "ghost_scared",
+    "ghost_eyes",
+    "ghost_in_house",
+    "ghost_released"
+];
+const GHOST_CHANNEL: usize = 6;
+
+/// Actions are numbered: 0 keeps going, then up, right, down and left.
+pub const ACTIONS: [Option<Direction>; 5] = [None, Some(Direction::Up), Some(Direction::Right), Some(Direction::Down), Some(Direction::Left)];
+
+#[derive(Clone, Debug)]
+pub struct EnvConfig {
+    /// Sim ticks per step, with the action held throughout.
+    pub frame_skip: u32,
+    /// Most Ben idles after a reset, picked by the seed, so that episodes don't all start alike.
+    pub max_noops: u32,
+    /// Steps after which an episode is cut short.
+    pub max_steps: u64,
+    /// Added to the reward whenever Ben loses a life. The rest of the reward is the points scored, as `PointValues`
+    /// awards them.
+    pub death_penalty: f32
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            frame_skip: 4,
+            max_noops: 30,
+            max_steps: 27_000,
+            death_penalty: -constants::ENV_DEATH_PENALTY
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Observation {
+    pub width: usize,
+    pub height: usize,
+    /// `CHANNELS.len()` x `height` x `width`, row-major.
+    pub grid: Vec<f32>
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Info {
+    pub score: u64,
+    pub lives: u8,
+    pub level: u32,
+    pub tick: u64,
+    pub dots_left: usize,
+    pub ghosts_eaten: u32,
+    /// The episode hit `max_steps` rather than ending with the game.
+    pub truncated: bool
+}
+
+pub struct StepResult {
+    pub observation: Observation,
+    pub reward: f32,
+    pub done: bool,
+    pub info: Info
+}
+
+/// A Gym-style environment over the headless sim, for training agents. No window and no Bevy: a step is a few sim
+/// ticks, so thousands of them run every second.
+pub struct BenjaManEnv {
+    board: Board,
+    sim_config: SimConfig,
+    config: EnvConfig,
+    sim: Sim,
+    steps: u64,
+    ghosts_eaten: u32
+}
+
+impl BenjaManEnv {
+    pub fn new(board: Board, config: EnvConfig) -> Self {
+        Self {
+            sim_config: SimConfig::from_board(&board),
+            sim: Sim::new(&board, 1),
+            board,
+            config,
+            steps: 0,
+            ghosts_eaten: 0
+        }
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.sim = Sim::new(&self.board, 1);
+        self.steps = 0;
+        self.ghosts_eaten = 0;
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let noops = rng.gen_range(0..=self.config.max_noops);
+        for _ in 0..noops {
+            self.sim.step(&self.board, &self.sim_config, &[None]);
+        }
+        self.observe()
+    }
+
+    /// `action` is an index into `ACTIONS`; anything else counts as 0.
+    pub fn step(&mut self, action: usize) -> StepResult {
+        let input = ACTIONS.get(action).copied().flatten();
+        let score = self.sim.bens[0].score;
+        let mut reward = 0.;
+        for _ in 0..self.config.frame_skip {
+            for event in self.sim.step(&self.board, &self.sim_config, &[input]) {
+                match event {
+                    SimEvent::BenCaught { .. } => reward += self.config.death_penalty,
+                    SimEvent::GhostEaten { .. } => self.ghosts_eaten += 1,
+                    _ => { }
+                }
+            }
+            if self.sim.is_over() {
+                break;
+            }
+        }
+        reward += (self.sim.bens[0].score - score) as f32;
+        self.steps += 1;
+
+        let truncated = !self.sim.is_over() && self.steps >= self.config.max_steps;
+        StepResult {
+            observation: self.observe(),
+            reward,
+            done: self.sim.is_over() || truncated,
+            info: Info {
+                score: self.sim.bens[0].score,
+                lives: self.sim.bens[0].lives,
+                level: self.sim.level,
+                tick: self.sim.tick,
+                dots_left: self.sim.remaining(BoardTile::Dot),
+                ghosts_eaten: self.ghosts_eaten,
+                truncated
+            }
+        }
+    }
+
+    pub fn observe(&self) -> Observation {
+        let (width, height) = (self.board.width(), self.board.height());
+        let mut observation = Observation {
+            width,
+            height,
+            grid: vec![0.; CHANNELS.len() * width * height]
+        };
+        let mut set = |channel: usize, (i, j): (usize, usize)| {
+            observation.grid[(channel * height + i) * width + j] = 1.;
+        };
+
+        for i in 0..height {
+            for j in 0..width {
+                match self.board.try_get(i, j) {
+                    Some(BoardTile::Wall) => set(0, (i, j)),
+                    Some(BoardTile::GhostGate) => set(1, (i, j)),
+                    Some(BoardTile::Fruit) => set(4, (i, j)),
+                    _ => { }
+                }
+                match self.sim.pickups[i][j] {
+                    BoardTile::Dot => set(2, (i, j)),
+                    BoardTile::PowerUp => set(3, (i, j)),
+                    _ => { }
+                }
+            }
+        }
+
+        let ben = &self.sim.bens[0];
+        if ben.lives > 0 {
+            set(5, ben.position.tile());
+        }
+        for ghost in self.sim.ghosts.iter() {
+            let tile = ghost.position.tile();
+            set(GHOST_CHANNEL + ghost.release_priority, tile);
+            if ghost.attack_state == AttackState::Scared {
+                set(10, tile);
+            }
+            match ghost.release_state {
+                ReleaseState::Eyes | ReleaseState::Entering => set(11, tile),
+                ReleaseState::Caged | ReleaseState::Releasing | ReleaseState::Reviving => set(12, tile),
+                ReleaseState::Released => set(13, tile)
+            }
+        }
+        observation
+    }
+}
+
+/// The flat JSON objects the protocol reads, e.g. `{"cmd": "step", "action": 2}`. Values are strings or numbers.
+fn parse_command(line: &str) -> Option<BTreeMap<String, String>> {
+    let body = line.trim().strip_prefix('{')?.strip_suffix('}')?;
+    let mut fields = BTreeMap::new();
+    for field in body.split(',').filter(|field| !field.trim().is_empty()) {
+        let (key, value) = field.split_once(':')?;
+        let unquote = |text: &str| text.trim().trim_matches('"').to_string();
+        fields.insert(unquote(key), unquote(value));
+    }
+    Some(fields)
+}
+
+fn observation_json(observation: &Observation) -> String {
+    let mut json = format!("{{\"width\":{},\"height\":{},\"grid\":[", observation.width, observation.height);
+    for (index, value) in observation.grid.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        write!(json, "{}", value).unwrap();
+    }
+    json.push_str("]}");
+    json
+}
+
+fn info_json(info: &Info) -> String {
+    format!(
+        "{{\"score\":{},\"lives\":{},\"level\":{},\"tick\":{},\"dots_left\":{},\"ghosts_eaten\":{},\"truncated\":{}}}",
+        info.score, info.lives, info.level, info.tick, info.dots_left, info.ghosts_eaten, info.truncated
+    )
+}
+
+fn spec_json(env: &BenjaManEnv) -> String {
+    let channels: Vec<String> = CHANNELS.iter().map(|channel| format!("\"{}\"", channel)).collect();
+    format!(
+        "{{\"channels\":[{}],\"height\":{},\"width\":{},\"actions\":{},\"frame_skip\":{},\"ticks_per_second\":{}}}",
+        channels.join(","), env.board.height(), env.board.width(), ACTIONS.len(), env.config.frame_skip, sim::TICKS_PER_SECOND
+    )
+}
+
+/// Serves the `env` subcommand: one JSON command per line on stdin, one JSON reply per line on stdout.
+///
+/// - `{"cmd": "spec"}` describes the observation and actions.
+/// - `{"cmd": "reset", "seed": 7}` starts an episode and replies with its first observation.
+/// - `{"cmd": "step", "action": 2}` replies with `observation`, `reward`, `done` and `info`.
+/// - `{"cmd": "close"}` exits.
+///
+/// Malformed commands get `{"error": "..."}`. Returns the process exit code.
+pub fn run(level_path: Option<&str>, config: EnvConfig) -> i32 {
+    let board = match level_path {
+        Some(path) => match Board::from_file(path, constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET) {
+            Ok(board) => board,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 2;
+            }
+        },
+        None => Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET)
+    };
+
+    let mut env = BenjaManEnv::new(board, config);
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let command = parse_command(&line).unwrap_or_default();
+        let number = |key: &str| command.get(key).and_then(|value| value.parse::<u64>().ok());
+        let reply = match command.get("cmd").map(|cmd| cmd.as_str()) {
+            Some("spec") => spec_json(&env),
+            Some("reset") => format!("{{\"observation\":{}}}", observation_json(&env.reset(number("seed").unwrap_or(0)))),
+            Some("step") => {
+                let result = env.step(number("action").unwrap_or(0) as usize);
+                format!(
+                    "{{\"observation\":{},\"reward\":{},\"done\":{},\"info\":{}}}",
+                    observation_json(&result.observation), result.reward, result.done, info_json(&result.info)
+                )
+            },
+            Some("close") => break,
+            _ => format!("{{\"error\":\"Unknown command: {}\"}}", line.replace('\\', "\\\\").replace('"', "\\\""))
+        };
+        if writeln!(out, "{}", reply).and_then(|_| out.flush()).is_err() {
+            break;
+        }
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env() -> BenjaManEnv {
+        BenjaManEnv::new(Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET), EnvConfig::default())
+    }
+
+    #[test]
+    fn same_seed_and_actions_give_the_same_episode() {
+        let (mut first, mut second) = (env(), env());
+        assert_eq!(first.reset(3), second.reset(3));
+        for step in 0..500 {
+            let action = step / 20 % ACTIONS.len();
+            let (a, b) = (first.step(action), second.step(action));
+            assert_eq!(a.observation, b.observation);
+            assert_eq!(a.reward, b.reward);
+            assert_eq!(a.info, b.info);
+        }
+    }
+
+    #[test]
+    fn reward_is_the_points_scored_less_the_deaths() {
+        let mut env = env();
+        env.reset(0);
+        // Ben keeps moving while he idles after a reset, so he may have eaten something already
+        let mut total = env.sim.bens[0].score as f32;
+        loop {
+            let result = env.step(2);
+            total += result.reward;
+            let deaths = (constants::BEN_LIVES - result.info.lives) as f32;
+            assert_eq!(total, result.info.score as f32 - deaths * constants::ENV_DEATH_PENALTY);
+            if result.done {
+                assert!(result.info.score > 0);
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn observation_marks_ben_and_every_ghost() {
+        let mut env = env();
+        let observation = env.reset(0);
+        for (channel, name) in CHANNELS.iter().enumerate().take(GHOST_CHANNEL + 4).skip(5) {
+            let count = (0..observation.height)
+                .flat_map(|i| (0..observation.width).map(move |j| (i, j)))
+                .filter(|(i, j)| observation.grid[(channel * observation.height + i) * observation.width + j] == 1.)
+                .count();
+            assert_eq!(count, 1, "channel {}", name);
+        }
+    }
+
+    #[test]
+    fn commands_are_parsed() {
+        let command = parse_command(r#"{"cmd": "step", "action": 2}"#).unwrap();
+        assert_eq!(command.get("cmd").map(|cmd| cmd.as_str()), Some("step"));
+        assert_eq!(command.get("action").map(|action| action.as_str()), Some("2"));
+        assert!(parse_command("step 2").is_none());
+    }
+}
@@ -1,5 +1,18 @@
+use bevy::prelude::Entity;
 use crate::enums::Direction;
+use crate::sequence::Cue;
+use crate::movement::TilePosition;
 
-pub struct BenDirectionChangedEvent(pub Direction);
+pub struct BenDirectionChangedEvent(pub Entity, pub Direction);
 
 pub struct PowerUpConsumedEvent;
+
+pub struct DotConsumedEvent;
+
+pub struct CueEvent(pub Cue);
+
+/// Shows `points` at `position`. Sent for anything worth more than a dot: ghosts, power-ups and fruit.
+pub struct ScorePopupEvent {
+    pub points: u64,
+    pub position: TilePosition
+}
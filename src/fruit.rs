@@ -0,0 +1,67 @@
+use bevy::prelude::*;
+use crate::board::Board;
+use crate::movement::TilePosition;
+
+/// The bonus item of a level, in arcade order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fruit {
+    Cherry,
+    Strawberry,
+    Orange,
+    Apple,
+    Melon,
+    Flagship,
+    Bell,
+    Key
+}
+
+impl Fruit {
+    /// Levels start at 1. From level 13 on it's keys all the way down.
+    pub fn for_level(level: u32) -> Self {
+        match level {
+            0 | 1 => Fruit::Cherry,
+            2 => Fruit::Strawberry,
+            3 | 4 => Fruit::Orange,
+            5 | 6 => Fruit::Apple,
+            7 | 8 => Fruit::Melon,
+            9 | 10 => Fruit::Flagship,
+            11 | 12 => Fruit::Bell,
+            _ => Fruit::Key
+        }
+    }
+
+    pub fn image_path(&self) -> &'static str {
+        match self {
+            Fruit::Cherry => "fruit/cherry.png",
+            Fruit::Strawberry => "fruit/strawberry.png",
+            Fruit::Orange => "fruit/orange.png",
+            Fruit::Apple => "fruit/apple.png",
+            Fruit::Melon => "fruit/melon.png",
+            Fruit::Flagship => "fruit/flagship.png",
+            Fruit::Bell => "fruit/bell.png",
+            Fruit::Key => "fruit/key.png"
+        }
+    }
+}
+
+/// A fruit on the board, worth `score::PointValues::fruit` to the Ben that reaches it.
+pub struct FruitPickup(pub Fruit);
+
+pub fn spawn_fruit(commands: &mut Commands, board: &Board, material: Handle<ColorMaterial>, fruit: Fruit, i: usize, j: usize) {
+    let (x, y) = board.indeces_to_coordinates(i, j);
+    commands.spawn_bundle(SpriteBundle {
+        material,
+        sprite: Sprite {
+            size: Vec2::splat(board.cell_size()),
+            resize_mode: SpriteResizeMode::Manual,
+            ..Default::default()
+        },
+        transform: Transform {
+            translation: Vec3::new(x, y, 3.),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .insert(TilePosition::centred(i, j))
+    .insert(FruitPickup(fruit));
+}
@@ -1,6 +1,12 @@
 use bevy::prelude::*;
 use crate::path::Path;
+use crate::movement::{self, TilePosition, PreviousTilePosition};
+use crate::board::Board;
 use crate::constants;
+use crate::enums::{CollisionType, Direction};
+use crate::animation::{self, Animation, AnimationClip};
+use crate::score::PointValues;
+use crate::utils;
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
 pub enum AttackState {
@@ -8,12 +14,87 @@ pub enum AttackState {
     Scared
 }
 
+/// A ghost's life cycle, which only ever moves forward through `next`:
+/// caged -> releasing -> released -> (eaten) eyes -> entering -> reviving -> caged.
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
 pub enum ReleaseState {
     Caged,
     Releasing,
     Released,
-    Respawning
+    // Eaten: only the eyes are left, flying back to the ghost gate
+    Eyes,
+    // Eyes passing through the gate down to the spawn point
+    Entering,
+    // Growing a body back inside the house
+    Reviving
+}
+
+impl ReleaseState {
+    pub fn next(self) -> Self {
+        match self {
+            ReleaseState::Caged => ReleaseState::Releasing,
+            ReleaseState::Releasing => ReleaseState::Released,
+            ReleaseState::Released => ReleaseState::Eyes,
+            ReleaseState::Eyes => ReleaseState::Entering,
+            ReleaseState::Entering => ReleaseState::Reviving,
+            ReleaseState::Reviving => ReleaseState::Caged
+        }
+    }
+
+    /// Eyes and reviving ghosts pass straight through Ben.
+    pub fn is_collidable(self) -> bool {
+        matches!(self, ReleaseState::Caged | ReleaseState::Releasing | ReleaseState::Released)
+    }
+}
+
+/// The direction a ghost last moved in, which the eyes look towards.
+pub struct GhostFacing(pub Direction);
+
+pub struct GhostReviveTimer(pub Timer);
+
+impl Default for GhostReviveTimer {
+    fn default() -> Self {
+        GhostReviveTimer(Timer::from_seconds(constants::GHOST_REVIVE_SECONDS, false))
+    }
+}
+
+/// Frames of each ghost's sprite sheet: the body, scared, scared flashing white, then the eyes looking each way.
+const FRAME_DEFAULT: u32 = 0;
+const FRAME_SCARED: u32 = 1;
+const FRAME_FLASH: u32 = 2;
+const FRAME_EYES_UP: u32 = 3;
+const FRAME_EYES_RIGHT: u32 = 4;
+const FRAME_EYES_DOWN: u32 = 5;
+const FRAME_EYES_LEFT: u32 = 6;
+pub const SHEET_COLUMNS: usize = 7;
+
+pub fn ghost_animation() -> Animation {
+    let still = |frame: u32| AnimationClip::new(&[frame], 1., true);
+    Animation::new(vec![
+        (animation::WALK_UP, still(FRAME_DEFAULT)),
+        (animation::WALK_RIGHT, still(FRAME_DEFAULT)),
+        (animation::WALK_DOWN, still(FRAME_DEFAULT)),
+        (animation::WALK_LEFT, still(FRAME_DEFAULT)),
+        (animation::SCARED, still(FRAME_SCARED)),
+        (animation::FLASHING, AnimationClip::new(&[FRAME_FLASH, FRAME_SCARED], 0.2, true)),
+        (animation::EYES_UP, still(FRAME_EYES_UP)),
+        (animation::EYES_RIGHT, still(FRAME_EYES_RIGHT)),
+        (animation::EYES_DOWN, still(FRAME_EYES_DOWN)),
+        (animation::EYES_LEFT, still(FRAME_EYES_LEFT)),
+        // Flickers between the eyes and the body while it re-forms
+        (animation::REVIVING, AnimationClip::new(&[FRAME_EYES_DOWN, FRAME_DEFAULT], constants::GHOST_REVIVE_BLINK_SECONDS, true))
+    ], animation::WALK_UP)
+}
+
+/// The clip a ghost should be playing. Scared ghosts start flashing once `flashing` says the scare is about to wear off.
+pub fn ghost_clip(release_state: ReleaseState, attack_state: AttackState, facing: Direction, flashing: bool) -> &'static str {
+    match (release_state, attack_state) {
+        (ReleaseState::Eyes, _) | (ReleaseState::Entering, _) => animation::eyes_clip(facing),
+        (ReleaseState::Reviving, _) => animation::REVIVING,
+        (_, AttackState::Attacking) => animation::walk_clip(facing),
+        (_, AttackState::Scared) if flashing => animation::FLASHING,
+        (_, AttackState::Scared) => animation::SCARED
+    }
 }
 
 pub struct Ghost;
@@ -22,22 +103,146 @@ pub struct GhostPath(pub Path);
 
 pub struct GhostSpeed(pub f32);
 
+/// How fast ghosts move through tunnel tiles as a percentage of full speed, read from the level's optional
+/// `@ghost_tunnel_speed` property.
+pub struct GhostTunnelSpeed(pub f32);
+
+impl GhostTunnelSpeed {
+    pub fn from_board(board: &Board) -> Self {
+        Self(board.property("ghost_tunnel_speed")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(constants::GHOST_SPEED_TUNNEL))
+    }
+}
+
 pub struct GhostScareTimer(pub Timer);
 
 impl Default for GhostScareTimer {
     fn default() -> Self {
-        GhostScareTimer(Timer::from_seconds(10., false))
+        GhostScareTimer(Timer::from_seconds(constants::GHOST_SCARE_SECONDS, false))
     }
 }
 
+/// Fallback that releases the next caged ghost when Ben hasn't eaten a dot for a while, read from the level's
+/// optional `@ghost_release_timeout` property (seconds).
 pub struct GhostReleaseTimer(pub Timer);
 
 impl Default for GhostReleaseTimer {
     fn default() -> Self {
-        Self(Timer::from_seconds(5., false))
+        Self(Timer::from_seconds(constants::GHOST_RELEASE_TIMEOUT_SECONDS, false))
+    }
+}
+
+impl GhostReleaseTimer {
+    pub fn from_board(board: &Board) -> Self {
+        let seconds = board.property("ghost_release_timeout")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(constants::GHOST_RELEASE_TIMEOUT_SECONDS);
+        Self(Timer::from_seconds(seconds, false))
+    }
+}
+
+/// Caged ghosts leave the house in priority order, and only the caged ghost with the lowest priority counts dots.
+pub struct GhostReleasePriority(pub usize);
+
+/// The arcade's dot counters. Each ghost has a personal counter that is kept for the whole level, but after Ben loses
+/// a life a single global counter takes over until the last ghost would have been released by it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GhostHouse {
+    pub dot_limits: [u32; 4],
+    pub global_dot_limits: [u32; 4],
+    pub dot_counters: [u32; 4],
+    pub global_dot_counter: Option<u32>
+}
+
+impl Default for GhostHouse {
+    fn default() -> Self {
+        Self {
+            dot_limits: constants::GHOST_DOT_LIMITS,
+            global_dot_limits: constants::GHOST_GLOBAL_DOT_LIMITS,
+            dot_counters: [0; 4],
+            global_dot_counter: None
+        }
+    }
+}
+
+impl GhostHouse {
+    /// Limits come from the level's optional `@ghost_dot_limits` and `@ghost_global_dot_limits` properties, which list
+    /// one value per release priority (e.g. `0 0 30 60`).
+    pub fn from_board(board: &Board) -> Self {
+        let parse = |key: &str| -> Option<[u32; 4]> {
+            let values: Vec<u32> = board.property(key)?
+                .split_whitespace()
+                .map(|value| value.parse().ok())
+                .collect::<Option<_>>()?;
+            values.try_into().ok()
+        };
+
+        let default = Self::default();
+        Self {
+            dot_limits: parse("ghost_dot_limits").unwrap_or(default.dot_limits),
+            global_dot_limits: parse("ghost_global_dot_limits").unwrap_or(default.global_dot_limits),
+            ..default
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.dot_counters = [0; 4];
+        self.global_dot_counter = None;
+    }
+
+    pub fn life_lost(&mut self) {
+        self.global_dot_counter = Some(0);
+    }
+
+    /// Counts a dot towards the ghost that would be released next, if any.
+    pub fn dot_eaten(&mut self, preferred: Option<usize>) {
+        let last = self.global_dot_limits.len() - 1;
+        match self.global_dot_counter.as_mut() {
+            Some(counter) => {
+                *counter += 1;
+
+                // As in the arcade, the last ghost is not released by the global counter; it falls back to its own
+                if *counter == self.global_dot_limits[last] && preferred == Some(last) {
+                    self.global_dot_counter = None;
+                }
+            },
+            None => if let Some(priority) = preferred {
+                self.dot_counters[priority] += 1;
+            }
+        }
+    }
+
+    pub fn should_release(&self, priority: usize) -> bool {
+        match self.global_dot_counter {
+            Some(counter) => counter >= self.global_dot_limits[priority],
+            None => self.dot_counters[priority] >= self.dot_limits[priority]
+        }
+    }
+
+    /// The caged ghost that leaves now, if any: the one next in line, once its counter allows or Ben hasn't eaten a dot
+    /// for long enough that the release timer has `timed_out`.
+    pub fn to_release(&self, next_in_line: Option<usize>, timed_out: bool) -> Option<usize> {
+        next_in_line.filter(|priority| timed_out || self.should_release(*priority))
     }
 }
 
+/// The caged ghost with the lowest release priority, which is the one dots count towards.
+pub fn next_in_line(ghosts: impl Iterator<Item = (usize, ReleaseState)>) -> Option<usize> {
+    ghosts
+        .filter(|(_, release_state)| *release_state == ReleaseState::Caged)
+        .map(|(release_priority, _)| release_priority)
+        .min()
+}
+
+/// Moves a releasing ghost towards the exit above the gate. Returns whether it got there, which is when it is released.
+pub fn leave_house(position: &mut TilePosition, board: &Board, speed: f32) -> bool {
+    // Waypoints are approached along x first, so this gets centered within the cage before moving upward
+    let exit = utils::get_ghost_exit_position(board);
+    position.move_towards(board, &exit, movement::speed_units(speed));
+    *position == exit
+}
+
 pub struct GhostChain(pub u8);
 
 impl Default for GhostChain {
@@ -51,11 +256,17 @@ pub struct GhostBundle {
     pub ghost: Ghost,
     pub attack_state: AttackState,
     pub release_state: ReleaseState,
+    pub position: TilePosition,
+    pub previous_position: PreviousTilePosition,
     pub path: GhostPath,
     pub speed: GhostSpeed,
+    pub release_priority: GhostReleasePriority,
+    pub facing: GhostFacing,
+    pub revive_timer: GhostReviveTimer,
+    pub animation: Animation,
 
     #[bundle]
-    pub sprite_bundle: SpriteBundle
+    pub sprite_sheet_bundle: SpriteSheetBundle
 }
 
 impl Default for GhostBundle {
@@ -64,23 +275,174 @@ impl Default for GhostBundle {
             ghost: Ghost,
             attack_state: AttackState::Attacking,
             release_state: ReleaseState::Caged,
+            position: TilePosition::default(),
+            previous_position: PreviousTilePosition::default(),
             path: GhostPath(Path::new()),
             speed: GhostSpeed(constants::GHOST_SPEED_DEFAULT),
-            sprite_bundle: SpriteBundle::default()
+            release_priority: GhostReleasePriority(0),
+            facing: GhostFacing(Direction::Up),
+            revive_timer: GhostReviveTimer::default(),
+            animation: ghost_animation(),
+            sprite_sheet_bundle: SpriteSheetBundle::default()
         }
     }
 }
 
 pub struct Caleb;
 
-pub struct CalebMaterials {
-    pub default_material: Handle<ColorMaterial>,
-    pub scared_material: Handle<ColorMaterial>
+/// Caleb's "Cruise Elroy" stage: 0 normally, then 1 and 2 once few enough dots remain, each one faster than the last.
+pub struct CruiseElroy(pub usize);
+
+impl CruiseElroy {
+    pub fn speed(&self) -> Option<f32> {
+        match self.0 {
+            0 => None,
+            stage => Some(constants::ELROY_SPEEDS[stage - 1])
+        }
+    }
+}
+
+/// Remaining dot counts at which Caleb enters each Elroy stage, read from the level's optional `@elroy_dots`
+/// property (e.g. `20 10`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ElroyThresholds(pub [usize; 2]);
+
+impl ElroyThresholds {
+    pub fn from_board(board: &Board) -> Self {
+        let thresholds = board.property("elroy_dots").and_then(|value| {
+            let values: Vec<usize> = value.split_whitespace()
+                .map(|value| value.parse().ok())
+                .collect::<Option<_>>()?;
+            values.try_into().ok()
+        });
+        Self(thresholds.unwrap_or(constants::ELROY_DOTS_REMAINING))
+    }
+
+    pub fn stage(&self, dots_remaining: usize) -> CruiseElroy {
+        CruiseElroy(self.0.iter().filter(|threshold| dots_remaining <= **threshold).count())
+    }
+}
+
+/// How fast a released ghost moves from where it is. `ghost_speed` is the usual speed, which practice can change, and
+/// Elroy's speeds scale along with it. Elroy keeps chasing at full tilt, but still slows down in tunnels and when scared.
+pub fn released_speed(
+    board: &Board,
+    position: &TilePosition,
+    attack_state: AttackState,
+    cruise_elroy: Option<&CruiseElroy>,
+    ghost_speed: f32,
+    ghost_tunnel_speed: f32
+) -> f32 {
+    if board.is_tunnel_tile(position.i, position.j) {
+        return ghost_tunnel_speed;
+    }
+
+    cruise_elroy
+        .and_then(|cruise_elroy| cruise_elroy.speed())
+        .filter(|_| attack_state == AttackState::Attacking)
+        .map_or(ghost_speed, |elroy_speed| elroy_speed * ghost_speed / constants::GHOST_SPEED_DEFAULT)
+}
+
+/// Moves a released ghost `distance` units along its path, heading for the closest of `ben_tiles` whenever the path
+/// runs out.
+pub fn chase(position: &mut TilePosition, path: &mut Path, board: &Board, distance: i32, ben_tiles: &[(usize, usize)]) {
+    let mut distance = distance;
+    while distance > 0 {
+        distance = path.follow(position, board, distance);
+        if distance > 0 {
+            let (i, j) = position.tile();
+            let ben_tile = match ben_tiles.iter().min_by_key(|(ben_i, ben_j)| {
+                (*ben_i as i64 - i as i64).pow(2) + (*ben_j as i64 - j as i64).pow(2)
+            }) {
+                Some(ben_tile) => *ben_tile,
+                None => break
+            };
+            *path = Path::shortest_to_tile(position, ben_tile, board);
+            if path.is_empty() {
+                break;
+            }
+        }
+    }
+}
+
+/// Flies eyes back to the gate and down into the house. Returns whether they just reached the spawn point, which is
+/// when the ghost starts reviving.
+pub fn return_home(position: &mut TilePosition, release_state: &mut ReleaseState, path: &mut Path, board: &Board) -> bool {
+    let distance = movement::speed_units(constants::GHOST_SPEED_RESPAWNING);
+    match *release_state {
+        ReleaseState::Eyes => {
+            let exit = utils::get_ghost_exit_position(board);
+            if path.is_empty() {
+                *path = Path::shortest_to_tile(position, exit.tile(), board);
+                path.push_back(exit);
+            }
+            path.follow(position, board, distance);
+            if *position == exit {
+                *release_state = release_state.next();
+                path.clear();
+            }
+            false
+        },
+        ReleaseState::Entering => {
+            let spawn = utils::get_ghost_spawn_position(board);
+            position.move_towards(board, &spawn, distance);
+            if *position == spawn {
+                *release_state = release_state.next();
+                return true;
+            }
+            false
+        },
+        _ => false
+    }
+}
+
+/// Power-ups scare every ghost that can still be touched; eyes and reviving ghosts are left alone.
+pub fn scare(attack_state: &mut AttackState, release_state: ReleaseState) {
+    if release_state.is_collidable() {
+        *attack_state = AttackState::Scared;
+    }
+}
+
+/// Where an actor was at the start of the tick and where it is now.
+pub type Movement = (TilePosition, TilePosition);
+
+/// Which Ben each ghost touched during the tick, as pairs of indeces into `ghosts` and `bens`. A ghost touches at most
+/// one Ben, the first it reaches in `bens`. Ghosts that can't be touched are left out.
+pub fn touches(board: &Board, collision_radius: i32, bens: &[Movement], ghosts: &[(Movement, ReleaseState)]) -> Vec<(usize, usize)> {
+    ghosts.iter()
+        .enumerate()
+        .filter(|(_, (_, release_state))| release_state.is_collidable())
+        .filter_map(|(ghost, ((ghost_from, ghost_to), _))| {
+            bens.iter()
+                .position(|(ben_from, ben_to)| {
+                    utils::did_collide(ghost_from, ghost_to, ben_from, ben_to, board, CollisionType::Swept(collision_radius))
+                })
+                .map(|ben| (ghost, ben))
+        })
+        .collect()
+}
+
+/// Turns a scared ghost into eyes and moves the chain on. Returns the points it is worth.
+pub fn eat(
+    release_state: &mut ReleaseState,
+    attack_state: &mut AttackState,
+    path: &mut Path,
+    ghost_chain: &mut u8,
+    point_values: &PointValues
+) -> u64 {
+    // Only the eyes are left, and they no longer care about power-ups
+    *release_state = ReleaseState::Eyes;
+    *attack_state = AttackState::Attacking;
+    path.clear();
+    let points = point_values.ghost(*ghost_chain);
+    *ghost_chain += 1;
+    points
 }
 
 #[derive(Bundle)]
 pub struct CalebBundle {
     pub caleb: Caleb,
+    pub cruise_elroy: CruiseElroy,
 
     #[bundle]
     pub ghost_bundle: GhostBundle
@@ -90,6 +452,7 @@ impl Default for CalebBundle {
     fn default() -> Self {
         Self {
             caleb: Caleb,
+            cruise_elroy: CruiseElroy(0),
             ghost_bundle: GhostBundle::default()
         }
     }
@@ -97,11 +460,6 @@ impl Default for CalebBundle {
 
 pub struct Harris;
 
-pub struct HarrisMaterials {
-    pub default_material: Handle<ColorMaterial>,
-    pub scared_material: Handle<ColorMaterial>
-}
-
 #[derive(Bundle)]
 pub struct HarrisBundle {
     pub sam: Harris,
@@ -121,11 +479,6 @@ impl Default for HarrisBundle {
 
 pub struct Claflin;
 
-pub struct ClaflinMaterials {
-    pub default_material: Handle<ColorMaterial>,
-    pub scared_material: Handle<ColorMaterial>
-}
-
 #[derive(Bundle)]
 pub struct ClaflinBundle {
     pub neyton: Claflin,
@@ -145,11 +498,6 @@ impl Default for ClaflinBundle {
 
 pub struct Samson;
 
-pub struct SamsonMaterials {
-    pub default_material: Handle<ColorMaterial>,
-    pub scared_material: Handle<ColorMaterial>
-}
-
 #[derive(Bundle)]
 pub struct SamsonBundle {
     pub samson: Samson,
@@ -166,3 +514,121 @@ impl Default for SamsonBundle {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ghost_house() -> GhostHouse {
+        GhostHouse {
+            dot_limits: [0, 2, 3, 4],
+            global_dot_limits: [0, 1, 2, 3],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn dots_count_towards_the_next_ghost_in_line_only() {
+        let mut ghost_house = ghost_house();
+        assert_eq!(ghost_house.to_release(Some(0), false), Some(0));
+        assert_eq!(ghost_house.to_release(Some(1), false), None);
+
+        ghost_house.dot_eaten(Some(1));
+        ghost_house.dot_eaten(Some(1));
+        assert_eq!(ghost_house.dot_counters, [0, 2, 0, 0]);
+        assert_eq!(ghost_house.to_release(Some(1), false), Some(1));
+        assert_eq!(ghost_house.to_release(Some(2), false), None);
+
+        // With nobody caged, dots don't count at all
+        ghost_house.dot_eaten(None);
+        assert_eq!(ghost_house.dot_counters, [0, 2, 0, 0]);
+    }
+
+    #[test]
+    fn losing_a_life_switches_to_the_global_counter() {
+        let mut ghost_house = ghost_house();
+        for _ in 0..3 {
+            ghost_house.dot_eaten(Some(2));
+        }
+        assert!(ghost_house.should_release(2));
+
+        // The personal counters are kept, but don't count while the global one runs
+        ghost_house.life_lost();
+        assert!(!ghost_house.should_release(2));
+        ghost_house.dot_eaten(Some(1));
+        assert!(ghost_house.should_release(1));
+        ghost_house.dot_eaten(Some(2));
+        assert!(ghost_house.should_release(2));
+        assert_eq!(ghost_house.dot_counters, [0, 0, 3, 0]);
+    }
+
+    #[test]
+    fn last_ghost_falls_back_to_its_own_counter() {
+        let mut ghost_house = ghost_house();
+        ghost_house.life_lost();
+        for _ in 0..3 {
+            ghost_house.dot_eaten(Some(3));
+        }
+        assert_eq!(ghost_house.global_dot_counter, None);
+        assert!(!ghost_house.should_release(3));
+
+        for _ in 0..4 {
+            ghost_house.dot_eaten(Some(3));
+        }
+        assert!(ghost_house.should_release(3));
+    }
+
+    #[test]
+    fn release_timer_lets_the_next_ghost_out_regardless() {
+        let mut ghost_house = ghost_house();
+        assert_eq!(ghost_house.to_release(Some(3), true), Some(3));
+        assert_eq!(ghost_house.to_release(None, true), None);
+
+        ghost_house.life_lost();
+        assert_eq!(ghost_house.to_release(Some(2), false), None);
+        assert_eq!(ghost_house.to_release(Some(2), true), Some(2));
+    }
+
+    #[test]
+    fn next_in_line_is_the_caged_ghost_with_the_lowest_priority() {
+        let ghosts = [(0, ReleaseState::Released), (1, ReleaseState::Releasing), (3, ReleaseState::Caged), (2, ReleaseState::Caged)];
+        assert_eq!(next_in_line(ghosts.into_iter()), Some(2));
+        assert_eq!(next_in_line(ghosts[..2].iter().copied()), None);
+    }
+
+    #[test]
+    fn elroy_stages_follow_the_dots_left() {
+        let thresholds = ElroyThresholds([20, 10]);
+        assert_eq!(thresholds.stage(21).0, 0);
+        assert_eq!(thresholds.stage(20).0, 1);
+        assert_eq!(thresholds.stage(11).0, 1);
+        assert_eq!(thresholds.stage(10).0, 2);
+        assert_eq!(thresholds.stage(0).0, 2);
+        assert_eq!(thresholds.stage(20).speed(), Some(constants::ELROY_SPEEDS[0]));
+        assert_eq!(thresholds.stage(21).speed(), None);
+    }
+
+    #[test]
+    fn elroy_is_suspended_while_scared_and_in_tunnels() {
+        let board = Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET);
+        let corridor = TilePosition::centred(1, 1);
+        let tunnel = TilePosition::centred(14, 0);
+        let elroy = CruiseElroy(2);
+        let speed = |position: &TilePosition, attack_state: AttackState, cruise_elroy: Option<&CruiseElroy>| {
+            released_speed(&board, position, attack_state, cruise_elroy, constants::GHOST_SPEED_DEFAULT, constants::GHOST_SPEED_TUNNEL)
+        };
+
+        assert_eq!(speed(&corridor, AttackState::Attacking, Some(&elroy)), constants::ELROY_SPEEDS[1]);
+        assert_eq!(speed(&corridor, AttackState::Attacking, Some(&CruiseElroy(0))), constants::GHOST_SPEED_DEFAULT);
+        assert_eq!(speed(&corridor, AttackState::Attacking, None), constants::GHOST_SPEED_DEFAULT);
+        assert_eq!(speed(&corridor, AttackState::Scared, Some(&elroy)), constants::GHOST_SPEED_DEFAULT);
+        assert_eq!(speed(&tunnel, AttackState::Attacking, Some(&elroy)), constants::GHOST_SPEED_TUNNEL);
+    }
+
+    #[test]
+    fn elroy_scales_with_practice_ghost_speed() {
+        let board = Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET);
+        let speed = released_speed(&board, &TilePosition::centred(1, 1), AttackState::Attacking, Some(&CruiseElroy(1)), constants::GHOST_SPEED_DEFAULT / 2., constants::GHOST_SPEED_TUNNEL);
+        assert_eq!(speed, constants::ELROY_SPEEDS[0] / 2.);
+    }
+}
@@ -0,0 +1,322 @@
+use bevy::prelude::*;
+use crate::ben::{self, Ben, BenLives, PlayerId};
+use crate::constants;
+use crate::fruit::Fruit;
+use crate::misc::Level;
+use crate::players::Players;
+use crate::practice::Practice;
+use crate::score::Score;
+use crate::versus::Versus;
+
+// How many levels' fruit the bottom bar shows, newest on the right
+const FRUIT_HISTORY: u32 = 7;
+const UP_BLINK_SECONDS: f32 = 0.25;
+const ICON_SIZE: f32 = 28.;
+
+/// The best score of this session.
+pub struct HighScore(pub u64);
+
+impl Default for HighScore {
+    fn default() -> Self {
+        Self(0)
+    }
+}
+
+pub struct HudMaterials {
+    /// Tinted to match each player's Ben.
+    pub lives: Vec<Handle<ColorMaterial>>,
+    pub fruits: Vec<(Fruit, Handle<ColorMaterial>)>
+}
+
+impl HudMaterials {
+    pub fn fruit(&self, fruit: Fruit) -> Handle<ColorMaterial> {
+        self.fruits.iter().find(|(other, _)| *other == fruit).unwrap().1.clone()
+    }
+}
+
+/// The "1UP" or "2UP" label over a player's score, which blinks while it's that player's turn.
+pub struct UpText {
+    pub player: usize,
+    pub timer: Timer
+}
+
+/// A player's score. Whoever is playing has theirs on their Ben; in alternating play the others' are in `Players`.
+pub struct ScoreText(pub usize);
+
+pub struct HighScoreText;
+
+/// Holds a `LifeIcon` for every life a player's Ben has left in reserve.
+pub struct LifeIcons(pub usize);
+
+pub struct LifeIcon(pub usize);
+
+/// Holds a `FruitIcon` for each of the last few levels.
+pub struct FruitRow;
+
+pub struct FruitIcon;
+
+/// Lays out the HUD in Bevy UI, so it sits in the bars above and below the maze regardless of the board's scale.
+pub fn setup_hud(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    asset_server: Res<AssetServer>
+) {
+    commands.spawn_bundle(UiCameraBundle::default());
+
+    let fruits = [
+        Fruit::Cherry,
+        Fruit::Strawberry,
+        Fruit::Orange,
+        Fruit::Apple,
+        Fruit::Melon,
+        Fruit::Flagship,
+        Fruit::Bell,
+        Fruit::Key
+    ];
+    let life_texture = asset_server.load("ben/ben_life.png");
+    commands.insert_resource(HudMaterials {
+        lives: (0..2)
+            .map(|player| materials.add(ColorMaterial::modulated_texture(life_texture.clone(), ben::player_color(player))))
+            .collect(),
+        fruits: fruits.iter()
+            .map(|fruit| (*fruit, materials.add(asset_server.load(fruit.image_path()).into())))
+            .collect()
+    });
+
+    let transparent = materials.add(Color::NONE.into());
+    let font = asset_server.load("font.ttf");
+    let label_style = TextStyle {
+        font: font.clone(),
+        font_size: 20.,
+        color: Color::WHITE
+    };
+    let value_style = TextStyle {
+        font,
+        font_size: 24.,
+        color: Color::WHITE
+    };
+    // UI columns are laid out bottom to top
+    let column = NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::ColumnReverse,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        material: transparent.clone(),
+        ..Default::default()
+    };
+
+    // Top bar
+    commands.spawn_bundle(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                left: Val::Px(0.),
+                top: Val::Px(0.),
+                ..Default::default()
+            },
+            size: Size::new(Val::Percent(100.), Val::Px(constants::HUD_TOP_HEIGHT)),
+            justify_content: JustifyContent::SpaceAround,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        material: transparent.clone(),
+        ..Default::default()
+    })
+    .with_children(|parent| {
+        parent.spawn_bundle(column.clone()).with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section("1UP", label_style.clone(), TextAlignment::default()),
+                ..Default::default()
+            })
+            .insert(UpText {
+                player: 0,
+                timer: Timer::from_seconds(UP_BLINK_SECONDS, true)
+            });
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section("0", value_style.clone(), TextAlignment::default()),
+                ..Default::default()
+            })
+            .insert(ScoreText(0));
+        });
+        parent.spawn_bundle(column.clone()).with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section("HIGH SCORE", label_style.clone(), TextAlignment::default()),
+                ..Default::default()
+            });
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section("0", value_style.clone(), TextAlignment::default()),
+                ..Default::default()
+            })
+            .insert(HighScoreText);
+        });
+        parent.spawn_bundle(column.clone()).with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section("2UP", label_style.clone(), TextAlignment::default()),
+                ..Default::default()
+            })
+            .insert(UpText {
+                player: 1,
+                timer: Timer::from_seconds(UP_BLINK_SECONDS, true)
+            });
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section("", value_style.clone(), TextAlignment::default()),
+                ..Default::default()
+            })
+            .insert(ScoreText(1));
+        });
+    });
+
+    // Bottom bar
+    let row = NodeBundle {
+        style: Style {
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        material: transparent.clone(),
+        ..Default::default()
+    };
+    commands.spawn_bundle(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                left: Val::Px(0.),
+                bottom: Val::Px(0.),
+                ..Default::default()
+            },
+            size: Size::new(Val::Percent(100.), Val::Px(constants::HUD_BOTTOM_HEIGHT)),
+            padding: Rect::all(Val::Px(8.)),
+            justify_content: JustifyContent::SpaceBetween,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        material: transparent,
+        ..Default::default()
+    })
+    .with_children(|parent| {
+        parent.spawn_bundle(row.clone()).insert(LifeIcons(0));
+        parent.spawn_bundle(row.clone()).insert(FruitRow);
+        parent.spawn_bundle(row).insert(LifeIcons(1));
+    });
+}
+
+fn icon(material: Handle<ColorMaterial>) -> ImageBundle {
+    ImageBundle {
+        style: Style {
+            size: Size::new(Val::Px(ICON_SIZE), Val::Px(ICON_SIZE)),
+            margin: Rect::all(Val::Px(2.)),
+            ..Default::default()
+        },
+        material,
+        ..Default::default()
+    }
+}
+
+/// Whether `player` is in the game, and whether it's their go. Alternating players share the one Ben; in co-op every
+/// player has a Ben of their own and they all play at once. In versus player two is the ghost.
+pub fn player_status(player: usize, players: &Players, ben_players: &[usize], versus: &Versus) -> (bool, bool) {
+    if versus.active {
+        (player < 2, player < 2)
+    } else if players.count() > 1 {
+        (player < players.count(), player == players.current)
+    } else {
+        let playing = ben_players.contains(&player);
+        (playing, playing)
+    }
+}
+
+pub fn up_blink_system(
+    mut query: Query<(&mut UpText, &mut Visible)>,
+    ben_query: Query<&PlayerId, With<Ben>>,
+    players: Res<Players>,
+    versus: Res<Versus>,
+    time: Res<Time>
+) {
+    let ben_players: Vec<usize> = ben_query.iter().map(|player_id| player_id.0).collect();
+    for (mut up_text, mut visible) in query.iter_mut() {
+        match player_status(up_text.player, &players, &ben_players, &versus) {
+            (false, _) => visible.is_visible = false,
+            (true, false) => visible.is_visible = true,
+            (true, true) => if up_text.timer.tick(time.delta()).just_finished() {
+                visible.is_visible = !visible.is_visible;
+            }
+        }
+    }
+}
+
+pub fn high_score_system(
+    mut high_score: ResMut<HighScore>,
+    mut text_query: Query<&mut Text, With<HighScoreText>>,
+    score_query: Query<&Score, With<Ben>>,
+    practice: Res<Practice>
+) {
+    // Practice scores are never records
+    if practice.active {
+        return;
+    }
+
+    for score in score_query.iter() {
+        if score.0 > high_score.0 {
+            high_score.0 = score.0;
+        }
+    }
+    if high_score.is_changed() {
+        let mut text = text_query.single_mut().unwrap();
+        text.sections[0].value = high_score.0.to_string();
+    }
+}
+
+pub fn life_icons_system(
+    mut commands: Commands,
+    container_query: Query<(Entity, &LifeIcons)>,
+    icon_query: Query<(Entity, &LifeIcon)>,
+    ben_query: Query<(&PlayerId, &BenLives), With<Ben>>,
+    hud_materials: Res<HudMaterials>
+) {
+    for (container_entity, life_icons) in container_query.iter() {
+        let player = life_icons.0;
+
+        // The life in play isn't shown
+        let reserve = ben_query.iter()
+            .find(|(player_id, _)| player_id.0 == player)
+            .map_or(0, |(_, ben_lives)| ben_lives.0.saturating_sub(1) as usize);
+        let icons: Vec<Entity> = icon_query.iter()
+            .filter(|(_, life_icon)| life_icon.0 == player)
+            .map(|(icon_entity, _)| icon_entity)
+            .collect();
+        if icons.len() == reserve {
+            continue;
+        }
+
+        for icon_entity in icons {
+            commands.entity(icon_entity).despawn_recursive();
+        }
+        commands.entity(container_entity).with_children(|parent| {
+            for _ in 0..reserve {
+                parent.spawn_bundle(icon(hud_materials.lives[player].clone())).insert(LifeIcon(player));
+            }
+        });
+    }
+}
+
+pub fn fruit_row_system(
+    mut commands: Commands,
+    container_query: Query<Entity, With<FruitRow>>,
+    icon_query: Query<Entity, With<FruitIcon>>,
+    level: Res<Level>,
+    hud_materials: Res<HudMaterials>
+) {
+    if !level.is_changed() {
+        return;
+    }
+
+    for icon_entity in icon_query.iter() {
+        commands.entity(icon_entity).despawn_recursive();
+    }
+    let first_level = level.0.saturating_sub(FRUIT_HISTORY - 1).max(1);
+    commands.entity(container_query.single().unwrap()).with_children(|parent| {
+        for shown_level in first_level..=level.0 {
+            parent.spawn_bundle(icon(hud_materials.fruit(Fruit::for_level(shown_level)))).insert(FruitIcon);
+        }
+    });
+}
@@ -10,8 +10,30 @@ mod power_up;
 mod path;
 mod constants;
 mod misc;
-
-use std::{thread, time};
+mod wall;
+mod maze;
+mod editor;
+mod validate;
+mod movement;
+mod settings;
+mod animation;
+mod sequence;
+mod popup;
+mod fruit;
+mod hud;
+mod view;
+mod players;
+mod versus;
+mod sim;
+mod net;
+mod autopilot;
+mod env;
+mod simulate;
+mod save;
+mod rewind;
+mod practice;
+
+use std::{collections::HashSet, thread, time};
 use bevy::{
     prelude::*,
     render::camera::{OrthographicProjection, WindowOrigin}
@@ -22,61 +44,156 @@ use ghost::{
     AttackState, 
     ReleaseState,
     GhostSpeed,
+    GhostTunnelSpeed,
     GhostBundle,
     Caleb,
     CalebBundle, 
-    CalebMaterials,
+    CruiseElroy,
+    ElroyThresholds,
     Harris, 
-    HarrisMaterials,
     HarrisBundle, 
     Claflin, 
-    ClaflinMaterials,
     ClaflinBundle, 
     Samson, 
-    SamsonMaterials,
     SamsonBundle,
     GhostScareTimer,
     GhostReleaseTimer,
-    GhostChain
+    GhostReleasePriority,
+    GhostHouse,
+    GhostChain,
+    GhostFacing,
+    GhostReviveTimer
 };
 use board::Board;
-use ben::{Ben, BenBundle, BenAnimationTimer, BenSpeed, BenDirection, BenNextDirection, BenMaterials};
-use enums::{Direction, GameState, Label, CollisionType};
+use ben::{Ben, BenLives, BenSpeed, BenDirection, BenNextDirection, BenInputTimer, BenSpriteSheet, PlayerId, Caught};
+use enums::{Direction, GameState, Label};
 use dot::{Dot, DotMaterial};
-use score::{Score, ScoreBundle, PointValues};
-use events::{BenDirectionChangedEvent, PowerUpConsumedEvent};
-use power_up::{PowerUp, PowerUpMaterials, PowerUpAnimationTimer};
-use path::Path;
+use score::{Score, PointValues};
+use events::{BenDirectionChangedEvent, PowerUpConsumedEvent, DotConsumedEvent, CueEvent, ScorePopupEvent};
+use power_up::{PowerUp, PowerUpSpriteSheet};
+use movement::{TilePosition, PreviousTilePosition, CollisionRadius};
+use wall::{WallMaterials, MazeFlash};
+use maze::MazeTheme;
+use editor::{EditorSession, EditorMaterials};
+use animation::Animation;
+use sequence::{Sequence, Cue};
+use settings::Settings;
+use players::Players;
+use versus::{Versus, HumanControlled};
+use autopilot::Autopilot;
+use save::{SaveState, PendingLoad};
+use practice::Practice;
+use fruit::FruitPickup;
 
 fn main() {
-    let board = Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET);
+    // `validate [level] [--soak <strategy>]` checks a level and exits, `env [level]` serves the training environment
+    // over stdin and stdout and `simulate [level] [options]` plays headless games in bulk; otherwise an optional level
+    // file may be passed as the first argument
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(|arg| arg.as_str()) == Some("validate") {
+        let level_path = args.get(2).filter(|arg| !arg.starts_with("--")).map(|arg| arg.as_str());
+        let soak_strategy = args.iter()
+            .position(|arg| arg == "--soak")
+            .map(|index| args.get(index + 1).map_or("cautious", |name| name.as_str()));
+        std::process::exit(validate::run(level_path, soak_strategy));
+    }
+    if args.get(1).map(|arg| arg.as_str()) == Some("env") {
+        std::process::exit(env::run(args.get(2).map(|arg| arg.as_str()), env::EnvConfig::default()));
+    }
+    if args.get(1).map(|arg| arg.as_str()) == Some("simulate") {
+        std::process::exit(simulate::run(&args[2..]));
+    }
 
-    App::build()
+    // `host [port]` and `join <address>` play online, and take the level as `--level <path>`
+    let online = matches!(args.get(1).map(|arg| arg.as_str()), Some("host") | Some("join"));
+    let level_path = if online {
+        args.iter().position(|arg| arg == "--level").and_then(|index| args.get(index + 1)).cloned()
+    } else {
+        args.get(1).cloned()
+    };
+    let board = match &level_path {
+        Some(path) => match Board::from_file(path, constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET) {
+            Ok(board) => board,
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(2);
+            }
+        },
+        None => Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET)
+    };
+    let ghost_tunnel_speed = GhostTunnelSpeed::from_board(&board);
+    let ghost_release_timer = GhostReleaseTimer::from_board(&board);
+    let ghost_house = GhostHouse::from_board(&board);
+    let elroy_thresholds = ElroyThresholds::from_board(&board);
+    let collision_radius = CollisionRadius::from_board(&board);
+    let settings = Settings::load(settings::SETTINGS_PATH);
+    let (window_width, window_height) = view::initial_window_size(&board);
+    let editor_session = EditorSession {
+        level_path: level_path.unwrap_or_else(|| editor::DEFAULT_LEVEL_PATH.to_string()),
+        ..Default::default()
+    };
+    let session = if online {
+        match net::connect(&args, &board) {
+            Ok(session) => Some(session),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    let mut app = App::build();
+    if let Some(session) = session {
+        app.insert_resource(session);
+    }
+    app
         // Resources
         .insert_resource(ClearColor(Color::rgb(0., 0., 0.)))
         .insert_resource(WindowDescriptor {
             title: "Benja-Man".to_string(),
-            width: board.width() as f32 * board.cell_size(),
-            height: board.height() as f32 * board.cell_size() + 32.,
-            resizable: false,
+            width: window_width,
+            height: window_height,
+            resizable: true,
+            mode: view::window_mode(settings.fullscreen),
             ..Default::default()
         })
         .insert_resource(board)
+        .insert_resource(ghost_tunnel_speed)
         .init_resource::<PointValues>()
         .init_resource::<GhostScareTimer>()
-        .init_resource::<GhostReleaseTimer>()
+        .insert_resource(ghost_release_timer)
+        .insert_resource(ghost_house)
+        .insert_resource(elroy_thresholds)
+        .insert_resource(collision_radius)
         .init_resource::<GhostChain>()
         .init_resource::<misc::EndMessageText>()
+        .init_resource::<MazeFlash>()
+        .init_resource::<Sequence>()
+        .init_resource::<misc::Level>()
+        .init_resource::<hud::HighScore>()
+        .init_resource::<Players>()
+        .init_resource::<Versus>()
+        .init_resource::<rewind::Rewind>()
+        .init_resource::<Practice>()
+        .insert_resource(editor_session)
+        .insert_resource(settings)
+        .init_resource::<view::BoardView>()
 
         // Events
         .add_event::<BenDirectionChangedEvent>()
         .add_event::<PowerUpConsumedEvent>()
+        .add_event::<DotConsumedEvent>()
+        .add_event::<CueEvent>()
+        .add_event::<ScorePopupEvent>()
 
         // State
-        .add_state(GameState::Wait)
+        .add_state(if online { GameState::Online } else { GameState::Wait })
 
         // Startup
         .add_startup_system(setup.system())
+        .add_startup_system(hud::setup_hud.system())
 
         // Game start
         .add_system_set(
@@ -87,50 +204,191 @@ fn main() {
         // Mainloop
         .add_system_set(
             SystemSet::on_update(GameState::Default)
+                .with_system(rewind::record_rewind_system.system().label(Label::RewindSystem).before(Label::RecordPositionsSystem).before(Label::BenControllerSystem))
+                .with_system(record_positions_system.system().label(Label::RecordPositionsSystem).before(Label::ThrottleFramerateSystem))
                 .with_system(ben_controller_system.system().label(Label::BenControllerSystem).before(Label::ThrottleFramerateSystem))
-                .with_system(ben_movement_system.system().label(Label::BenMovementSystem).after(Label::BenControllerSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(autopilot::autopilot_system.system().label(Label::BenControllerSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(ben_movement_system.system().label(Label::BenMovementSystem).after(Label::BenControllerSystem).after(Label::RecordPositionsSystem).before(Label::ThrottleFramerateSystem))
                 .with_system(ben_dot_collision_system.system().after(Label::BenMovementSystem).before(Label::ThrottleFramerateSystem))
                 .with_system(ben_power_up_collision_system.system().after(Label::BenMovementSystem).before(Label::ThrottleFramerateSystem)) 
-                .with_system(ben_ghost_collision_system.system().label(Label::BenGhostCollisionSystem).after(Label::BenMovementSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(ben_fruit_collision_system.system().after(Label::BenMovementSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(ben_ghost_collision_system.system().label(Label::BenGhostCollisionSystem).after(Label::BenMovementSystem).after(Label::GhostMovementSystem).after(Label::WinSystem).before(Label::ThrottleFramerateSystem))
                 .with_system(ben_animation_system.system().before(Label::ThrottleFramerateSystem))
                 .with_system(scare_ghosts_system.system().before(Label::ThrottleFramerateSystem))
-                .with_system(ghost_movement_system.system().before(Label::ThrottleFramerateSystem))
-                .with_system(caleb_animation_system.system().before(Label::ThrottleFramerateSystem))
-                .with_system(harris_animation_system.system().before(Label::ThrottleFramerateSystem))
-                .with_system(claflin_animation_system.system().before(Label::ThrottleFramerateSystem))
-                .with_system(samson_animation_system.system().before(Label::ThrottleFramerateSystem))
-                .with_system(win_system.system().before(Label::ThrottleFramerateSystem))
-                .with_system(ghost_release_system.system().before(Label::ThrottleFramerateSystem))
-                .with_system(ghost_respawn_system.system().after(Label::BenGhostCollisionSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(cruise_elroy_system.system().before(Label::ThrottleFramerateSystem))
+                .with_system(ghost_movement_system.system().label(Label::GhostMovementSystem).after(Label::RecordPositionsSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(versus::human_ghost_movement_system.system().label(Label::GhostMovementSystem).after(Label::RecordPositionsSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(ghost_animation_system.system().before(Label::ThrottleFramerateSystem))
+                .with_system(win_system.system().label(Label::WinSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(ghost_release_system.system().label(Label::GhostReleaseSystem).after(Label::RecordPositionsSystem).before(Label::GhostMovementSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(ghost_respawn_system.system().label(Label::GhostRespawnSystem).after(Label::BenGhostCollisionSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(ghost_facing_system.system().after(Label::GhostReleaseSystem).after(Label::GhostMovementSystem).after(Label::GhostRespawnSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(save::quick_save_system.system().before(Label::ThrottleFramerateSystem))
+                .with_system(save::quick_load_system.system().after(Label::BenGhostCollisionSystem).after(Label::WinSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(rewind::start_rewind_system.system().after(Label::BenGhostCollisionSystem).after(Label::WinSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(practice::practice_controls_system.system().after(Label::BenGhostCollisionSystem).after(Label::WinSystem).before(Label::ThrottleFramerateSystem))
                 .with_system(throttle_framerate_system.system().label(Label::ThrottleFramerateSystem))
         )
 
         // Game end
+        .add_system_set(
+            SystemSet::on_enter(GameState::End)
+                .with_system(versus::tally_round_system.system())
+        )
         .add_system_set(
             SystemSet::on_update(GameState::End)
                 .with_system(display_end_message_system.system().before(Label::WaitForRestartSystem))
                 .with_system(wait_for_restart_system.system().label(Label::WaitForRestartSystem))
         )
 
+        // Rewind
+        .add_system_set(
+            SystemSet::on_enter(GameState::Rewind)
+                .with_system(rewind::enter_rewind_system.system())
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Rewind)
+                .with_system(rewind::rewind_system.system().label(Label::RewindSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(ghost_animation_system.system().before(Label::ThrottleFramerateSystem))
+                .with_system(throttle_framerate_system.system().label(Label::ThrottleFramerateSystem))
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Rewind)
+                .with_system(rewind::exit_rewind_system.system())
+        )
+
+        // Online play
+        .add_system_set(
+            SystemSet::on_enter(GameState::Online)
+                .with_system(net::enter_online_system.system())
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Online)
+                .with_system(net::online_system.system().label(Label::OnlineSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(net::online_render_system.system().label(Label::OnlineRenderSystem).after(Label::OnlineSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(ben_animation_system.system().after(Label::OnlineRenderSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(ghost_facing_system.system().after(Label::OnlineRenderSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(ghost_animation_system.system().after(Label::OnlineRenderSystem).before(Label::ThrottleFramerateSystem))
+                .with_system(throttle_framerate_system.system().label(Label::ThrottleFramerateSystem))
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Online)
+                .with_system(net::leave_online_system.system())
+        )
+
+        // Level editor
+        .add_system_set(
+            SystemSet::on_enter(GameState::Editor)
+                .with_system(editor::enter_editor_system.system())
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Editor)
+                .with_system(editor::editor_brush_system.system())
+                .with_system(editor::editor_paint_system.system())
+                .with_system(editor::editor_history_system.system())
+                .with_system(editor::editor_save_system.system())
+                .with_system(editor::editor_playtest_system.system())
+                .with_system(editor::editor_redraw_system.system())
+                .with_system(editor::editor_status_system.system())
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Editor)
+                .with_system(editor::exit_editor_system.system())
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Default)
+                .with_system(editor::playtest_return_system.system())
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::End)
+                .with_system(editor::playtest_return_system.system())
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Sequence)
+                .with_system(editor::playtest_return_system.system())
+        )
+
+        // Sequences
+        .add_system_set(
+            SystemSet::on_update(GameState::Sequence)
+                .with_system(sequence::sequence_system.system().label(Label::SequenceSystem))
+                .with_system(ben_death_cue_system.system().after(Label::SequenceSystem))
+                .with_system(hide_ghosts_cue_system.system().after(Label::SequenceSystem))
+                .with_system(reset_ben_system.system().after(Label::SequenceSystem))
+                .with_system(reset_caleb_system.system().after(Label::SequenceSystem))
+                .with_system(reset_harris_system.system().after(Label::SequenceSystem))
+                .with_system(reset_claflin_system.system().after(Label::SequenceSystem))
+                .with_system(reset_samson_system.system().after(Label::SequenceSystem))
+                .with_system(ready_cue_system.system().after(Label::SequenceSystem))
+                .with_system(flash_maze_cue_system.system().after(Label::SequenceSystem))
+                .with_system(players::switch_players_cue_system.system().after(Label::SequenceSystem))
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Sequence)
+                .with_system(exit_sequence_system.system())
+        )
+
+        // Settings menu
+        .add_system_set(
+            SystemSet::on_enter(GameState::Settings)
+                .with_system(settings::enter_settings_system.system())
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Settings)
+                .with_system(settings::settings_menu_system.system())
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Settings)
+                .with_system(settings::exit_settings_system.system())
+        )
+
         // Restart game
         .add_system_set(
             SystemSet::on_enter(GameState::Reset)
                 .with_system(reset_score_system.system().before(Label::RestartGameSystem))
-                .with_system(reset_ben_system.system().before(Label::RestartGameSystem))
-                .with_system(reset_caleb_system.system().before(Label::RestartGameSystem))
-                .with_system(reset_harris_system.system().before(Label::RestartGameSystem))
-                .with_system(reset_claflin_system.system().before(Label::RestartGameSystem))
-                .with_system(reset_samson_system.system().before(Label::RestartGameSystem))
+                .with_system(reset_ben_lives_system.system().before(Label::RestartGameSystem))
+                .with_system(players::reset_players_system.system().before(Label::RestartGameSystem))
+                .with_system(versus::reset_versus_system.system().before(Label::RestartGameSystem))
+                .with_system(rewind::reset_rewind_system.system().before(Label::RestartGameSystem))
                 .with_system(reset_dots_and_power_ups_system.system().before(Label::RestartGameSystem))
                 .with_system(reset_ghost_release_timer.system().before(Label::RestartGameSystem))
                 .with_system(reset_end_message_text.system().before(Label::RestartGameSystem))
+                .with_system(reset_scare_system.system().before(Label::RestartGameSystem))
+                .with_system(reset_maze_flash_system.system().before(Label::RestartGameSystem))
                 .with_system(restart_game_system.system().label(Label::RestartGameSystem))
         )
 
+        // Next level, with the maze refilled
+        .add_system_set(
+            SystemSet::on_enter(GameState::NextLevel)
+                .with_system(reset_dots_and_power_ups_system.system().before(Label::NextLevelSystem))
+                .with_system(reset_ghost_release_timer.system().before(Label::NextLevelSystem))
+                .with_system(reset_scare_system.system().before(Label::NextLevelSystem))
+                .with_system(reset_maze_flash_system.system().before(Label::NextLevelSystem))
+                .with_system(next_level_system.system().label(Label::NextLevelSystem))
+        )
+
         // Miscellaneous
-        .add_system(power_up_animation_system.system())
+        .add_system(animation::animation_system.system())
+        .add_system(maze_flash_system.system())
         .add_system(score_system.system())
+        .add_system(hud::high_score_system.system())
+        .add_system(hud::up_blink_system.system())
+        .add_system(hud::life_icons_system.system())
+        .add_system(hud::fruit_row_system.system())
+        .add_system(view::board_view_system.system())
+        .add_system(view::fullscreen_toggle_system.system())
+        .add_system(view::window_mode_system.system())
+        .add_system(popup::spawn_score_popup_system.system())
+        .add_system(popup::score_popup_system.system())
         .add_system(background_music_system.system())
+        .add_system(save::apply_save_system.system())
+        .add_system(practice::practice_speed_system.system())
+        .add_system(practice::practice_panel_system.system())
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            sync_transform_system.system().before(bevy::transform::TransformSystem::TransformPropagate)
+        )
         
         // Plugins
         .add_plugins(DefaultPlugins)
@@ -140,6 +398,8 @@ fn main() {
 fn setup(
     mut commands: Commands,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
     asset_server: Res<AssetServer>,
     board: Res<Board>,
 ) {
@@ -149,65 +409,58 @@ fn setup(
             window_origin: WindowOrigin::BottomLeft,
             ..Default::default()
         },
+        // Leaves room for the bottom HUD bar below the maze until `view::board_view_system` fits it to the window
+        transform: Transform::from_xyz(0., -constants::HUD_BOTTOM_HEIGHT, 1000. - 0.1),
         ..OrthographicCameraBundle::new_2d()
-    });
-
-    // Board
-    let board_material_handle = materials.add(asset_server.load("board.png").into());
-    commands.spawn_bundle(SpriteBundle {
-        material: board_material_handle.clone(),
-        transform: Transform {
-            translation: Vec3::new(board.width() as f32 * board.cell_size() / 2., board.height() as f32 * board.cell_size() / 2., 1.),
-            ..Default::default()
-        },
-        ..Default::default()
+    })
+    .insert(view::BoardCamera);
+
+    // Walls
+    let maze_theme = MazeTheme::from_board(&board);
+    let maze_texture = textures.add(maze::render_walls(&board));
+    let wall_materials = WallMaterials {
+        maze: materials.add(ColorMaterial::modulated_texture(maze_texture.clone(), maze_theme.wall)),
+        maze_texture,
+        ghost_gate: materials.add(maze_theme.gate.into())
+    };
+    utils::init_walls(&mut commands, &board, &wall_materials);
+    commands.insert_resource(wall_materials);
+    commands.insert_resource(maze_theme);
+
+    // Level editor markers
+    commands.insert_resource(EditorMaterials {
+        fruit: materials.add(Color::rgb(0.87, 0.13, 0.13).into()),
+        ben_spawn: materials.add(Color::rgba(1., 1., 0., 0.6).into()),
+        ghost_house: materials.add(Color::rgba(1., 0.72, 0.87, 0.6).into()),
+        ghost_exit: materials.add(Color::rgba(0., 1., 1., 0.6).into())
     });
 
     // Dots and power-ups
     let dot_material = DotMaterial {
         handle: materials.add(asset_server.load("cookie.png").into())
     };
-    let power_up_materials = PowerUpMaterials {
-        material_1: materials.add(asset_server.load("arizona_1.png").into()),
-        material_2: materials.add(asset_server.load("arizona_2.png").into()),
+    let power_up_sprite_sheet = PowerUpSpriteSheet {
+        atlas: animation::load_sprite_sheet(&asset_server, &mut texture_atlases, "power_up_sheet.png", Vec2::new(1000., 1000.), power_up::SHEET_COLUMNS)
     };
-    utils::init_dots_and_power_ups(&mut commands, &board, dot_material.handle.clone(), power_up_materials.material_1.clone());
+    utils::init_dots_and_power_ups(&mut commands, &board, dot_material.handle.clone(), power_up_sprite_sheet.atlas.clone(), &HashSet::new());
     commands.insert_resource(dot_material);
-    commands.insert_resource(power_up_materials);
+    commands.insert_resource(power_up_sprite_sheet);
 
     // Ben
-    let ben_materials = BenMaterials {
-        ben_default: materials.add(asset_server.load("ben/ben.png").into()),
-        ben_up: materials.add(asset_server.load("ben/ben_up.png").into()),
-        ben_right: materials.add(asset_server.load("ben/ben_right.png").into()),
-        ben_down: materials.add(asset_server.load("ben/ben_down.png").into()),
-        ben_left: materials.add(asset_server.load("ben/ben_left.png").into()),
+    let ben_sprite_sheet = BenSpriteSheet {
+        atlas: animation::load_sprite_sheet(&asset_server, &mut texture_atlases, "ben/ben_sheet.png", Vec2::new(256., 256.), ben::SHEET_COLUMNS)
     };
-    let (ben_init_x, ben_init_y) = utils::get_ben_spawn_coordinates(&board);
-    commands.spawn_bundle(BenBundle {
-        sprite_bundle: SpriteBundle {
-            material: ben_materials.ben_default.clone(),
-            transform: Transform {
-                translation: Vec3::new(ben_init_x, ben_init_y, 10.),
-                scale: Vec3::new(1./6., 1./6., 1.),
-                ..Default::default()
-            },
-            ..Default::default()
-        },
-        ..Default::default()
-    });
-    commands.insert_resource(ben_materials);
+    ben::spawn_ben(&mut commands, &board, ben_sprite_sheet.atlas.clone(), 0);
+    commands.insert_resource(ben_sprite_sheet);
 
     // Caleb
-    let caleb_materials = CalebMaterials {
-        default_material: materials.add(asset_server.load("ghosts/caleb.png").into()),
-        scared_material: materials.add(asset_server.load("ghosts/caleb_scared.png").into()),
-    };
+    let caleb_atlas = animation::load_sprite_sheet(&asset_server, &mut texture_atlases, "ghosts/caleb_sheet.png", Vec2::new(256., 256.), ghost::SHEET_COLUMNS);
     let (caleb_init_x, caleb_init_y) = utils::get_caleb_spawn_coordinates(&board);
     commands.spawn_bundle(CalebBundle {
         ghost_bundle: GhostBundle {
-            sprite_bundle: SpriteBundle {
-                material: caleb_materials.default_material.clone(),
+            position: TilePosition::from_coordinates(&board, caleb_init_x, caleb_init_y),
+            sprite_sheet_bundle: SpriteSheetBundle {
+                texture_atlas: caleb_atlas,
                 transform: Transform {
                     translation: Vec3::new(caleb_init_x, caleb_init_y, 9.),
                     scale: Vec3::new(1./6., 1./6., 1.),
@@ -220,18 +473,16 @@ fn setup(
         },
         ..Default::default()
     });
-    commands.insert_resource(caleb_materials);
 
     // Harris 
-    let harris_materials = HarrisMaterials {
-        default_material: materials.add(asset_server.load("ghosts/sam_h.png").into()),
-        scared_material: materials.add(asset_server.load("ghosts/sam_h_scared.png").into()),
-    };
+    let harris_atlas = animation::load_sprite_sheet(&asset_server, &mut texture_atlases, "ghosts/sam_h_sheet.png", Vec2::new(256., 256.), ghost::SHEET_COLUMNS);
     let (harris_init_x, harris_init_y) = utils::get_harris_spawn_coordinates(&board);
     commands.spawn_bundle(HarrisBundle {
         ghost_bundle: GhostBundle {
-            sprite_bundle: SpriteBundle {
-                material: harris_materials.default_material.clone(),
+            position: TilePosition::from_coordinates(&board, harris_init_x, harris_init_y),
+            release_priority: GhostReleasePriority(2),
+            sprite_sheet_bundle: SpriteSheetBundle {
+                texture_atlas: harris_atlas,
                 transform: Transform {
                     translation: Vec3::new(harris_init_x, harris_init_y, 9.),
                     scale: Vec3::new(1./6., 1./6., 1.),
@@ -243,18 +494,16 @@ fn setup(
         },
         ..Default::default()
     });
-    commands.insert_resource(harris_materials);
 
     // Claflin 
-    let claflin_materials = ClaflinMaterials {
-        default_material: materials.add(asset_server.load("ghosts/sam_c.png").into()),
-        scared_material: materials.add(asset_server.load("ghosts/sam_c_scared.png").into()),
-    };
+    let claflin_atlas = animation::load_sprite_sheet(&asset_server, &mut texture_atlases, "ghosts/sam_c_sheet.png", Vec2::new(256., 256.), ghost::SHEET_COLUMNS);
     let (claflin_init_x, claflin_init_y) = utils::get_claflin_spawn_coordinates(&board);
     commands.spawn_bundle(ClaflinBundle {
         ghost_bundle: GhostBundle {
-            sprite_bundle: SpriteBundle {
-                material: claflin_materials.default_material.clone(),
+            position: TilePosition::from_coordinates(&board, claflin_init_x, claflin_init_y),
+            release_priority: GhostReleasePriority(1),
+            sprite_sheet_bundle: SpriteSheetBundle {
+                texture_atlas: claflin_atlas,
                 transform: Transform {
                     translation: Vec3::new(claflin_init_x, claflin_init_y, 9.),
                     scale: Vec3::new(1./6., 1./6., 1.),
@@ -266,18 +515,16 @@ fn setup(
         },
         ..Default::default()
     });
-    commands.insert_resource(claflin_materials);
 
     // Samson 
-    let samson_materials = SamsonMaterials {
-        default_material: materials.add(asset_server.load("ghosts/samson.png").into()),
-        scared_material: materials.add(asset_server.load("ghosts/samson_scared.png").into()),
-    };
+    let samson_atlas = animation::load_sprite_sheet(&asset_server, &mut texture_atlases, "ghosts/samson_sheet.png", Vec2::new(256., 256.), ghost::SHEET_COLUMNS);
     let (samson_init_x, samson_init_y) = utils::get_samson_spawn_coordinates(&board);
     commands.spawn_bundle(SamsonBundle {
         ghost_bundle: GhostBundle {
-            sprite_bundle: SpriteBundle {
-                material: samson_materials.default_material.clone(),
+            position: TilePosition::from_coordinates(&board, samson_init_x, samson_init_y),
+            release_priority: GhostReleasePriority(3),
+            sprite_sheet_bundle: SpriteSheetBundle {
+                texture_atlas: samson_atlas,
                 transform: Transform {
                     translation: Vec3::new(samson_init_x, samson_init_y, 9.),
                     scale: Vec3::new(1./6., 1./6., 1.),
@@ -289,33 +536,11 @@ fn setup(
         },
         ..Default::default()
     });
-    commands.insert_resource(samson_materials);
 
-    // Score and start message
+    // Font, the HUD is set up in `hud::setup_hud`
     let font_material = misc::FontMaterial {
         handle: asset_server.load("font.ttf")
     };
-    let font = font_material.handle.clone();
-    let text_style = TextStyle {
-        font,
-        font_size: 35.,
-        color: Color::WHITE
-    };
-    let text_alignment = TextAlignment {
-        vertical: VerticalAlign::Top,
-        horizontal: HorizontalAlign::Center
-    };
-    commands.spawn_bundle(ScoreBundle {
-        text_bundle: Text2dBundle {
-            text: Text::with_section("", text_style, text_alignment),
-            transform: Transform {
-                translation: Vec3::new(board.width() as f32 * board.cell_size() / 2., board.height() as f32 * board.cell_size(), 100.),
-                ..Default::default()
-            },
-            ..Default::default()
-        },
-        ..Default::default()
-    });
     commands.insert_resource(font_material);
 
     // Sounds
@@ -330,16 +555,32 @@ fn setup(
     commands.insert_resource(misc::BackgroundMusicTimer(Timer::from_seconds(constants::BACKGROUND_MUSIC_DURATION_SECONDS, false)));
 }
 
+fn start_message(practice: &Practice) -> String {
+    let continue_line = if std::path::Path::new(save::SAVE_PATH).exists() { "Press L to continue\n" } else { "" };
+    format!(
+        "{}Press space to start\nPress 2 for two players\nPress C for co-op\nPress V for versus\nPress A for attract mode\nPress E to edit the level\nPress O for settings\nPress P for practice: {}",
+        continue_line,
+        if practice.active { "ON" } else { "OFF" }
+    )
+}
+
 fn wait_for_game_start(
     mut commands: Commands,
     mut game_state: ResMut<State<GameState>>,
-    query: Query<Entity, With<misc::StartMessage>>,
+    mut sequence: ResMut<Sequence>,
+    mut players: ResMut<Players>,
+    mut versus: ResMut<Versus>,
+    mut practice: ResMut<Practice>,
+    mut query: Query<(Entity, &mut Text), With<misc::StartMessage>>,
+    ben_query: Query<(Entity, &PlayerId), With<Ben>>,
+    ghost_query: Query<(Entity, Option<&Samson>), With<Ghost>>,
     keys: Res<Input<KeyCode>>,
     board: Res<Board>,
-    font_material: Res<misc::FontMaterial>
+    font_material: Res<misc::FontMaterial>,
+    ben_sprite_sheet: Res<BenSpriteSheet>
 ) {
     let mut start_message_exists = false;
-    for _ in query.iter() {
+    for _ in query.iter_mut() {
         start_message_exists = true;
     }
 
@@ -354,7 +595,7 @@ fn wait_for_game_start(
             horizontal: HorizontalAlign::Center
         };
         commands.spawn_bundle(Text2dBundle {
-            text: Text::with_section("Press space to start", text_style, text_alignment),
+            text: Text::with_section(start_message(&practice), text_style, text_alignment),
             transform: Transform {
                 translation: Vec3::new(board.width() as f32 * board.cell_size() / 2., board.height() as f32 * board.cell_size() / 2. + 256., 100.),
                 ..Default::default()
@@ -363,132 +604,175 @@ fn wait_for_game_start(
         })
         .insert(misc::StartMessage);
     } else {
-        let start_message_entity = query.single().unwrap();
-        if keys.just_pressed(KeyCode::Space) {
+        let (start_message_entity, mut start_message_text) = query.single_mut().unwrap();
+
+        // Practice carries over into whichever mode is picked next
+        if keys.just_pressed(KeyCode::P) {
+            practice.active = !practice.active;
+            start_message_text.sections[0].value = start_message(&practice);
+        }
+
+        // Continuing picks up the saved game, which has to be of this level
+        let save_state = if keys.just_pressed(KeyCode::L) {
+            SaveState::load(save::SAVE_PATH, &board).map_err(|err| eprintln!("{}", err)).ok()
+        } else {
+            None
+        };
+        let modes = [KeyCode::Space, KeyCode::Key2, KeyCode::C, KeyCode::V, KeyCode::A];
+        if modes.iter().any(|key| keys.just_pressed(*key)) || save_state.is_some() {
+            commands.entity(start_message_entity).despawn();
+            *players = Players::new(if keys.just_pressed(KeyCode::Key2) { 2 } else { 1 });
+
+            // In versus a player takes over Samson; any ghost given `HumanControlled` would do
+            let versus_mode = keys.just_pressed(KeyCode::V);
+            *versus = if versus_mode { Versus::new(constants::VERSUS_BEST_OF) } else { Versus::default() };
+            for (ghost_entity, samson) in ghost_query.iter() {
+                if versus_mode && samson.is_some() {
+                    commands.entity(ghost_entity).insert(HumanControlled::default());
+                } else {
+                    commands.entity(ghost_entity).remove::<HumanControlled>();
+                }
+            }
+
+            // Co-op adds a second Ben, which is gone again in any other mode
+            let coop = keys.just_pressed(KeyCode::C) || save_state.as_ref().is_some_and(|save_state| save_state.bens.len() > 1);
+            let mut has_second_ben = false;
+            for (ben_entity, player_id) in ben_query.iter() {
+                if player_id.0 == 1 {
+                    has_second_ben = true;
+                    if !coop {
+                        commands.entity(ben_entity).despawn();
+                    }
+                }
+            }
+            if coop && !has_second_ben {
+                ben::spawn_ben(&mut commands, &board, ben_sprite_sheet.atlas.clone(), 1);
+            }
+
+            // Attract mode plays itself
+            for (ben_entity, _) in ben_query.iter() {
+                if keys.just_pressed(KeyCode::A) {
+                    commands.entity(ben_entity).insert(Autopilot::default());
+                } else {
+                    commands.entity(ben_entity).remove::<Autopilot>();
+                }
+            }
+
+            *sequence = match save_state {
+                Some(save_state) => {
+                    commands.insert_resource(PendingLoad(save_state));
+                    Sequence::resume()
+                },
+                None => Sequence::level_start()
+            };
+            game_state.set(GameState::Sequence).unwrap();
+        } else if keys.just_pressed(KeyCode::E) {
             commands.entity(start_message_entity).despawn();
-            game_state.set(GameState::Default).unwrap();
+            game_state.set(GameState::Editor).unwrap();
+        } else if keys.just_pressed(KeyCode::O) {
+            commands.entity(start_message_entity).despawn();
+            game_state.set(GameState::Settings).unwrap();
         }
     }
 }
 
 fn ben_controller_system(
-    mut query: Query<&mut BenNextDirection, With<Ben>>,
-    keys: Res<Input<KeyCode>>
+    mut query: Query<(&PlayerId, &mut BenNextDirection, &mut BenInputTimer), (With<Ben>, Without<Autopilot>)>,
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    versus: Res<Versus>
 ) {
-    let mut ben_next_direction = query.single_mut().unwrap();
-    if keys.just_pressed(KeyCode::W) || keys.just_pressed(KeyCode::Up) {
-        ben_next_direction.0 = Some(Direction::Up);
-    } else if keys.just_pressed(KeyCode::D) || keys.just_pressed(KeyCode::Right) {
-        ben_next_direction.0 = Some(Direction::Right);
-    } else if keys.just_pressed(KeyCode::S) || keys.just_pressed(KeyCode::Down) {
-        ben_next_direction.0 = Some(Direction::Down);
-    } else if keys.just_pressed(KeyCode::A) || keys.just_pressed(KeyCode::Left) {
-        ben_next_direction.0 = Some(Direction::Left);
+    // Ben has to share the keyboard in co-op and versus
+    let coop = query.iter_mut().count() > 1 || versus.active;
+    for (player_id, mut ben_next_direction, mut ben_input_timer) in query.iter_mut() {
+        let direction_keys = ben::direction_keys(player_id.0, coop);
+        let is_held = |direction: Direction| direction_keys.iter()
+            .any(|&(key_direction, key)| key_direction == direction && keys.pressed(key));
+        let just_pressed = direction_keys.iter()
+            .find(|&&(_, key)| keys.just_pressed(key))
+            .map(|&(direction, _)| direction);
+
+        // A held key stays queued; once released it is only remembered for the length of the input buffer
+        if let Some(direction) = just_pressed {
+            ben_next_direction.0 = Some(direction);
+            ben_input_timer.0 = Timer::from_seconds(settings.input_buffer_seconds, false);
+        } else if let Some(direction) = ben_next_direction.0 {
+            if is_held(direction) {
+                ben_input_timer.0.reset();
+            } else if !settings.input_buffer || ben_input_timer.0.tick(time.delta()).finished() {
+                ben_next_direction.0 = None;
+            }
+        }
     }
 }
 
 fn ben_movement_system(
-    mut query: Query<(&mut Transform, &mut BenNextDirection , &mut BenDirection, &BenSpeed), With<Ben>>,
+    mut query: Query<(Entity, &mut TilePosition, &mut BenNextDirection , &mut BenDirection, &BenSpeed, &BenLives), With<Ben>>,
     mut direction_changed_event: EventWriter<BenDirectionChangedEvent>,
-    board: Res<Board>
+    board: Res<Board>,
+    settings: Res<Settings>
 ) {
-    let (mut transform, mut ben_next_direction, mut ben_direction, ben_speed) = query.single_mut().unwrap();
-    let speed = ben_speed.0;
-    let can_move_up = utils::can_move_up(&transform, &board, speed);
-    let can_move_right = utils::can_move_right(&transform, &board, speed);
-    let can_move_down = utils::can_move_down(&transform, &board, speed);
-    let can_move_left = utils::can_move_left(&transform, &board, speed);
-
-    // Determine if the direction needs to be changed
-    let next_direction = ben_next_direction.0;
-    if next_direction.is_some() {
-        let initial_direction = ben_direction.0;
-        match next_direction.unwrap() {
-            Direction::Up => {
-                if can_move_up {
-                    ben_direction.0 = Direction::Up;
-                }
-            },
-            Direction::Right => {
-                if can_move_right {
-                    ben_direction.0 = Direction::Right;
-                }
-            },
-            Direction::Down => {
-                if can_move_down {
-                    ben_direction.0 = Direction::Down;
-                }
-            },
-            Direction::Left => {
-                if can_move_left {
-                    ben_direction.0 = Direction::Left;
-                }
-            },
+    for (ben_entity, mut position, mut ben_next_direction, mut ben_direction, ben_speed, ben_lives) in query.iter_mut() {
+        if ben_lives.0 == 0 {
+            continue;
         }
 
+        let initial_direction = ben_direction.0;
+        ben_direction.0 = ben::advance(
+            &board,
+            &mut position,
+            ben_direction.0,
+            &mut ben_next_direction.0,
+            movement::speed_units(ben_speed.0),
+            settings.cornering,
+            settings.instant_reversal
+        );
+
         // Direction changed -> publish event
         if ben_direction.0 != initial_direction {
-            direction_changed_event.send(BenDirectionChangedEvent(ben_direction.0));
-            ben_next_direction.0 = None;
+            direction_changed_event.send(BenDirectionChangedEvent(ben_entity, ben_direction.0));
         }
     }
-
-    // Perform the movement
-    let direction = ben_direction.0;
-    let (target_x, target_y) = board.get_coordinates(transform.translation.x, transform.translation.y, direction, speed);
-    if can_move_up && direction == Direction::Up
-    || can_move_right && direction == Direction::Right
-    || can_move_down && direction == Direction::Down
-    || can_move_left && direction == Direction::Left {
-        transform.translation.x = target_x;
-        transform.translation.y = target_y;
-    } 
 }
 
 fn ben_animation_system(
-    mut query: Query<(&mut Handle<ColorMaterial>, &mut BenAnimationTimer, &BenDirection), With<Ben>>,
-    mut event_direction_changed: EventReader<BenDirectionChangedEvent>,
-    time: Res<Time>,
-    ben_materials: Res<BenMaterials>
+    mut query: Query<(&mut Animation, &BenDirection), With<Ben>>,
+    mut event_direction_changed: EventReader<BenDirectionChangedEvent>
 ) {
-    let (mut material_handle, mut ben_animation_timer, ben_direction) = query.single_mut().unwrap();
-
-    // Always update the sprite if the direction was just changed
-    for event in event_direction_changed.iter() {
-        utils::update_ben_sprite(&mut material_handle, event.0, &ben_materials);
-        return;
-    }
-
-    // Throttle the refresh rate
-    let timer = &mut ben_animation_timer.0;
-    timer.tick(time.delta());
-    if !timer.finished() {
-        return;
+    // Start chomping as soon as the game starts, then follow every turn
+    for (mut animation, ben_direction) in query.iter_mut() {
+        if animation.clip() == animation::IDLE {
+            animation.play(animation::walk_clip(ben_direction.0));
+        }
     }
-
-    if material_handle.id != ben_materials.ben_default.id {
-        *material_handle = ben_materials.ben_default.clone();
-    } else {
-        utils::update_ben_sprite(&mut material_handle, ben_direction.0, &ben_materials);
+    for event in event_direction_changed.iter() {
+        if let Ok((mut animation, _)) = query.get_mut(event.0) {
+            animation.play(animation::walk_clip(event.1));
+        }
     }
 }
 
 fn ben_dot_collision_system(
     mut commands: Commands,
-    mut query_set: QuerySet<(
-        Query<&Transform, With<Ben>>,
-        Query<(Entity, &Transform), With<Dot>>,
-        Query<&mut Score>,
-    )>,
-    board: Res<Board>,
+    mut ben_query: Query<(&TilePosition, &BenLives, &mut Score), With<Ben>>,
+    dot_query: Query<(Entity, &TilePosition), With<Dot>>,
+    mut dot_consumed_event: EventWriter<DotConsumedEvent>,
     point_values: Res<PointValues>,
 ) {
-    let ben_transform = query_set.q0().single().unwrap().clone();
-    if utils::is_centered_horizontally(&ben_transform, &board) && utils::is_centered_vertically(&ben_transform, &board) {
-        for (dot_entity, dot_transform) in query_set.q1().iter() {
-            if dot_transform.translation.x == ben_transform.translation.x && dot_transform.translation.y == ben_transform.translation.y {
+    // Dots are eaten as soon as Ben enters their tile, and count for whoever got there first
+    let mut eaten = HashSet::new();
+    for (ben_position, ben_lives, mut score) in ben_query.iter_mut() {
+        if ben_lives.0 == 0 {
+            continue;
+        }
+
+        let ben_tile = ben_position.tile();
+        for (dot_entity, dot_position) in dot_query.iter() {
+            if dot_position.tile() == ben_tile && eaten.insert(dot_entity) {
                 commands.entity(dot_entity).despawn();
-                query_set.q2_mut().single_mut().unwrap().0 += point_values.dot;
+                score.0 += point_values.dot;
+                dot_consumed_event.send(DotConsumedEvent);
                 break;
             }
         }
@@ -497,26 +781,64 @@ fn ben_dot_collision_system(
 
 fn ben_power_up_collision_system(
     mut commands: Commands,
-    mut query_set: QuerySet<(
-        Query<&Transform, With<Ben>>,
-        Query<(Entity, &Transform), With<PowerUp>>,
-        Query<&mut Score>
-    )>,
+    mut ben_query: Query<(&TilePosition, &BenLives, &mut Score), With<Ben>>,
+    power_up_query: Query<(Entity, &TilePosition), With<PowerUp>>,
     mut power_up_consumed_event: EventWriter<PowerUpConsumedEvent>,
+    mut score_popup_event: EventWriter<ScorePopupEvent>,
     mut ghost_chain: ResMut<GhostChain>,
-    board: Res<Board>,
     point_values: Res<PointValues>,
     sound_materials: Res<misc::SoundMaterials>,
     audio: Res<Audio>
 ) {
-    let ben_transform = query_set.q0().single().unwrap().clone();
-    if utils::is_centered_horizontally(&ben_transform, &board) && utils::is_centered_vertically(&ben_transform, &board) {
-        for (power_up_entity, power_up_transform) in query_set.q1_mut().iter_mut() {
-            if power_up_transform.translation.x == ben_transform.translation.x && power_up_transform.translation.y == ben_transform.translation.y {
+    let mut eaten = HashSet::new();
+    for (ben_position, ben_lives, mut score) in ben_query.iter_mut() {
+        if ben_lives.0 == 0 {
+            continue;
+        }
+
+        let ben_tile = ben_position.tile();
+        for (power_up_entity, power_up_position) in power_up_query.iter() {
+            if power_up_position.tile() == ben_tile && eaten.insert(power_up_entity) {
                 commands.entity(power_up_entity).despawn();
-                query_set.q2_mut().single_mut().unwrap().0 += point_values.power_up;
+                score.0 += point_values.power_up;
                 ghost_chain.0 = 0;
                 power_up_consumed_event.send(PowerUpConsumedEvent);
+                score_popup_event.send(ScorePopupEvent {
+                    points: point_values.power_up,
+                    position: *power_up_position
+                });
+                audio.play(sound_materials.slurp_sound.clone());
+                break;
+            }
+        }
+    }
+}
+
+fn ben_fruit_collision_system(
+    mut commands: Commands,
+    mut ben_query: Query<(&TilePosition, &BenLives, &mut Score), With<Ben>>,
+    fruit_query: Query<(Entity, &TilePosition, &FruitPickup)>,
+    mut score_popup_event: EventWriter<ScorePopupEvent>,
+    point_values: Res<PointValues>,
+    sound_materials: Res<misc::SoundMaterials>,
+    audio: Res<Audio>
+) {
+    let mut eaten = HashSet::new();
+    for (ben_position, ben_lives, mut score) in ben_query.iter_mut() {
+        if ben_lives.0 == 0 {
+            continue;
+        }
+
+        let ben_tile = ben_position.tile();
+        for (fruit_entity, fruit_position, fruit_pickup) in fruit_query.iter() {
+            if fruit_position.tile() == ben_tile && eaten.insert(fruit_entity) {
+                commands.entity(fruit_entity).despawn();
+                let fruit_points = point_values.fruit(fruit_pickup.0);
+                score.0 += fruit_points;
+                score_popup_event.send(ScorePopupEvent {
+                    points: fruit_points,
+                    position: *fruit_position
+                });
                 audio.play(sound_materials.slurp_sound.clone());
                 break;
             }
@@ -525,102 +847,125 @@ fn ben_power_up_collision_system(
 }
 
 fn ben_ghost_collision_system(
+    mut commands: Commands,
     mut game_state: ResMut<State<GameState>>,
+    mut sequence: ResMut<Sequence>,
     mut query_set: QuerySet<(
-        Query<&Transform, With<Ben>>,
-        Query<(&Transform, &AttackState, &mut ReleaseState, &mut GhostPath), With<Ghost>>,
-        Query<&mut Score>
+        Query<(Entity, &TilePosition, &PreviousTilePosition, &mut BenLives, &mut Score), With<Ben>>,
+        Query<(Entity, &TilePosition, &PreviousTilePosition, &mut AttackState, &mut ReleaseState, &mut GhostPath, Option<&HumanControlled>), With<Ghost>>
     )>,
     mut ghost_chain: ResMut<GhostChain>,
+    mut versus: ResMut<Versus>,
+    mut ghost_house: ResMut<GhostHouse>,
     mut end_message_text: ResMut<misc::EndMessageText>,
+    mut score_popup_event: EventWriter<ScorePopupEvent>,
+    (players, practice): (Res<Players>, Res<Practice>),
     board: Res<Board>,
+    collision_radius: Res<CollisionRadius>,
     point_values: Res<PointValues>,
     sound_materials: Res<misc::SoundMaterials>,
     audio: Res<Audio>
 ) {
-    let ben_transform = query_set.q0().single().unwrap().clone();
-    let mut points = 0;
-    for (ghost_transform, attack_state, mut release_state, mut ghost_path) in query_set.q1_mut().iter_mut() {
-        if utils::did_collide(ghost_transform, &ben_transform, &board, CollisionType::Approximate) {
-            match attack_state {
-                AttackState::Attacking => {
-                    game_state.set(GameState::End).unwrap();
-                    end_message_text.0 = "Fat And\nImmeasurably\nCringe".to_string();
-                    audio.play(sound_materials.ben_death_sound.clone())
-                },
-                AttackState::Scared => {
-                    if *release_state == ReleaseState::Respawning {
-                        continue;
+    let (ben_entities, bens): (Vec<Entity>, Vec<ghost::Movement>) = query_set.q0_mut().iter_mut()
+        .filter(|(_, _, _, ben_lives, _)| ben_lives.0 > 0)
+        .map(|(ben_entity, position, previous_position, _, _)| (ben_entity, (previous_position.0, *position)))
+        .unzip();
+    let (ghost_entities, ghosts): (Vec<Entity>, Vec<(ghost::Movement, ReleaseState)>) = query_set.q1_mut().iter_mut()
+        .map(|(ghost_entity, position, previous_position, _, release_state, _, _)| (ghost_entity, ((previous_position.0, *position), *release_state)))
+        .unzip();
+
+    let mut caught = Vec::new();
+    let mut human_catches = Vec::new();
+    let mut points = Vec::new();
+    for (ghost, ben) in ghost::touches(&board, collision_radius.0, &bens, &ghosts) {
+        let ben_entity = ben_entities[ben];
+        let (_, ghost_position, _, mut attack_state, mut release_state, mut ghost_path, human_controlled) = query_set.q1_mut().get_mut(ghost_entities[ghost]).unwrap();
+        match *attack_state {
+            // Practice can make Ben walk straight through them
+            AttackState::Attacking if practice.is_invincible() => { },
+            AttackState::Attacking => {
+                if !caught.contains(&ben_entity) {
+                    caught.push(ben_entity);
+                    if human_controlled.is_some() {
+                        human_catches.push(bens[ben].1);
                     }
-
-                    *release_state = ReleaseState::Respawning;
-                    points += match ghost_chain.0 {
-                        0 => point_values.first_ghost,
-                        1 => point_values.second_ghost,
-                        2 => point_values.third_ghost,
-                        _ => point_values.fourth_ghost
-                    };
-                    ghost_chain.0 += 1;
-                    ghost_path.0.clear();
-                    audio.play(sound_materials.ghost_death_sound.clone());
                 }
+            },
+            AttackState::Scared => {
+                let ghost_points = ghost::eat(&mut release_state, &mut attack_state, &mut ghost_path.0, &mut ghost_chain.0, &point_values);
+                points.push((ben_entity, ghost_points));
+                score_popup_event.send(ScorePopupEvent {
+                    points: ghost_points,
+                    position: *ghost_position
+                });
+                audio.play(sound_materials.ghost_death_sound.clone());
             }
         }
     }
 
-    let score = &mut query_set.q2_mut().single_mut().unwrap();
-    score.0 += points;
-}
+    // Points go to whoever ate the ghost
+    for (ben_entity, ghost_points) in points.iter() {
+        let (_, _, _, _, mut score) = query_set.q0_mut().get_mut(*ben_entity).unwrap();
+        score.0 += ghost_points;
+    }
 
-fn power_up_animation_system(
-    mut query: Query<(&mut Handle<ColorMaterial>, &mut PowerUpAnimationTimer), With<PowerUp>>,
-    power_up_materials: Res<PowerUpMaterials>,
-    time: Res<Time>
-) {
-    for (mut material_handle, mut power_up_animation_timer) in query.iter_mut() {
-        let timer = &mut power_up_animation_timer.0; 
-        timer.tick(time.delta());
-        if !timer.finished() {
-            continue;
+    // Runs after `win_system`, so clearing the maze on the same frame wins: Ben keeps his life and the freeze is skipped
+    if !caught.is_empty() {
+        if game_state.set(GameState::Sequence).is_err() {
+            return;
         }
-
-        *material_handle = if material_handle.id == power_up_materials.material_1.id {
-            power_up_materials.material_2.clone()
-        } else {
-            power_up_materials.material_1.clone()
+        for position in human_catches {
+            versus.ghost_score += constants::VERSUS_CATCH_POINTS;
+            score_popup_event.send(ScorePopupEvent {
+                points: constants::VERSUS_CATCH_POINTS,
+                position
+            });
+        }
+        for ben_entity in caught.iter() {
+            let (_, _, _, mut ben_lives, _) = query_set.q0_mut().get_mut(*ben_entity).unwrap();
+            ben_lives.0 -= 1;
+            commands.entity(*ben_entity).insert(Caught);
         }
+        ghost_house.life_lost();
+
+        // Co-op carries on as long as any Ben has a life left
+        let lives: u8 = query_set.q0_mut().iter_mut().map(|(_, _, _, ben_lives, _)| ben_lives.0).sum();
+        let next_player = players.next_player(lives);
+        *sequence = Sequence::death(next_player.is_none(), next_player != Some(players.current));
+        end_message_text.0 = "Fat And\nImmeasurably\nCringe".to_string();
+    } else if !points.is_empty() && game_state.set(GameState::Sequence).is_ok() {
+        *sequence = Sequence::ghost_eaten();
     }
 }
 
 fn scare_ghosts_system(
-    mut query: Query<&mut AttackState, With<Ghost>>,
+    mut query: Query<(&mut AttackState, &ReleaseState), With<Ghost>>,
     mut power_up_consumed_event: EventReader<PowerUpConsumedEvent>,
     mut ghost_scare_timer: ResMut<GhostScareTimer>,
+    practice: Res<Practice>,
     time: Res<Time>
 ) {
     let timer = &mut ghost_scare_timer.0;
 
     for _ in power_up_consumed_event.iter() {
         timer.reset();
-        for mut attack_state in query.iter_mut() {
-            if *attack_state == AttackState::Attacking {
-                *attack_state = AttackState::Scared;
-            }
+        for (mut attack_state, release_state) in query.iter_mut() {
+            ghost::scare(&mut attack_state, *release_state);
         }
     }
 
     let mut scared = false;
-    for attack_state in query.iter_mut() {
+    for (attack_state, _) in query.iter_mut() {
         if *attack_state == AttackState::Scared {
             scared = true;
             break;
         }
     }
 
-    if scared {
+    if scared && !practice.has_infinite_power_ups() {
         timer.tick(time.delta());
         if timer.finished() {
-            for mut attack_state in query.iter_mut() {
+            for (mut attack_state, _) in query.iter_mut() {
                 if *attack_state == AttackState::Scared {
                     *attack_state = AttackState::Attacking; 
                 }
@@ -631,89 +976,88 @@ fn scare_ghosts_system(
     }
 }
 
+fn cruise_elroy_system(
+    mut query: Query<&mut CruiseElroy, With<Caleb>>,
+    dot_query: Query<&Dot>,
+    elroy_thresholds: Res<ElroyThresholds>
+) {
+    let stage = elroy_thresholds.stage(dot_query.iter().count());
+    let mut cruise_elroy = query.single_mut().unwrap();
+    if cruise_elroy.0 != stage.0 {
+        *cruise_elroy = stage;
+    }
+}
+
 fn ghost_movement_system(
     mut query_set: QuerySet<(
-        Query<(&mut Transform, &mut GhostPath, &GhostSpeed, &ReleaseState), With<Ghost>>,
-        Query<&Transform, With<Ben>>
+        Query<(&mut TilePosition, &mut GhostPath, &GhostSpeed, &ReleaseState, &AttackState, Option<&CruiseElroy>), (With<Ghost>, Without<HumanControlled>)>,
+        Query<(&TilePosition, &BenLives), With<Ben>>
     )>,
     board: Res<Board>,
+    ghost_tunnel_speed: Res<GhostTunnelSpeed>,
+    practice: Res<Practice>
 ) {
-    let ben_transform = query_set.q1().single().unwrap().clone(); 
-    for (mut caleb_transform, mut ghost_path, ghost_speed, release_state) in query_set.q0_mut().iter_mut() {
+    if practice.are_ghosts_frozen() {
+        return;
+    }
+
+    let ben_tiles: Vec<(usize, usize)> = query_set.q1().iter()
+        .filter(|(_, ben_lives)| ben_lives.0 > 0)
+        .map(|(ben_position, _)| ben_position.tile())
+        .collect();
+    for (mut position, mut ghost_path, ghost_speed, release_state, attack_state, cruise_elroy) in query_set.q0_mut().iter_mut() {
         if *release_state != ReleaseState::Released {
             continue; 
         }
 
-        if let Some((x, y)) = ghost_path.0.pop_front() {
-            caleb_transform.translation.x = x;
-            caleb_transform.translation.y = y;
-        } else {
-            ghost_path.0 = Path::shortest_to_transform(
-                &caleb_transform, 
-                &ben_transform, 
-                &board, 
-                ghost_speed.0, 
-                CollisionType::Approximate
-            );
-        }
-    }
-}
-
-fn caleb_animation_system(
-    mut query: Query<(&mut Handle<ColorMaterial>, &AttackState), With<Caleb>>,
-    caleb_materials: Res<CalebMaterials>
-) {
-    let (mut material_handle, attack_state) = query.single_mut().unwrap();
-    *material_handle = match attack_state {
-        AttackState::Attacking => caleb_materials.default_material.clone(),
-        AttackState::Scared => caleb_materials.scared_material.clone(),
-    };
-}
-
-fn harris_animation_system(
-    mut query: Query<(&mut Handle<ColorMaterial>, &AttackState), With<Harris>>,
-    harris_materials: Res<HarrisMaterials>
-) {
-    let (mut material_handle, attack_state) = query.single_mut().unwrap();
-    *material_handle = match attack_state {
-        AttackState::Attacking => harris_materials.default_material.clone(),
-        AttackState::Scared => harris_materials.scared_material.clone(),
-    };
-}
+        let speed = ghost::released_speed(&board, &position, *attack_state, cruise_elroy, ghost_speed.0, ghost_tunnel_speed.0);
 
-fn claflin_animation_system(
-    mut query: Query<(&mut Handle<ColorMaterial>, &AttackState), With<Claflin>>,
-    claflin_materials: Res<ClaflinMaterials>
-) {
-    let (mut material_handle, attack_state) = query.single_mut().unwrap();
-    *material_handle = match attack_state {
-        AttackState::Attacking => claflin_materials.default_material.clone(),
-        AttackState::Scared => claflin_materials.scared_material.clone(),
-    };
+        // With two Bens about, each ghost goes after the closer one
+        ghost::chase(&mut position, &mut ghost_path.0, &board, movement::speed_units(speed), &ben_tiles);
+    }
 }
 
-fn samson_animation_system(
-    mut query: Query<(&mut Handle<ColorMaterial>, &AttackState), With<Samson>>,
-    samson_materials: Res<SamsonMaterials>
+fn ghost_animation_system(
+    mut query: Query<(&mut Animation, &AttackState, &ReleaseState, &GhostFacing), With<Ghost>>,
+    ghost_scare_timer: Res<GhostScareTimer>
 ) {
-    let (mut material_handle, attack_state) = query.single_mut().unwrap();
-    *material_handle = match attack_state {
-        AttackState::Attacking => samson_materials.default_material.clone(),
-        AttackState::Scared => samson_materials.scared_material.clone(),
-    };
+    let timer = &ghost_scare_timer.0;
+    let flashing = timer.duration().as_secs_f32() - timer.elapsed_secs() <= constants::GHOST_FLASH_SECONDS;
+    for (mut animation, attack_state, release_state, facing) in query.iter_mut() {
+        animation.play(ghost::ghost_clip(*release_state, *attack_state, facing.0, flashing));
+    }
 }
 
 fn score_system(
-    mut query: Query<(&mut Text, &Score)>
+    mut query: Query<(&mut Text, &hud::ScoreText)>,
+    ben_query: Query<(&PlayerId, &Score), With<Ben>>,
+    players: Res<Players>,
+    versus: Res<Versus>
 ) {
-    let (mut text, score) = query.single_mut().unwrap();
-    text.sections[0].value = format!("Score: {}", score.0);
+    let ben_players: Vec<usize> = ben_query.iter().map(|(player_id, _)| player_id.0).collect();
+    for (mut text, score_text) in query.iter_mut() {
+        let player = score_text.0;
+
+        // Alternating players share the first Ben
+        let ben_player = if players.count() > 1 { 0 } else { player };
+        let ben_score = ben_query.iter()
+            .find(|(player_id, _)| player_id.0 == ben_player)
+            .map(|(_, score)| score.0);
+        text.sections[0].value = match hud::player_status(player, &players, &ben_players, &versus) {
+            (false, _) => String::new(),
+            (true, _) if versus.active && player == 1 => versus.ghost_score.to_string(),
+            (true, true) => ben_score.unwrap_or(0).to_string(),
+            (true, false) => players.states[player].score.to_string()
+        };
+    }
 }
 
 fn win_system(
     mut game_state: ResMut<State<GameState>>,
+    mut sequence: ResMut<Sequence>,
     mut end_message_text: ResMut<misc::EndMessageText>,
     query: Query<&Dot>,
+    versus: Res<Versus>
 ) {
     let mut did_win = true;
     for _ in query.iter() {
@@ -722,109 +1066,126 @@ fn win_system(
     }
 
     if did_win {
-        game_state.set(GameState::End).unwrap();
-        end_message_text.0 = "Based\nAND\nRed-Pilled".to_string();
+        // Outside versus, clearing the maze goes on to the next level rather than ending the game
+        *sequence = Sequence::level_complete(versus.active);
+        game_state.set(GameState::Sequence).unwrap();
+        if versus.active {
+            end_message_text.0 = "Based\nAND\nRed-Pilled".to_string();
+        }
     }
 }
 
-fn ghost_release_system(
-    mut ghost_release_timer: ResMut<GhostReleaseTimer>,
-    mut query: Query<(&mut ReleaseState, &mut Transform, &GhostSpeed), With<Ghost>>,
-    board: Res<Board>,
+fn maze_flash_system(
+    mut maze_flash: ResMut<MazeFlash>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    wall_materials: Res<WallMaterials>,
+    maze_theme: Res<MazeTheme>,
     time: Res<Time>
 ) {
-    // First pass: determine if any ghosts are currently being released
-    let mut any_caged = false;
-    let mut currently_releasing = false;
-    for (release_state, _, _) in query.iter_mut() {
-        match *release_state {
-            ReleaseState::Caged => any_caged = true,
-            ReleaseState::Releasing => currently_releasing = true,
-            _ => { }
-        }
+    if maze_flash.remaining == 0 {
+        return;
     }
 
-    // If no ghosts are currently caged or being released, no work needs to be done 
-    if !any_caged && !currently_releasing {
+    let timer = &mut maze_flash.timer;
+    timer.tick(time.delta());
+    if !timer.finished() {
         return;
     }
 
-    // Second pass: perform necessary operations
-    for (mut release_state, mut ghost_transform, ghost_speed) in query.iter_mut() {
-        match *release_state {
-            ReleaseState::Caged => {
-                // Ghosts that are currently being released must be prioritized
-                if currently_releasing {
-                    continue;
-                }
+    // Alternate between white and the theme colour, always finishing on the latter
+    maze_flash.remaining -= 1;
+    if let Some(material) = materials.get_mut(&wall_materials.maze) {
+        material.color = if maze_flash.remaining % 2 == 1 {
+            Color::WHITE
+        } else {
+            maze_theme.wall
+        };
+    }
+}
 
-                let timer = &mut ghost_release_timer.0;
-                timer.tick(time.delta());
-                if !timer.finished() {
-                    return;
-                }
+fn ghost_release_system(
+    mut ghost_release_timer: ResMut<GhostReleaseTimer>,
+    mut ghost_house: ResMut<GhostHouse>,
+    mut dot_consumed_event: EventReader<DotConsumedEvent>,
+    mut query: Query<(&mut ReleaseState, &mut TilePosition, &GhostSpeed, &GhostReleasePriority, Option<&HumanControlled>), With<Ghost>>,
+    practice: Res<Practice>,
+    board: Res<Board>,
+    time: Res<Time>
+) {
+    if practice.are_ghosts_frozen() {
+        return;
+    }
 
-                *release_state = ReleaseState::Releasing;
-                timer.reset();
-                return;
-            }, 
-            ReleaseState::Releasing => {
-                // Step 1: Get centered within the cage
-                let x_target = board.width() as f32 * board.cell_size() / 2.;
-                if ghost_transform.translation.x < x_target {
-                    ghost_transform.translation.x += ghost_speed.0;
-                    return;
-                } else if ghost_transform.translation.x > x_target {
-                    ghost_transform.translation.x -= ghost_speed.0;
-                    return;
-                }
+    // First pass: determine which ghost leaves next and whether one is already on its way out
+    let preferred = ghost::next_in_line(query.iter_mut().map(|(release_state, _, _, release_priority, _)| (release_priority.0, *release_state)));
+    let currently_releasing = query.iter_mut().any(|(release_state, _, _, _, _)| *release_state == ReleaseState::Releasing);
+    let human_caged = query.iter_mut()
+        .find(|(release_state, _, _, _, human_controlled)| **release_state == ReleaseState::Caged && human_controlled.is_some())
+        .map(|(_, _, _, release_priority, _)| release_priority.0);
+
+    // Eating restarts the fallback timer; otherwise it keeps running
+    let timer = &mut ghost_release_timer.0;
+    let mut ate = false;
+    for _ in dot_consumed_event.iter() {
+        ghost_house.dot_eaten(preferred);
+        ate = true;
+    }
+    if ate {
+        timer.reset();
+    } else {
+        timer.tick(time.delta());
+    }
 
-                // Step 2: Move upward 
-                let (_, y_target) = board.indeces_to_coordinates(11, 0);
-                if ghost_transform.translation.y < y_target {
-                    ghost_transform.translation.y += ghost_speed.0;
-                    return;
-                } else {
-                    *release_state = ReleaseState::Released;
-                    return;
-                }
-            },
-            _ => continue
+    // Second pass: perform necessary operations. Ghosts that are currently being released must be prioritized
+    if currently_releasing {
+        for (mut release_state, mut position, ghost_speed, _, _) in query.iter_mut() {
+            if *release_state == ReleaseState::Releasing && ghost::leave_house(&mut position, &board, ghost_speed.0) {
+                *release_state = release_state.next();
+            }
+        }
+        return;
+    }
+
+    // A ghost that a player controls skips the queue and doesn't wait for any counter
+    let release = human_caged.or_else(|| ghost_house.to_release(preferred, timer.finished()));
+    for (mut release_state, _, _, release_priority, _) in query.iter_mut() {
+        if *release_state == ReleaseState::Caged && Some(release_priority.0) == release {
+            *release_state = release_state.next();
+            timer.reset();
         }
     }
 }
 
 fn ghost_respawn_system(
-    mut query: Query<(&mut Transform, &mut ReleaseState, &mut GhostPath, &mut AttackState), With<Ghost>>,
-    board: Res<Board>
+    mut query: Query<(&mut TilePosition, &mut ReleaseState, &mut GhostPath, &mut GhostReviveTimer), With<Ghost>>,
+    board: Res<Board>,
+    time: Res<Time>
 ) {
-    for (mut transform, mut release_state, mut ghost_path, mut attack_state) in query.iter_mut() {
-        if *release_state != ReleaseState::Respawning {
-            continue;
-        }
-
-        if let Some((x, y)) = ghost_path.0.pop_front() {
-            let (target_x, target_y) = utils::get_ghost_spawn_coordinates(&board);
-            transform.translation.x = x;
-            transform.translation.y = y;
-
-            if transform.translation.x == target_x && transform.translation.y == target_y {
-                *release_state = ReleaseState::Caged;
-                *attack_state = AttackState::Attacking;
+    for (mut position, mut release_state, mut ghost_path, mut revive_timer) in query.iter_mut() {
+        if *release_state == ReleaseState::Reviving {
+            if revive_timer.0.tick(time.delta()).finished() {
+                *release_state = release_state.next();
             }
+        } else if ghost::return_home(&mut position, &mut release_state, &mut ghost_path.0, &board) {
+            revive_timer.0.reset();
         }
-        else {
-            // Hack to ensure that the ghost is centered on a tile before searching for a path to the spawn point.
-            // This is required to prevent the ghost from never finding a path.
-            if !utils::is_centered_horizontally(&transform, &board) {
-                transform.translation.x -= transform.translation.x % board.cell_size();
-            } 
-            if !utils::is_centered_vertically(&transform, &board) {
-                transform.translation.y -= transform.translation.y % board.cell_size();
-            }
+    }
+}
 
-            ghost_path.0 = Path::shortest_to_ghost_spawn(&transform, &board, constants::GHOST_SPEED_RESPAWNING);
+fn ghost_facing_system(mut query: Query<(&TilePosition, &PreviousTilePosition, &mut GhostFacing), With<Ghost>>, board: Res<Board>) {
+    for (position, previous_position, mut facing) in query.iter_mut() {
+        let (dx, dy) = previous_position.0.delta(position, &board);
+        if dx == 0 && dy == 0 {
+            continue;
         }
+
+        facing.0 = if dx.abs() >= dy.abs() {
+            if dx > 0 { Direction::Right } else { Direction::Left }
+        } else if dy > 0 {
+            Direction::Up
+        } else {
+            Direction::Down
+        };
     }
 }
 
@@ -846,65 +1207,87 @@ fn background_music_system(
 }
 
 fn reset_score_system(
-    mut query: Query<&mut Score>
+    mut query: Query<&mut Score, With<Ben>>
 ) {
-    let mut score = query.single_mut().unwrap();
-    score.0 = 0;
+    for mut score in query.iter_mut() {
+        score.0 = 0;
+    }
+}
+
+fn reset_ben_lives_system(mut query: Query<&mut BenLives, With<Ben>>) {
+    for mut ben_lives in query.iter_mut() {
+        ben_lives.0 = constants::BEN_LIVES;
+    }
 }
 
 fn reset_ben_system(
-    mut query: Query<(&mut Transform, &mut BenDirection, &mut Handle<ColorMaterial>), With<Ben>>,
-    board: Res<Board>,
-    ben_materials: Res<BenMaterials>
+    mut commands: Commands,
+    mut query: Query<(Entity, &PlayerId, &BenLives, &mut TilePosition, &mut BenDirection, &mut BenNextDirection, &mut Animation, &mut Visible), With<Ben>>,
+    mut cue_event: EventReader<CueEvent>,
+    board: Res<Board>
 ) {
-    let (mut transform, mut ben_direction, mut material_handle) = query.single_mut().unwrap();
+    if !sequence::was_cued(&mut cue_event, Cue::ResetPositions) {
+        return;
+    }
 
-    // Position
-    let (x, y) = utils::get_ben_spawn_coordinates(&board);
-    transform.translation.x = x;
-    transform.translation.y = y;
+    for (ben_entity, player_id, ben_lives, mut position, mut ben_direction, mut ben_next_direction, mut animation, mut visible) in query.iter_mut() {
+        // Position
+        let (x, y) = utils::get_ben_spawn_coordinates(&board);
+        *position = TilePosition::from_coordinates(&board, x, y);
 
-    // Direction
-    ben_direction.0 = constants::BEN_DIRECTION_DEFAULT;
+        // Direction
+        ben_direction.0 = ben::initial_direction(player_id.0);
+        ben_next_direction.0 = None;
 
-    // Sprite
-    *material_handle = ben_materials.ben_default.clone();
+        // Sprite; a co-op Ben that is out of lives stays off the board
+        animation.restart(animation::IDLE);
+        visible.is_visible = ben_lives.0 > 0;
+        commands.entity(ben_entity).remove::<Caught>();
+    }
 }
 
 fn reset_caleb_system(
-    mut query: Query<(&mut Transform, &mut AttackState, &mut ReleaseState, &mut GhostPath, &mut Handle<ColorMaterial>), With<Caleb>>,
-    board: Res<Board>,
-    caleb_materials: Res<CalebMaterials>
+    mut query: Query<(&mut TilePosition, &mut AttackState, &mut ReleaseState, &mut GhostPath, &mut CruiseElroy, &mut Animation, &mut Visible), With<Caleb>>,
+    mut cue_event: EventReader<CueEvent>,
+    board: Res<Board>
 ) {
-    let (mut transform, mut attack_state, mut release_state, mut ghost_path, mut material_handle) = query.single_mut().unwrap();
+    if !sequence::was_cued(&mut cue_event, Cue::ResetPositions) {
+        return;
+    }
+
+    let (mut position, mut attack_state, mut release_state, mut ghost_path, mut cruise_elroy, mut animation, mut visible) = query.single_mut().unwrap();
 
     // Position
     let (x, y) = utils::get_caleb_spawn_coordinates(&board);
-    transform.translation.x = x;
-    transform.translation.y = y;
+    *position = TilePosition::from_coordinates(&board, x, y);
 
     // States
     *attack_state = AttackState::Attacking;
     *release_state = ReleaseState::Released;
+    *cruise_elroy = CruiseElroy(0);
 
     // Path
     ghost_path.0.clear();
 
     // Sprite
-    *material_handle = caleb_materials.default_material.clone(); 
+    animation.restart(animation::WALK_UP);
+    visible.is_visible = true;
 }
 
 fn reset_harris_system(
-    mut query: Query<(&mut Transform, &mut AttackState, &mut ReleaseState, &mut GhostPath, &mut Handle<ColorMaterial>), With<Harris>>,
-    board: Res<Board>,
-    harris_materials: Res<HarrisMaterials>
+    mut query: Query<(&mut TilePosition, &mut AttackState, &mut ReleaseState, &mut GhostPath, &mut Animation, &mut Visible), With<Harris>>,
+    mut cue_event: EventReader<CueEvent>,
+    board: Res<Board>
 ) {
-    let (mut transform, mut attack_state, mut release_state, mut ghost_path, mut material_handle) = query.single_mut().unwrap();
+    if !sequence::was_cued(&mut cue_event, Cue::ResetPositions) {
+        return;
+    }
+
+    let (mut position, mut attack_state, mut release_state, mut ghost_path, mut animation, mut visible) = query.single_mut().unwrap();
 
     // Position
     let (x, y) = utils::get_harris_spawn_coordinates(&board);
-    transform.translation.x = x;
-    transform.translation.y = y;
+    *position = TilePosition::from_coordinates(&board, x, y);
 
     // States
     *attack_state = AttackState::Attacking;
@@ -914,20 +1297,24 @@ fn reset_harris_system(
     ghost_path.0.clear();
 
     // Sprite
-    *material_handle = harris_materials.default_material.clone();
+    animation.restart(animation::WALK_UP);
+    visible.is_visible = true;
 }
 
 fn reset_claflin_system(
-    mut query: Query<(&mut Transform, &mut AttackState, &mut ReleaseState, &mut GhostPath, &mut Handle<ColorMaterial>), With<Claflin>>,
-    board: Res<Board>,
-    claflin_materials: Res<ClaflinMaterials>
+    mut query: Query<(&mut TilePosition, &mut AttackState, &mut ReleaseState, &mut GhostPath, &mut Animation, &mut Visible), With<Claflin>>,
+    mut cue_event: EventReader<CueEvent>,
+    board: Res<Board>
 ) {
-    let (mut transform, mut attack_state, mut release_state, mut ghost_path, mut material_handle) = query.single_mut().unwrap();
+    if !sequence::was_cued(&mut cue_event, Cue::ResetPositions) {
+        return;
+    }
+
+    let (mut position, mut attack_state, mut release_state, mut ghost_path, mut animation, mut visible) = query.single_mut().unwrap();
 
     // Position
     let (x, y) = utils::get_claflin_spawn_coordinates(&board);
-    transform.translation.x = x;
-    transform.translation.y = y;
+    *position = TilePosition::from_coordinates(&board, x, y);
 
     // States
     *attack_state = AttackState::Attacking;
@@ -937,20 +1324,24 @@ fn reset_claflin_system(
     ghost_path.0.clear();
 
     // Sprite
-    *material_handle = claflin_materials.default_material.clone();
+    animation.restart(animation::WALK_UP);
+    visible.is_visible = true;
 }
 
 fn reset_samson_system(
-    mut query: Query<(&mut Transform, &mut AttackState, &mut ReleaseState, &mut GhostPath, &mut Handle<ColorMaterial>), With<Samson>>,
-    board: Res<Board>,
-    samson_materials: Res<SamsonMaterials>
+    mut query: Query<(&mut TilePosition, &mut AttackState, &mut ReleaseState, &mut GhostPath, &mut Animation, &mut Visible), With<Samson>>,
+    mut cue_event: EventReader<CueEvent>,
+    board: Res<Board>
 ) {
-    let (mut transform, mut attack_state, mut release_state, mut ghost_path, mut material_handle) = query.single_mut().unwrap();
+    if !sequence::was_cued(&mut cue_event, Cue::ResetPositions) {
+        return;
+    }
+
+    let (mut position, mut attack_state, mut release_state, mut ghost_path, mut animation, mut visible) = query.single_mut().unwrap();
 
     // Position
     let (x, y) = utils::get_samson_spawn_coordinates(&board);
-    transform.translation.x = x;
-    transform.translation.y = y;
+    *position = TilePosition::from_coordinates(&board, x, y);
 
     // States
     *attack_state = AttackState::Attacking;
@@ -960,18 +1351,120 @@ fn reset_samson_system(
     ghost_path.0.clear();
 
     // Sprite
-    *material_handle = samson_materials.default_material.clone();
+    animation.restart(animation::WALK_UP);
+    visible.is_visible = true;
+}
+
+fn ben_death_cue_system(
+    mut query: Query<&mut Animation, (With<Ben>, With<Caught>)>,
+    mut cue_event: EventReader<CueEvent>,
+    sound_materials: Res<misc::SoundMaterials>,
+    audio: Res<Audio>
+) {
+    if sequence::was_cued(&mut cue_event, Cue::BenDeath) {
+        for mut animation in query.iter_mut() {
+            animation.restart(animation::DEATH);
+        }
+        audio.play(sound_materials.ben_death_sound.clone());
+    }
+}
+
+fn hide_ghosts_cue_system(
+    mut query: Query<&mut Visible, With<Ghost>>,
+    mut cue_event: EventReader<CueEvent>
+) {
+    if sequence::was_cued(&mut cue_event, Cue::HideGhosts) {
+        for mut visible in query.iter_mut() {
+            visible.is_visible = false;
+        }
+    }
+}
+
+fn ready_cue_system(
+    mut commands: Commands,
+    mut cue_event: EventReader<CueEvent>,
+    font_material: Res<misc::FontMaterial>,
+    players: Res<Players>,
+    board: Res<Board>
+) {
+    if !sequence::was_cued(&mut cue_event, Cue::Ready) {
+        return;
+    }
+
+    // With two players, whose turn it is goes above the ghost house
+    if players.count() > 1 {
+        let (x, y) = utils::get_ghost_spawn_coordinates(&board);
+        let text_style = TextStyle {
+            font: font_material.handle.clone(),
+            font_size: 28.,
+            color: Color::CYAN
+        };
+        let text_alignment = TextAlignment {
+            vertical: VerticalAlign::Center,
+            horizontal: HorizontalAlign::Center
+        };
+        commands.spawn_bundle(Text2dBundle {
+            text: Text::with_section(players::player_name(players.current), text_style, text_alignment),
+            transform: Transform {
+                translation: Vec3::new(x, y + board.cell_size() * 3., 100.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(misc::ReadyMessage);
+    }
+
+    // Shown just below the ghost house, where the arcade puts it
+    let (x, y) = utils::get_ghost_spawn_coordinates(&board);
+    let text_style = TextStyle {
+        font: font_material.handle.clone(),
+        font_size: 28.,
+        color: Color::YELLOW
+    };
+    let text_alignment = TextAlignment {
+        vertical: VerticalAlign::Center,
+        horizontal: HorizontalAlign::Center
+    };
+    commands.spawn_bundle(Text2dBundle {
+        text: Text::with_section("READY!", text_style, text_alignment),
+        transform: Transform {
+            translation: Vec3::new(x, y - board.cell_size() * 3., 100.),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .insert(misc::ReadyMessage);
+}
+
+fn flash_maze_cue_system(
+    mut maze_flash: ResMut<MazeFlash>,
+    mut cue_event: EventReader<CueEvent>
+) {
+    if sequence::was_cued(&mut cue_event, Cue::FlashMaze) {
+        maze_flash.timer.reset();
+        maze_flash.remaining = 8;
+    }
+}
+
+fn exit_sequence_system(
+    mut commands: Commands,
+    query: Query<Entity, With<misc::ReadyMessage>>
+) {
+    for ready_message_entity in query.iter() {
+        commands.entity(ready_message_entity).despawn();
+    }
 }
 
 fn reset_dots_and_power_ups_system(
     mut commands: Commands,
     mut query_set: QuerySet<(
         Query<Entity, With<Dot>>,
-        Query<Entity, With<PowerUp>>
+        Query<Entity, With<PowerUp>>,
+        Query<Entity, With<FruitPickup>>
     )>,
     board: Res<Board>,
     dot_material: Res<DotMaterial>,
-    power_up_materials: Res<PowerUpMaterials>
+    power_up_sprite_sheet: Res<PowerUpSpriteSheet>
 ) {
     // Despawn all dots
     for dot_entity in query_set.q0_mut().iter_mut() {
@@ -983,28 +1476,69 @@ fn reset_dots_and_power_ups_system(
         commands.entity(power_up_entity).despawn();
     }
 
+    // Fruit is only ever dropped in by practice
+    for fruit_entity in query_set.q2_mut().iter_mut() {
+        commands.entity(fruit_entity).despawn();
+    }
+
     // Re-initialize all dots and power ups
-    utils::init_dots_and_power_ups(&mut commands, &board, dot_material.handle.clone(), power_up_materials.material_1.clone());
+    utils::init_dots_and_power_ups(&mut commands, &board, dot_material.handle.clone(), power_up_sprite_sheet.atlas.clone(), &HashSet::new());
 }
 
 fn reset_ghost_release_timer(
-    mut ghost_release_timer: ResMut<GhostReleaseTimer>
+    mut ghost_release_timer: ResMut<GhostReleaseTimer>,
+    mut ghost_house: ResMut<GhostHouse>
 ) {
     ghost_release_timer.0.reset();
+    ghost_house.reset();
+}
+
+fn reset_scare_system(
+    mut ghost_scare_timer: ResMut<GhostScareTimer>,
+    mut ghost_chain: ResMut<GhostChain>
+) {
+    ghost_scare_timer.0.reset();
+    ghost_chain.0 = 0;
+}
+
+fn reset_maze_flash_system(
+    mut maze_flash: ResMut<MazeFlash>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    wall_materials: Res<WallMaterials>,
+    maze_theme: Res<MazeTheme>
+) {
+    maze_flash.remaining = 0;
+    if let Some(material) = materials.get_mut(&wall_materials.maze) {
+        material.color = maze_theme.wall;
+    }
 }
 
 fn reset_end_message_text(
     mut commands: Commands,
     query: Query<Entity, With<misc::EndMessage>>
 ) {
-    let end_message_entity = query.single().unwrap();
-    commands.entity(end_message_entity).despawn()
+    // Playtests started from the editor reset without ever reaching the end screen
+    for end_message_entity in query.iter() {
+        commands.entity(end_message_entity).despawn();
+    }
+}
+
+fn next_level_system(
+    mut game_state: ResMut<State<GameState>>,
+    mut sequence: ResMut<Sequence>,
+    mut level: ResMut<misc::Level>
+) {
+    level.0 += 1;
+    *sequence = Sequence::level_start();
+    game_state.set(GameState::Sequence).unwrap();
 }
 
 fn restart_game_system(
-    mut game_state: ResMut<State<GameState>>
+    mut game_state: ResMut<State<GameState>>,
+    mut sequence: ResMut<Sequence>
 ) {
-    game_state.set(GameState::Default).unwrap();
+    *sequence = Sequence::level_start();
+    game_state.set(GameState::Sequence).unwrap();
 }
 
 fn wait_for_restart_system(
@@ -1082,6 +1616,23 @@ fn display_end_message_system(
     }
 }
 
+fn record_positions_system(mut query: Query<(&TilePosition, &mut PreviousTilePosition)>) {
+    for (position, mut previous_position) in query.iter_mut() {
+        previous_position.0 = *position;
+    }
+}
+
+fn sync_transform_system(
+    mut query: Query<(&TilePosition, &mut Transform), Changed<TilePosition>>,
+    board: Res<Board>
+) {
+    for (position, mut transform) in query.iter_mut() {
+        let (x, y) = position.to_coordinates(&board);
+        transform.translation.x = x;
+        transform.translation.y = y;
+    }
+}
+
 fn throttle_framerate_system() {
     thread::sleep(time::Duration::from_secs_f64(1./constants::MAX_FRAMERATE));
 }
@@ -0,0 +1,305 @@
+use std::collections::VecDeque;
+use bevy::prelude::*;
+use bevy::render::texture::{Extent3d, TextureDimension, TextureFormat};
+use crate::board::{Board, BoardTile, SpawnAnchor};
+use crate::wall::WallMaterials;
+
+// Geometry is expressed as a fraction of the cell size so that it scales with the board
+const LINE_WIDTH: f32 = 3. / 32.;
+const BORDER_GAP: f32 = 6. / 32.;
+const PILLAR_RADIUS: f32 = 8. / 32.;
+
+/// A level's skin, read from the optional `@wall_color`, `@gate_color` and `@popup_color` properties (hex, e.g.
+/// `2121DE`) and `@popup_size` (font size of the score popups).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MazeTheme {
+    pub wall: Color,
+    pub gate: Color,
+    pub popup: Color,
+    pub popup_size: f32
+}
+
+impl Default for MazeTheme {
+    fn default() -> Self {
+        Self {
+            wall: Color::rgb_u8(33, 33, 222),
+            gate: Color::rgb_u8(255, 184, 222),
+            popup: Color::rgb_u8(0, 255, 255),
+            popup_size: 18.
+        }
+    }
+}
+
+impl MazeTheme {
+    pub fn from_board(board: &Board) -> Self {
+        let default = Self::default();
+        let parse = |key: &str| board.property(key).and_then(|value| Color::hex(value.trim_start_matches('#')).ok());
+        Self {
+            wall: parse("wall_color").unwrap_or(default.wall),
+            gate: parse("gate_color").unwrap_or(default.gate),
+            popup: parse("popup_color").unwrap_or(default.popup),
+            popup_size: board.property("popup_size")
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default.popup_size)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Cell {
+    Open,
+    Solid,
+    Gate
+}
+
+enum Stroke {
+    // From the tile centre to the midpoint of an edge, given as a unit offset
+    Spoke(f32, f32),
+    // Circle around a point, drawn only where it passes through the tile
+    Arc(f32, f32, f32),
+    Horizontal(f32),
+    Vertical(f32)
+}
+
+/// Re-renders the maze texture from the board and applies the level's theme to the wall materials.
+pub fn update_wall_materials(
+    board: &Board,
+    theme: &MazeTheme,
+    wall_materials: &WallMaterials,
+    materials: &mut Assets<ColorMaterial>,
+    textures: &mut Assets<Texture>
+) {
+    if let Some(texture) = textures.get_mut(&wall_materials.maze_texture) {
+        *texture = render_walls(board);
+    }
+    if let Some(material) = materials.get_mut(&wall_materials.maze) {
+        material.color = theme.wall;
+    }
+    if let Some(material) = materials.get_mut(&wall_materials.ghost_gate) {
+        material.color = theme.gate;
+    }
+}
+
+/// Draws the wall outlines as white lines on a transparent texture, one pixel per world unit. The lines run through
+/// the centres of the wall tiles that border the playable area, turn with rounded corners, and are doubled along the
+/// outer border. The material tints the texture, which is what lets the maze flash.
+pub fn render_walls(board: &Board) -> Texture {
+    let cell_size = board.cell_size() as usize;
+    let width = board.width() * cell_size;
+    let height = board.height() * cell_size;
+    let mut data = vec![0u8; width * height * 4];
+
+    let cells = classify_cells(board);
+    let outer = find_outer_cells(&cells);
+    let cell_at = |i: isize, j: isize| -> Cell {
+        if i < 0 || j < 0 || i as usize >= board.height() || j as usize >= board.width() {
+            Cell::Solid
+        } else {
+            cells[i as usize][j as usize]
+        }
+    };
+    let is_open = |i: isize, j: isize| cell_at(i, j) == Cell::Open;
+    let is_solid = |i: isize, j: isize| cell_at(i, j) != Cell::Open;
+
+    for i in 0..board.height() {
+        for j in 0..board.width() {
+            if cells[i][j] != Cell::Solid {
+                continue;
+            }
+
+            let (ii, jj) = (i as isize, j as isize);
+
+            // A spoke joins two solid tiles when open space runs alongside the pair
+            let up = is_solid(ii - 1, jj) && (is_open(ii, jj - 1) || is_open(ii - 1, jj - 1) || is_open(ii, jj + 1) || is_open(ii - 1, jj + 1));
+            let right = is_solid(ii, jj + 1) && (is_open(ii - 1, jj) || is_open(ii - 1, jj + 1) || is_open(ii + 1, jj) || is_open(ii + 1, jj + 1));
+            let down = is_solid(ii + 1, jj) && (is_open(ii, jj - 1) || is_open(ii + 1, jj - 1) || is_open(ii, jj + 1) || is_open(ii + 1, jj + 1));
+            let left = is_solid(ii, jj - 1) && (is_open(ii - 1, jj) || is_open(ii - 1, jj - 1) || is_open(ii + 1, jj) || is_open(ii + 1, jj - 1));
+            let spokes = [up, right, down, left].iter().filter(|spoke| **spoke).count();
+
+            // Offsets use a unit tile with y pointing down, matching the texture rows
+            let mut strokes = Vec::new();
+            match (up, right, down, left) {
+                (false, true, false, true) => {
+                    strokes.push(Stroke::Horizontal(0.5));
+                    if outer[i][j] {
+                        if is_open(ii - 1, jj) && !is_open(ii + 1, jj) {
+                            strokes.push(Stroke::Horizontal(0.5 + BORDER_GAP));
+                        } else if is_open(ii + 1, jj) && !is_open(ii - 1, jj) {
+                            strokes.push(Stroke::Horizontal(0.5 - BORDER_GAP));
+                        }
+                    }
+                },
+                (true, false, true, false) => {
+                    strokes.push(Stroke::Vertical(0.5));
+                    if outer[i][j] {
+                        if is_open(ii, jj - 1) && !is_open(ii, jj + 1) {
+                            strokes.push(Stroke::Vertical(0.5 + BORDER_GAP));
+                        } else if is_open(ii, jj + 1) && !is_open(ii, jj - 1) {
+                            strokes.push(Stroke::Vertical(0.5 - BORDER_GAP));
+                        }
+                    }
+                },
+                _ if spokes == 2 => {
+                    // Corner: a quarter circle around the tile corner that lies between the two spokes
+                    let corner_x = if right { 1. } else { 0. };
+                    let corner_y = if down { 1. } else { 0. };
+                    strokes.push(Stroke::Arc(corner_x, corner_y, 0.5));
+                    if outer[i][j] {
+                        // Convex corners have open space on both far sides, concave ones only diagonally
+                        let far_i = if down { ii - 1 } else { ii + 1 };
+                        let far_j = if right { jj - 1 } else { jj + 1 };
+                        if is_open(far_i, jj) && is_open(ii, far_j) {
+                            strokes.push(Stroke::Arc(corner_x, corner_y, 0.5 - BORDER_GAP));
+                        } else {
+                            strokes.push(Stroke::Arc(corner_x, corner_y, 0.5 + BORDER_GAP));
+                        }
+                    }
+                },
+                _ if spokes == 0 => {
+                    let borders_open = (-1..=1).any(|di| (-1..=1).any(|dj| is_open(ii + di, jj + dj)));
+                    if borders_open {
+                        strokes.push(Stroke::Arc(0.5, 0.5, PILLAR_RADIUS));
+                    }
+                },
+                _ => {
+                    // Dead ends and junctions fall back to straight spokes from the centre
+                    for (present, dx, dy) in [(up, 0., -0.5), (right, 0.5, 0.), (down, 0., 0.5), (left, -0.5, 0.)] {
+                        if present {
+                            strokes.push(Stroke::Spoke(dx, dy));
+                        }
+                    }
+                }
+            }
+
+            if strokes.is_empty() {
+                continue;
+            }
+
+            for py in 0..cell_size {
+                for px in 0..cell_size {
+                    let x = (px as f32 + 0.5) / cell_size as f32;
+                    let y = (py as f32 + 0.5) / cell_size as f32;
+                    let distance = strokes.iter()
+                        .map(|stroke| stroke_distance(stroke, x, y))
+                        .fold(f32::MAX, f32::min) * cell_size as f32;
+                    let coverage = (LINE_WIDTH * cell_size as f32 / 2. + 0.5 - distance).max(0.).min(1.);
+                    if coverage == 0. {
+                        continue;
+                    }
+
+                    let index = ((i * cell_size + py) * width + j * cell_size + px) * 4;
+                    data[index] = 255;
+                    data[index + 1] = 255;
+                    data[index + 2] = 255;
+                    data[index + 3] = (coverage * 255.) as u8;
+                }
+            }
+        }
+    }
+
+    Texture::new(
+        Extent3d::new(width as u32, height as u32, 1),
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb
+    )
+}
+
+fn stroke_distance(stroke: &Stroke, x: f32, y: f32) -> f32 {
+    match *stroke {
+        Stroke::Spoke(dx, dy) => {
+            // Distance to the segment from (0.5, 0.5) to (0.5 + dx, 0.5 + dy)
+            let (rx, ry) = (x - 0.5, y - 0.5);
+            let t = ((rx * dx + ry * dy) / (dx * dx + dy * dy)).max(0.).min(1.);
+            ((rx - t * dx).powi(2) + (ry - t * dy).powi(2)).sqrt()
+        },
+        Stroke::Arc(cx, cy, radius) => (((x - cx).powi(2) + (y - cy).powi(2)).sqrt() - radius).abs(),
+        Stroke::Horizontal(line_y) => (y - line_y).abs(),
+        Stroke::Vertical(line_x) => (x - line_x).abs()
+    }
+}
+
+/// Tiles count as open only when an actor can actually reach them, so pockets of `Empty` enclosed by walls (and the
+/// areas outside the maze) are drawn as solid wall.
+fn classify_cells(board: &Board) -> Vec<Vec<Cell>> {
+    let mut cells = vec![vec![Cell::Solid; board.width()]; board.height()];
+    let mut queue = VecDeque::new();
+    for i in 0..board.height() {
+        for j in 0..board.width() {
+            match board.try_get(i, j).unwrap() {
+                BoardTile::GhostGate => cells[i][j] = Cell::Gate,
+                BoardTile::Dot | BoardTile::PowerUp | BoardTile::Fruit => queue.push_back((i, j)),
+                _ => { }
+            }
+        }
+    }
+    queue.push_back(board.anchor(SpawnAnchor::Ben));
+    queue.push_back(board.anchor(SpawnAnchor::GhostHouse));
+    queue.push_back(board.anchor(SpawnAnchor::GhostExit));
+
+    let mut visited = vec![vec![false; board.width()]; board.height()];
+    while let Some((i, j)) = queue.pop_front() {
+        if visited[i][j] {
+            continue;
+        }
+        visited[i][j] = true;
+
+        match board.try_get(i, j).unwrap() {
+            BoardTile::Wall => continue,
+            BoardTile::GhostGate => { },
+            _ => cells[i][j] = Cell::Open
+        }
+
+        if i > 0 {
+            queue.push_back((i - 1, j));
+        }
+        if i + 1 < board.height() {
+            queue.push_back((i + 1, j));
+        }
+        if j > 0 {
+            queue.push_back((i, j - 1));
+        }
+        if j + 1 < board.width() {
+            queue.push_back((i, j + 1));
+        }
+    }
+
+    cells
+}
+
+/// Marks the solid tiles connected to the edge of the board, which make up the double-lined outer border.
+fn find_outer_cells(cells: &[Vec<Cell>]) -> Vec<Vec<bool>> {
+    let height = cells.len();
+    let width = cells[0].len();
+    let mut outer = vec![vec![false; width]; height];
+    let mut queue = VecDeque::new();
+    for i in 0..height {
+        for j in 0..width {
+            if i == 0 || j == 0 || i == height - 1 || j == width - 1 {
+                queue.push_back((i, j));
+            }
+        }
+    }
+
+    while let Some((i, j)) = queue.pop_front() {
+        if outer[i][j] || cells[i][j] != Cell::Solid {
+            continue;
+        }
+        outer[i][j] = true;
+
+        if i > 0 {
+            queue.push_back((i - 1, j));
+        }
+        if i + 1 < height {
+            queue.push_back((i + 1, j));
+        }
+        if j > 0 {
+            queue.push_back((i, j - 1));
+        }
+        if j + 1 < width {
+            queue.push_back((i, j + 1));
+        }
+    }
+
+    outer
+}
@@ -19,6 +19,8 @@ pub struct RestartMessage;
 
 pub struct EndMessage;
 
+pub struct ReadyMessage;
+
 pub struct EndMessageText(pub String);
 
 impl Default for EndMessageText {
@@ -26,3 +28,12 @@ impl Default for EndMessageText {
         Self(String::new())
     }
 }
+
+/// The level being played, starting at 1.
+pub struct Level(pub u32);
+
+impl Default for Level {
+    fn default() -> Self {
+        Self(1)
+    }
+}
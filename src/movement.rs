@@ -0,0 +1,292 @@
+use crate::board::{Board, BoardTile};
+use crate::constants;
+use crate::enums::Direction;
+
+/// Sub-tile resolution: an actor's offset from its tile centre is measured in 1/1024ths of a tile.
+pub const TILE_UNITS: i32 = 1024;
+const HALF_TILE_UNITS: i32 = TILE_UNITS / 2;
+
+/// Distance covered per frame at 100% speed. Speeds are percentages of this, like the arcade's 80%/75%/40%.
+pub const FULL_SPEED_UNITS: i32 = 160;
+
+/// How far past or short of a tile centre Ben may still turn a corner.
+pub const CORNERING_UNITS: i32 = TILE_UNITS / 4;
+
+pub fn speed_units(percent: f32) -> i32 {
+    (FULL_SPEED_UNITS as f32 * percent / 100.).round() as i32
+}
+
+/// An actor's place on the board: the tile it is in plus a fixed-point offset from that tile's centre. Offsets stay
+/// within half a tile either way, so crossing the midpoint between two tiles moves the actor into the next one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TilePosition {
+    pub i: usize,
+    pub j: usize,
+    // x grows to the right and y grows upwards, like world coordinates
+    pub offset_x: i32,
+    pub offset_y: i32
+}
+
+impl TilePosition {
+    pub fn centred(i: usize, j: usize) -> Self {
+        Self {
+            i,
+            j,
+            offset_x: 0,
+            offset_y: 0
+        }
+    }
+
+    /// Converts world coordinates, rounding to the nearest unit.
+    pub fn from_coordinates(board: &Board, x: f32, y: f32) -> Self {
+        let (x, y) = board.wrap_coordinates(x, y);
+        let units_x = ((x - board.offset()) / board.cell_size() * TILE_UNITS as f32).round() as i32;
+        let units_y = ((y - board.offset()) / board.cell_size() * TILE_UNITS as f32).round() as i32;
+        let mut position = Self::centred(board.height() - 1, 0);
+        position.shift(board, units_x, units_y);
+        position
+    }
+
+    pub fn to_coordinates(&self, board: &Board) -> (f32, f32) {
+        let (x, y) = board.indeces_to_coordinates(self.i, self.j);
+        (
+            x + self.offset_x as f32 / TILE_UNITS as f32 * board.cell_size(),
+            y + self.offset_y as f32 / TILE_UNITS as f32 * board.cell_size()
+        )
+    }
+
+    pub fn tile(&self) -> (usize, usize) {
+        (self.i, self.j)
+    }
+
+    pub fn is_centered(&self) -> bool {
+        self.offset_x == 0 && self.offset_y == 0
+    }
+
+    /// Offset along `direction`: positive once past the tile centre, negative while still approaching it.
+    pub fn offset_along(&self, direction: Direction) -> i32 {
+        let (unit_x, unit_y) = direction_units(direction);
+        self.offset_x * unit_x + self.offset_y * unit_y
+    }
+
+    /// Whether there is no offset across `direction`, i.e. the actor isn't halfway through cutting a corner.
+    pub fn is_in_lane(&self, direction: Direction) -> bool {
+        let (unit_x, unit_y) = direction_units(direction);
+        self.offset_x * unit_y == 0 && self.offset_y * unit_x == 0
+    }
+
+    /// Whether the tile next to this one in `direction` can be walked into. Ghost gates only open for ghosts that are
+    /// being released or are respawning, which move along scripted paths instead.
+    pub fn can_move(&self, board: &Board, direction: Direction) -> bool {
+        let (i, j) = board.neighbour(self.i, self.j, direction);
+        board.try_get(i, j).is_some_and(BoardTile::is_walkable)
+    }
+
+    /// Moves by a number of units along each axis, wrapping around the edges of the board.
+    pub fn shift(&mut self, board: &Board, units_x: i32, units_y: i32) {
+        let x = self.offset_x + units_x + HALF_TILE_UNITS;
+        let y = self.offset_y + units_y + HALF_TILE_UNITS;
+        let (i, j) = board.wrap_indeces(
+            self.i as isize - y.div_euclid(TILE_UNITS) as isize,
+            self.j as isize + x.div_euclid(TILE_UNITS) as isize
+        );
+        self.i = i;
+        self.j = j;
+        self.offset_x = x.rem_euclid(TILE_UNITS) - HALF_TILE_UNITS;
+        self.offset_y = y.rem_euclid(TILE_UNITS) - HALF_TILE_UNITS;
+    }
+
+    /// Shortest displacement in units from this position to another, which may cross an edge of the board.
+    pub fn delta(&self, other: &TilePosition, board: &Board) -> (i32, i32) {
+        let width = board.width() as i32 * TILE_UNITS;
+        let height = board.height() as i32 * TILE_UNITS;
+        let dx = (other.j as i32 - self.j as i32) * TILE_UNITS + other.offset_x - self.offset_x;
+        let dy = (self.i as i32 - other.i as i32) * TILE_UNITS + other.offset_y - self.offset_y;
+        (
+            (dx + width / 2).rem_euclid(width) - width / 2,
+            (dy + height / 2).rem_euclid(height) - height / 2
+        )
+    }
+
+    /// Heads straight for `target`, first along x and then along y, covering at most `distance` units. Returns the
+    /// distance left over once the target has been reached.
+    pub fn move_towards(&mut self, board: &Board, target: &TilePosition, distance: i32) -> i32 {
+        let mut remaining = distance;
+        let (dx, _) = self.delta(target, board);
+        let step = dx.abs().min(remaining);
+        self.shift(board, dx.signum() * step, 0);
+        remaining -= step;
+
+        let (_, dy) = self.delta(target, board);
+        let step = dy.abs().min(remaining);
+        self.shift(board, 0, dy.signum() * step);
+        remaining - step
+    }
+
+    /// Moves up to `distance` units in `direction`. Every time a tile centre is reached, `at_centre` picks the direction
+    /// to carry on in, or stops the actor by returning `None`. An offset across `direction`, left by turning a corner
+    /// early or late, is closed diagonally while moving on. Returns the direction the actor ends up facing.
+    pub fn travel(
+        &mut self,
+        board: &Board,
+        mut direction: Direction,
+        distance: i32,
+        mut at_centre: impl FnMut(&TilePosition, Direction) -> Option<Direction>
+    ) -> Direction {
+        let mut remaining = distance;
+        while remaining > 0 {
+            if self.is_centered() {
+                match at_centre(self, direction) {
+                    Some(next_direction) => direction = next_direction,
+                    None => break
+                }
+            }
+
+            // Distance to the next centre ahead, which is a whole tile away when starting from one
+            let (unit_x, unit_y) = direction_units(direction);
+            let ahead = self.offset_along(direction);
+            let to_centre = if ahead < 0 { -ahead } else { TILE_UNITS - ahead };
+            let step = to_centre.min(remaining);
+
+            // Corners are cut at full speed on both axes, like in the arcade
+            let (across_x, across_y) = (self.offset_x * unit_y.abs(), self.offset_y * unit_x.abs());
+            let slide = (across_x + across_y).abs().min(step);
+            self.shift(board, unit_x * step - across_x.signum() * slide, unit_y * step - across_y.signum() * slide);
+            remaining -= step;
+        }
+
+        direction
+    }
+}
+
+/// Where an actor was at the start of the current tick, so that collisions can be checked along the way it moved.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PreviousTilePosition(pub TilePosition);
+
+/// How close, in units, Ben and a ghost have to come during a tick to touch.
+pub struct CollisionRadius(pub i32);
+
+impl CollisionRadius {
+    pub fn from_board(board: &Board) -> Self {
+        let tiles = board.property("collision_radius")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(constants::COLLISION_RADIUS_TILES);
+        Self((tiles * TILE_UNITS as f32).round() as i32)
+    }
+}
+
+pub fn direction_units(direction: Direction) -> (i32, i32) {
+    match direction {
+        Direction::Up => (0, 1),
+        Direction::Right => (1, 0),
+        Direction::Down => (0, -1),
+        Direction::Left => (-1, 0)
+    }
+}
+
+pub fn opposite(direction: Direction) -> Direction {
+    match direction {
+        Direction::Up => Direction::Down,
+        Direction::Right => Direction::Left,
+        Direction::Down => Direction::Up,
+        Direction::Left => Direction::Right
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One open row that wraps through both edges, between two walls
+    const LOOP: &str = "@ben_spawn 1 0\n@ghost_house 1 2\n@ghost_exit 1 2\n#####\n_____\n#####\n";
+
+    fn board() -> Board {
+        Board::from_level_str(LOOP, constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET).unwrap()
+    }
+
+    #[test]
+    fn speeds_are_percentages_of_full_speed() {
+        assert_eq!(speed_units(100.), FULL_SPEED_UNITS);
+        assert_eq!(speed_units(80.), 128);
+        assert_eq!(speed_units(75.), 120);
+        assert_eq!(speed_units(40.), 64);
+        assert_eq!(speed_units(37.5), 60);
+        assert_eq!(speed_units(0.), 0);
+    }
+
+    #[test]
+    fn crossing_the_midpoint_moves_into_the_next_tile() {
+        let board = board();
+        let mut position = TilePosition::centred(1, 1);
+        position.shift(&board, HALF_TILE_UNITS - 1, 0);
+        assert_eq!(position, TilePosition { offset_x: HALF_TILE_UNITS - 1, ..TilePosition::centred(1, 1) });
+        position.shift(&board, 1, 0);
+        assert_eq!(position, TilePosition { offset_x: -HALF_TILE_UNITS, ..TilePosition::centred(1, 2) });
+    }
+
+    #[test]
+    fn shifting_wraps_through_the_edges() {
+        let board = board();
+        let mut position = TilePosition::centred(1, 0);
+        position.shift(&board, -TILE_UNITS - 100, 0);
+        assert_eq!(position, TilePosition { offset_x: -100, ..TilePosition::centred(1, 4) });
+        position.shift(&board, 0, 3 * TILE_UNITS);
+        assert_eq!(position, TilePosition { offset_x: -100, ..TilePosition::centred(1, 4) });
+
+        // The short way round is through the edge
+        assert_eq!(TilePosition::centred(1, 4).delta(&TilePosition::centred(1, 0), &board), (TILE_UNITS, 0));
+        assert_eq!(TilePosition::centred(1, 0).delta(&TilePosition::centred(1, 4), &board), (-TILE_UNITS, 0));
+    }
+
+    #[test]
+    fn travel_stops_at_every_centre_at_any_speed() {
+        let board = board();
+        for percent in [37.5, 40., 75., 80., 100., 133.] {
+            let distance = speed_units(percent);
+            let mut position = TilePosition::centred(1, 0);
+            let mut centres = 0;
+            let ticks = 200;
+            for _ in 0..ticks {
+                position.travel(&board, Direction::Right, distance, |position, direction| {
+                    assert!(position.is_centered());
+                    centres += 1;
+                    Some(direction)
+                });
+            }
+
+            // Every unit is carried over from tick to tick, including around the board and back
+            let total = distance * ticks;
+            let mut expected = TilePosition::centred(1, 0);
+            expected.shift(&board, total, 0);
+            assert_eq!(position, expected, "at {}%", percent);
+            assert_eq!(centres, (total + TILE_UNITS - 1) / TILE_UNITS, "at {}%", percent);
+        }
+    }
+
+    #[test]
+    fn travel_carries_overshoot_past_a_centre() {
+        let board = board();
+        let mut position = TilePosition { offset_x: -100, ..TilePosition::centred(1, 4) };
+        let direction = position.travel(&board, Direction::Right, 700, |_, _| Some(Direction::Right));
+        assert_eq!(direction, Direction::Right);
+        assert_eq!(position, TilePosition { offset_x: -424, ..TilePosition::centred(1, 0) });
+    }
+
+    #[test]
+    fn travel_stops_dead_at_a_centre() {
+        let board = board();
+        let mut position = TilePosition { offset_x: -100, ..TilePosition::centred(1, 2) };
+        position.travel(&board, Direction::Right, 700, |position, direction| {
+            if position.can_move(&board, Direction::Up) { Some(direction) } else { None }
+        });
+        assert_eq!(position, TilePosition::centred(1, 2));
+    }
+
+    #[test]
+    fn turning_late_closes_the_offset_diagonally() {
+        let board = board();
+        let mut position = TilePosition { offset_x: 0, offset_y: 200, ..TilePosition::centred(1, 2) };
+        position.travel(&board, Direction::Right, 300, |_, direction| Some(direction));
+        assert_eq!(position, TilePosition { offset_x: 300, offset_y: 0, ..TilePosition::centred(1, 2) });
+    }
+}
@@ -0,0 +1,720 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::io::ErrorKind;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+use bevy::prelude::*;
+use crate::ben::{self, Ben, BenDirection, BenLives, BenSpriteSheet, PlayerId};
+use crate::board::{Board, BoardTile};
+use crate::dot::{Dot, DotMaterial};
+use crate::enums::{Direction, GameState};
+use crate::events::BenDirectionChangedEvent;
+use crate::ghost::{Ghost, AttackState, ReleaseState, GhostReleasePriority, GhostScareTimer};
+use crate::misc::{self, Level};
+use crate::movement::{TilePosition, PreviousTilePosition};
+use crate::power_up::{PowerUp, PowerUpSpriteSheet};
+use crate::score::Score;
+use crate::sim::{self, Sim, SimConfig};
+use crate::utils;
+
+pub const DEFAULT_PORT: u16 = 7777;
+const MAGIC: [u8; 2] = *b"BM";
+const PROTOCOL_VERSION: u8 = 2;
+const HANDSHAKE_RESEND: Duration = Duration::from_millis(200);
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+// Unacknowledged inputs are resent with every packet, up to this many
+const MAX_INPUTS_PER_PACKET: usize = 64;
+// How many ticks of checksums are kept around for comparing with the peer's
+const CHECKSUM_HISTORY: u64 = 256;
+
+/// Both modes play the same game with a Ben each; they only differ in who wins.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NetMode {
+    /// The players clear the board together.
+    Coop,
+    /// The players race each other for points.
+    HeadToHead
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct NetConfig {
+    /// Ticks between reading a local input and simulating it. More delay means fewer rollbacks.
+    pub input_delay: u32,
+    /// How many ticks the sim may run ahead of the peer's last confirmed input before it waits for them.
+    pub max_rollback: u32,
+    pub mode: NetMode
+}
+
+impl Default for NetConfig {
+    fn default() -> Self {
+        Self {
+            input_delay: 2,
+            max_rollback: 8,
+            mode: NetMode::Coop
+        }
+    }
+}
+
+impl NetConfig {
+    /// Reads `--delay <ticks>`, `--rollback <ticks>` and `--head-to-head` from the command line.
+    pub fn from_args(args: &[String]) -> Self {
+        let mut config = Self::default();
+        let value = |flag: &str| args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|index| args.get(index + 1))
+            .and_then(|value| value.parse().ok());
+        if let Some(input_delay) = value("--delay") {
+            config.input_delay = input_delay;
+        }
+        if let Some(max_rollback) = value("--rollback") {
+            config.max_rollback = max_rollback;
+        }
+        if args.iter().any(|arg| arg == "--head-to-head") {
+            config.mode = NetMode::HeadToHead;
+        }
+        config
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Message {
+    /// Sent by the joining player until the host answers. `board` is the level's hash.
+    Hello { board: u64 },
+    Welcome { mode: NetMode },
+    /// The joining player has a different level.
+    Reject,
+    /// Local inputs from tick `start` on, the number of the peer's inputs received so far, and the checksum of the
+    /// latest tick both inputs were known for.
+    Inputs { start: u64, inputs: Vec<Option<Direction>>, ack: u64, checksum: Option<(u64, u64)> },
+    Bye
+}
+
+fn encode_direction(direction: Option<Direction>) -> u8 {
+    match direction {
+        None => 0,
+        Some(Direction::Up) => 1,
+        Some(Direction::Right) => 2,
+        Some(Direction::Down) => 3,
+        Some(Direction::Left) => 4
+    }
+}
+
+fn decode_direction(byte: u8) -> Option<Option<Direction>> {
+    match byte {
+        0 => Some(None),
+        1 => Some(Some(Direction::Up)),
+        2 => Some(Some(Direction::Right)),
+        3 => Some(Some(Direction::Down)),
+        4 => Some(Some(Direction::Left)),
+        _ => None
+    }
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> Option<u64> {
+    bytes.get(at..at + 8).map(|slice| u64::from_le_bytes(slice.try_into().unwrap()))
+}
+
+impl Message {
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![MAGIC[0], MAGIC[1], PROTOCOL_VERSION];
+        match self {
+            Message::Hello { board } => {
+                bytes.push(0);
+                bytes.extend_from_slice(&board.to_le_bytes());
+            },
+            Message::Welcome { mode } => {
+                bytes.push(1);
+                bytes.push(match mode {
+                    NetMode::Coop => 0,
+                    NetMode::HeadToHead => 1
+                });
+            },
+            Message::Reject => bytes.push(2),
+            Message::Inputs { start, inputs, ack, checksum } => {
+                bytes.push(3);
+                bytes.extend_from_slice(&start.to_le_bytes());
+                bytes.extend_from_slice(&ack.to_le_bytes());
+                let (tick, hash) = checksum.unwrap_or((u64::MAX, 0));
+                bytes.extend_from_slice(&tick.to_le_bytes());
+                bytes.extend_from_slice(&hash.to_le_bytes());
+                bytes.extend(inputs.iter().map(|input| encode_direction(*input)));
+            },
+            Message::Bye => bytes.push(4)
+        }
+        bytes
+    }
+
+    /// Anything that isn't a well-formed packet of this version is ignored.
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 4 || bytes[..2] != MAGIC || bytes[2] != PROTOCOL_VERSION {
+            return None;
+        }
+
+        match bytes[3] {
+            0 => Some(Message::Hello { board: read_u64(bytes, 4)? }),
+            1 => Some(Message::Welcome {
+                mode: match bytes.get(4)? {
+                    0 => NetMode::Coop,
+                    _ => NetMode::HeadToHead
+                }
+            }),
+            2 => Some(Message::Reject),
+            3 => {
+                let tick = read_u64(bytes, 20)?;
+                let hash = read_u64(bytes, 28)?;
+                Some(Message::Inputs {
+                    start: read_u64(bytes, 4)?,
+                    ack: read_u64(bytes, 12)?,
+                    checksum: if tick == u64::MAX { None } else { Some((tick, hash)) },
+                    inputs: bytes[36..].iter().map(|byte| decode_direction(*byte)).collect::<Option<_>>()?
+                })
+            },
+            4 => Some(Message::Bye),
+            _ => None
+        }
+    }
+}
+
+/// A UDP socket talking to one peer. Packets from anyone else are dropped.
+pub struct Connection {
+    socket: UdpSocket,
+    remote: SocketAddr
+}
+
+impl Connection {
+    fn new(socket: UdpSocket, remote: SocketAddr) -> Result<Self, String> {
+        socket.set_nonblocking(true).map_err(|err| format!("Failed to configure socket: {}", err))?;
+        Ok(Self { socket, remote })
+    }
+
+    fn send(&self, message: &Message) {
+        // Lost packets are made up for by the next ones, which repeat everything unacknowledged
+        let _ = self.socket.send_to(&message.encode(), self.remote);
+    }
+
+    fn receive(&self) -> Result<Option<Message>, String> {
+        let mut buffer = [0; 512];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((length, from)) if from == self.remote => if let Some(message) = Message::decode(&buffer[..length]) {
+                    return Ok(Some(message));
+                },
+                Ok(_) => continue,
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(None),
+                // Windows reports an ICMP port unreachable this way; the timeout deals with peers that are gone
+                Err(err) if err.kind() == ErrorKind::ConnectionReset => continue,
+                Err(err) => return Err(format!("Network error: {}", err))
+            }
+        }
+    }
+}
+
+/// A host waiting for someone to join.
+pub struct Lobby {
+    socket: UdpSocket
+}
+
+impl Lobby {
+    pub fn host(port: u16) -> Result<Self, String> {
+        let socket = UdpSocket::bind(("0.0.0.0", port)).map_err(|err| format!("Failed to listen on port {}: {}", port, err))?;
+        Ok(Self { socket })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.socket.local_addr().map_or(0, |address| address.port())
+    }
+
+    /// Blocks until a player with the same level joins. Players with another level are turned away.
+    pub fn accept(self, board_hash: u64, mode: NetMode) -> Result<Connection, String> {
+        let mut buffer = [0; 512];
+        loop {
+            let (length, from) = match self.socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(err) if err.kind() == ErrorKind::ConnectionReset => continue,
+                Err(err) => return Err(format!("Network error: {}", err))
+            };
+
+            if let Some(Message::Hello { board }) = Message::decode(&buffer[..length]) {
+                let reply = if board == board_hash { Message::Welcome { mode } } else { Message::Reject };
+                let _ = self.socket.send_to(&reply.encode(), from);
+                if board == board_hash {
+                    return Connection::new(self.socket, from);
+                }
+            }
+        }
+    }
+}
+
+/// Joins a host at `address` (e.g. `127.0.0.1:7777`), returning the connection and the mode the host picked.
+pub fn join(address: &str, board_hash: u64) -> Result<(Connection, NetMode), String> {
+    let remote = address.to_socket_addrs()
+        .ok()
+        .and_then(|mut addresses| addresses.next())
+        .ok_or_else(|| format!("Unknown address {}", address))?;
+    let bind = if remote.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+    let socket = UdpSocket::bind(bind).map_err(|err| format!("Failed to open socket: {}", err))?;
+    socket.set_read_timeout(Some(HANDSHAKE_RESEND)).map_err(|err| format!("Failed to configure socket: {}", err))?;
+
+    let started = Instant::now();
+    let mut buffer = [0; 512];
+    while started.elapsed() < HANDSHAKE_TIMEOUT {
+        let _ = socket.send_to(&Message::Hello { board: board_hash }.encode(), remote);
+        let length = match socket.recv_from(&mut buffer) {
+            Ok((length, from)) if from == remote => length,
+            _ => continue
+        };
+        match Message::decode(&buffer[..length]) {
+            Some(Message::Welcome { mode }) => return Ok((Connection::new(socket, remote)?, mode)),
+            Some(Message::Reject) => return Err("The host is playing a different level".to_string()),
+            _ => continue
+        }
+    }
+    Err(format!("No answer from {}", address))
+}
+
+/// A two player game kept in step over the network. Each player's inputs are sent to the other as they happen; until
+/// they arrive the peer is assumed to keep pushing the same direction as before, and if that guess turns out wrong the
+/// sim is rolled back to the tick in question and run forward again with the real input.
+pub struct Session {
+    connection: Connection,
+    config: NetConfig,
+    local_player: usize,
+    sim: Sim,
+    sim_config: SimConfig,
+    local_inputs: Vec<Option<Direction>>,
+    /// The peer's inputs received so far, which are always a contiguous run from tick 0.
+    remote_inputs: Vec<Option<Direction>>,
+    /// The peer input each simulated tick was run with, whether received or guessed.
+    used_remote_inputs: Vec<Option<Direction>>,
+    /// The sim at the start of each of the last few ticks, oldest first.
+    snapshots: VecDeque<Sim>,
+    remote_ack: u64,
+    local_checksums: BTreeMap<u64, u64>,
+    remote_checksums: BTreeMap<u64, u64>,
+    last_heard: Instant
+}
+
+impl Session {
+    /// The host is player one and whoever joined player two.
+    pub fn new(connection: Connection, local_player: usize, board: &Board, config: NetConfig) -> Self {
+        Self {
+            connection,
+            config,
+            local_player,
+            sim: Sim::new(board, 2),
+            sim_config: SimConfig::from_board(board),
+            local_inputs: vec![None; config.input_delay as usize],
+            remote_inputs: Vec::new(),
+            used_remote_inputs: Vec::new(),
+            snapshots: VecDeque::new(),
+            remote_ack: 0,
+            local_checksums: BTreeMap::new(),
+            remote_checksums: BTreeMap::new(),
+            last_heard: Instant::now()
+        }
+    }
+
+    pub fn sim(&self) -> &Sim {
+        &self.sim
+    }
+
+    pub fn mode(&self) -> NetMode {
+        self.config.mode
+    }
+
+    pub fn local_player(&self) -> usize {
+        self.local_player
+    }
+
+    /// The number of ticks for which both players' inputs are known.
+    pub fn confirmed_ticks(&self) -> u64 {
+        (self.remote_inputs.len() as u64).min(self.sim.tick)
+    }
+
+    /// Takes the local player's input for this frame and runs the next tick, unless the peer has fallen too far
+    /// behind. Returns whether a tick was run. Fails when the peer leaves or goes quiet, or when the two sims have
+    /// drifted apart.
+    pub fn advance(&mut self, board: &Board, input: Option<Direction>) -> Result<bool, String> {
+        self.poll(board)?;
+
+        let waiting = self.sim.tick >= self.remote_inputs.len() as u64 + self.config.max_rollback as u64;
+        if !waiting {
+            self.local_inputs.push(input);
+            self.simulate(board);
+        }
+        self.send();
+        Ok(!waiting)
+    }
+
+    /// Catches up with whatever the peer has sent, rolling back if a guess was wrong.
+    fn poll(&mut self, board: &Board) -> Result<(), String> {
+        if let Some(tick) = self.receive()? {
+            self.rollback(board, tick)?;
+        }
+        self.check_desync()
+    }
+
+    /// Reads every packet that has arrived. Returns the first tick that was simulated with a wrong guess, if any.
+    fn receive(&mut self) -> Result<Option<u64>, String> {
+        let mut mispredicted = None;
+        while let Some(message) = self.connection.receive()? {
+            self.last_heard = Instant::now();
+            match message {
+                Message::Inputs { start, inputs, ack, checksum } => {
+                    self.remote_ack = self.remote_ack.max(ack);
+                    if let Some((tick, hash)) = checksum {
+                        self.remote_checksums.insert(tick, hash);
+                    }
+
+                    for (tick, input) in (start..).zip(inputs) {
+                        let tick = tick as usize;
+                        if tick < self.remote_inputs.len() {
+                            continue;
+                        }
+                        // Reordered packets leave a gap, which a later packet will fill
+                        if tick > self.remote_inputs.len() {
+                            break;
+                        }
+
+                        self.remote_inputs.push(input);
+                        if self.used_remote_inputs.get(tick).is_some_and(|used| *used != input) {
+                            mispredicted = Some(mispredicted.map_or(tick as u64, |first: u64| first.min(tick as u64)));
+                        }
+                    }
+                },
+                // Our welcome got lost
+                Message::Hello { .. } => self.connection.send(&Message::Welcome { mode: self.config.mode }),
+                Message::Bye => return Err("The other player left".to_string()),
+                _ => continue
+            }
+        }
+
+        if self.last_heard.elapsed() > DISCONNECT_TIMEOUT {
+            return Err("Lost connection to the other player".to_string());
+        }
+        Ok(mispredicted)
+    }
+
+    fn remote_input(&self, tick: usize) -> Option<Direction> {
+        match self.remote_inputs.get(tick) {
+            Some(input) => *input,
+            None => self.remote_inputs.last().copied().flatten()
+        }
+    }
+
+    fn simulate(&mut self, board: &Board) {
+        let tick = self.sim.tick as usize;
+        let remote = self.remote_input(tick);
+        self.used_remote_inputs.truncate(tick);
+        self.used_remote_inputs.push(remote);
+
+        let mut inputs = [remote; 2];
+        inputs[self.local_player] = self.local_inputs[tick];
+
+        self.snapshots.push_back(self.sim.clone());
+        while self.snapshots.len() > self.config.max_rollback as usize + 1 {
+            self.snapshots.pop_front();
+        }
+        self.sim.step(board, &self.sim_config, &inputs);
+
+        self.local_checksums.insert(tick as u64, self.sim.checksum());
+        let oldest = self.sim.tick.saturating_sub(CHECKSUM_HISTORY);
+        self.local_checksums = self.local_checksums.split_off(&oldest);
+    }
+
+    /// Goes back to the start of `tick` and runs forward again to where the sim was.
+    fn rollback(&mut self, board: &Board, tick: u64) -> Result<(), String> {
+        let current = self.sim.tick;
+        let oldest = current - self.snapshots.len() as u64;
+        if tick < oldest {
+            return Err(format!("Can't roll back to tick {}", tick));
+        }
+
+        let index = (tick - oldest) as usize;
+        self.sim = self.snapshots[index].clone();
+        self.snapshots.truncate(index);
+        while self.sim.tick < current {
+            self.simulate(board);
+        }
+        Ok(())
+    }
+
+    fn check_desync(&mut self) -> Result<(), String> {
+        let confirmed = self.confirmed_ticks();
+        let oldest = self.sim.tick.saturating_sub(CHECKSUM_HISTORY);
+        let remote_checksums = std::mem::take(&mut self.remote_checksums);
+        for (tick, hash) in remote_checksums {
+            if tick < oldest {
+                continue;
+            }
+            if tick >= confirmed {
+                self.remote_checksums.insert(tick, hash);
+                continue;
+            }
+            if self.local_checksums.get(&tick).is_some_and(|local| *local != hash) {
+                return Err(format!("Desync at tick {}", tick));
+            }
+        }
+        Ok(())
+    }
+
+    fn send(&self) {
+        let start = self.remote_ack.min(self.local_inputs.len() as u64);
+        let end = (start as usize + MAX_INPUTS_PER_PACKET).min(self.local_inputs.len());
+        let confirmed = self.confirmed_ticks();
+        let checksum = confirmed.checked_sub(1)
+            .and_then(|tick| self.local_checksums.get(&tick).map(|hash| (tick, *hash)));
+        self.connection.send(&Message::Inputs {
+            start,
+            inputs: self.local_inputs[start as usize..end].to_vec(),
+            ack: self.remote_inputs.len() as u64,
+            checksum
+        });
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.connection.send(&Message::Bye);
+    }
+}
+
+/// Hosts or joins as the command line says: `host [port]` or `join <address>`, plus any `NetConfig` flags. Blocks
+/// until both players are there.
+pub fn connect(args: &[String], board: &Board) -> Result<Session, String> {
+    let config = NetConfig::from_args(args);
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some("host") => {
+            let port = match args.get(2).filter(|arg| !arg.starts_with("--")) {
+                Some(port) => port.parse().map_err(|_| format!("Invalid port {}", port))?,
+                None => DEFAULT_PORT
+            };
+            let lobby = Lobby::host(port)?;
+            println!("Waiting for a player on port {}", lobby.port());
+            let connection = lobby.accept(board.hash(), config.mode)?;
+            Ok(Session::new(connection, 0, board, config))
+        },
+        Some("join") => {
+            let address = args.get(2).ok_or_else(|| "Usage: join <address>".to_string())?;
+            let address = if address.contains(':') { address.clone() } else { format!("{}:{}", address, DEFAULT_PORT) };
+            println!("Joining {}", address);
+            let (connection, mode) = join(&address, board.hash())?;
+            Ok(Session::new(connection, 1, board, NetConfig { mode, ..config }))
+        },
+        _ => Err("Usage: host [port] | join <address>".to_string())
+    }
+}
+
+/// Both players start straight away; there is no menu in online play.
+pub fn enter_online_system(
+    mut commands: Commands,
+    query: Query<Entity, With<misc::StartMessage>>,
+    ben_query: Query<&PlayerId, With<Ben>>,
+    board: Res<Board>,
+    ben_sprite_sheet: Res<BenSpriteSheet>
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+    if !ben_query.iter().any(|player_id| player_id.0 == 1) {
+        ben::spawn_ben(&mut commands, &board, ben_sprite_sheet.atlas.clone(), 1);
+    }
+}
+
+/// Closing the session lets the other player know.
+pub fn leave_online_system(mut commands: Commands) {
+    commands.remove_resource::<Session>();
+}
+
+/// Feeds the local player's keys to the session, and ends the game when it's over or the connection fails.
+pub fn online_system(
+    mut session: ResMut<Session>,
+    mut game_state: ResMut<State<GameState>>,
+    mut end_message_text: ResMut<misc::EndMessageText>,
+    keys: Res<Input<KeyCode>>,
+    board: Res<Board>
+) {
+    let input = ben::direction_keys(0, false).iter()
+        .find(|&&(_, key)| keys.pressed(key))
+        .map(|&(direction, _)| direction);
+
+    if let Err(err) = session.advance(&board, input) {
+        eprintln!("{}", err);
+        end_message_text.0 = err;
+        game_state.set(GameState::End).unwrap();
+        return;
+    }
+
+    let sim = session.sim();
+    if sim.is_over() {
+        let local = sim.bens[session.local_player()].score;
+        let remote = sim.bens[1 - session.local_player()].score;
+        end_message_text.0 = match session.mode() {
+            NetMode::Coop => format!("GAME OVER\nTEAM SCORE {}", local + remote),
+            NetMode::HeadToHead if local == remote => "DRAW".to_string(),
+            NetMode::HeadToHead if local > remote => "YOU WIN".to_string(),
+            NetMode::HeadToHead => "YOU LOSE".to_string()
+        };
+        game_state.set(GameState::End).unwrap();
+    }
+}
+
+/// Mirrors the session's sim onto the entities that draw it.
+pub fn online_render_system(
+    mut commands: Commands,
+    mut ben_query: Query<(Entity, &PlayerId, &mut TilePosition, &mut PreviousTilePosition, &mut BenDirection, &mut BenLives, &mut Score, &mut Visible), With<Ben>>,
+    mut ghost_query: Query<(&GhostReleasePriority, &mut TilePosition, &mut PreviousTilePosition, &mut AttackState, &mut ReleaseState), (With<Ghost>, Without<Ben>)>,
+    pickup_query: Query<(Entity, &TilePosition), (Or<(With<Dot>, With<PowerUp>)>, Without<Ben>, Without<Ghost>)>,
+    mut direction_changed_event: EventWriter<BenDirectionChangedEvent>,
+    mut ghost_scare_timer: ResMut<GhostScareTimer>,
+    mut level: ResMut<Level>,
+    session: Res<Session>,
+    board: Res<Board>,
+    dot_material: Res<DotMaterial>,
+    power_up_sprite_sheet: Res<PowerUpSpriteSheet>
+) {
+    let sim = session.sim();
+    for (ben_entity, player_id, mut position, mut previous_position, mut ben_direction, mut ben_lives, mut score, mut visible) in ben_query.iter_mut() {
+        let sim_ben = match sim.bens.get(player_id.0) {
+            Some(sim_ben) => sim_ben,
+            None => continue
+        };
+
+        if *position != sim_ben.position {
+            *position = sim_ben.position;
+        }
+        previous_position.0 = sim_ben.previous_position;
+        if ben_direction.0 != sim_ben.direction {
+            ben_direction.0 = sim_ben.direction;
+            direction_changed_event.send(BenDirectionChangedEvent(ben_entity, sim_ben.direction));
+        }
+        ben_lives.0 = sim_ben.lives;
+        score.0 = sim_ben.score;
+        visible.is_visible = sim_ben.lives > 0;
+    }
+
+    for (release_priority, mut position, mut previous_position, mut attack_state, mut release_state) in ghost_query.iter_mut() {
+        let sim_ghost = match sim.ghosts.iter().find(|sim_ghost| sim_ghost.release_priority == release_priority.0) {
+            Some(sim_ghost) => sim_ghost,
+            None => continue
+        };
+
+        if *position != sim_ghost.position {
+            *position = sim_ghost.position;
+        }
+        previous_position.0 = sim_ghost.previous_position;
+        *attack_state = sim_ghost.attack_state;
+        *release_state = sim_ghost.release_state;
+    }
+
+    // The scare timer only drives the flashing here
+    let timer = &mut ghost_scare_timer.0;
+    let left = sim.scare_ticks as f32 / sim::TICKS_PER_SECOND;
+    timer.set_elapsed(timer.duration().saturating_sub(Duration::from_secs_f32(left)));
+
+    // A new level brings every dot back
+    if level.0 != sim.level {
+        level.0 = sim.level;
+        for (entity, _) in pickup_query.iter() {
+            commands.entity(entity).despawn();
+        }
+        utils::init_dots_and_power_ups(&mut commands, &board, dot_material.handle.clone(), power_up_sprite_sheet.atlas.clone(), &Default::default());
+        return;
+    }
+    for (entity, position) in pickup_query.iter() {
+        let (i, j) = position.tile();
+        if sim.pickups[i][j] == BoardTile::Empty {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants;
+
+    fn connect(config: NetConfig, board: &Board) -> (Session, Session) {
+        let lobby = Lobby::host(0).unwrap();
+        let address = format!("127.0.0.1:{}", lobby.port());
+        let board_hash = board.hash();
+        let host = std::thread::spawn(move || lobby.accept(board_hash, config.mode).unwrap());
+        let (connection, mode) = join(&address, board_hash).unwrap();
+        assert_eq!(mode, config.mode);
+        (
+            Session::new(host.join().unwrap(), 0, board, config),
+            Session::new(connection, 1, board, config)
+        )
+    }
+
+    /// Each player turns every so often, at different times, so that the peer's guesses are often wrong.
+    fn input(player: usize, tick: u64) -> Option<Direction> {
+        let directions = [Direction::Left, Direction::Up, Direction::Right, Direction::Down];
+        Some(directions[((tick / (17 + player as u64 * 6)) as usize + player) % 4])
+    }
+
+    #[test]
+    fn messages_survive_encoding() {
+        let messages = [
+            Message::Hello { board: 0x0123_4567_89ab_cdef },
+            Message::Welcome { mode: NetMode::HeadToHead },
+            Message::Reject,
+            Message::Inputs { start: 42, inputs: vec![None, Some(Direction::Up), Some(Direction::Left)], ack: 40, checksum: Some((39, 7)) },
+            Message::Inputs { start: 0, inputs: Vec::new(), ack: 0, checksum: None },
+            Message::Bye
+        ];
+        for message in messages {
+            assert_eq!(Message::decode(&message.encode()), Some(message));
+        }
+        assert_eq!(Message::decode(b"not a packet"), None);
+    }
+
+    #[test]
+    fn joining_with_another_level_is_rejected() {
+        let board = Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET);
+        let lobby = Lobby::host(0).unwrap();
+        let address = format!("127.0.0.1:{}", lobby.port());
+        let board_hash = board.hash();
+        std::thread::spawn(move || lobby.accept(board_hash, NetMode::Coop));
+        assert!(join(&address, board_hash ^ 1).is_err());
+    }
+
+    #[test]
+    fn peers_stay_in_sync_over_loopback() {
+        let board = Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET);
+        // Without input delay every turn the peer makes arrives late, and has to be rolled back for
+        let config = NetConfig { input_delay: 0, max_rollback: 8, mode: NetMode::Coop };
+        let (mut host, mut guest) = connect(config, &board);
+
+        let ticks = 600;
+        let started = Instant::now();
+        while host.confirmed_ticks() < ticks || guest.confirmed_ticks() < ticks {
+            assert!(started.elapsed() < Duration::from_secs(30), "peers stopped making progress");
+            for session in [&mut host, &mut guest] {
+                let player = session.local_player();
+                if session.sim().tick < ticks {
+                    session.advance(&board, input(player, session.sim().tick)).unwrap();
+                } else {
+                    // Keep exchanging packets until the peer has confirmed everything too
+                    session.poll(&board).unwrap();
+                    session.send();
+                }
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        // Both ended up where a single sim fed the same inputs would have
+        let sim_config = SimConfig::from_board(&board);
+        let mut expected = Sim::new(&board, 2);
+        let delayed = |player: usize, tick: u64| if tick < config.input_delay as u64 {
+            None
+        } else {
+            input(player, tick - config.input_delay as u64)
+        };
+        for tick in 0..ticks {
+            expected.step(&board, &sim_config, &[delayed(0, tick), delayed(1, tick)]);
+        }
+        assert_eq!(host.sim().checksum(), expected.checksum());
+        assert_eq!(guest.sim().checksum(), expected.checksum());
+    }
+}
@@ -1,70 +1,53 @@
 use std::collections::VecDeque;
-use bevy::prelude::*;
 use crate::board::Board;
-use crate::enums::{Direction, CollisionType};
-use crate::utils;
+use crate::enums::Direction;
+use crate::movement::TilePosition;
 
-pub struct Path(VecDeque<(f32, f32)>);
+/// Waypoints for a ghost to walk through in order. Out in the maze these are the centres of neighbouring tiles;
+/// inside the ghost house they may sit between tiles.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Path(VecDeque<TilePosition>);
 
 impl Path {
     pub fn new() -> Self {
         Self(VecDeque::new())
     }
 
-    pub fn shortest_to_transform(initial_transform: &Transform, target_transform: &Transform, board: &Board, speed: f32, collision_type: CollisionType) -> Self {
-        fn shortest_to_transform_helper(
+    pub fn shortest_to_tile(initial_position: &TilePosition, target: (usize, usize), board: &Board) -> Self {
+        fn shortest_to_tile_helper(
             _path: &mut Path,
-            _initial_transform: &mut Transform,
-            _target_transform: &Transform,
-            _board: &Board,
-            _speed: f32,
-            _collision_type: CollisionType
+            _tile: (usize, usize),
+            _target: (usize, usize),
+            _board: &Board
         ) {
-            if utils::did_collide(_initial_transform, _target_transform, _board, _collision_type) {
+            if _tile == _target {
                 return;
             }
 
-            let up_position = _board.get_coordinates(_initial_transform.translation.x, _initial_transform.translation.y, Direction::Up, _speed);
-            let right_position = _board.get_coordinates(_initial_transform.translation.x, _initial_transform.translation.y, Direction::Right, _speed);
-            let down_position = _board.get_coordinates(_initial_transform.translation.x, _initial_transform.translation.y, Direction::Down, _speed);
-            let left_position = _board.get_coordinates(_initial_transform.translation.x, _initial_transform.translation.y, Direction::Left, _speed);
-
-
+            let position = TilePosition::centred(_tile.0, _tile.1);
             let mut available_directions: Vec<Direction> = Vec::new();
-            if utils::can_move_up(_initial_transform, &_board, _speed) && !_path.0.contains(&up_position) {
-                available_directions.push(Direction::Up);
-            }
-            if utils::can_move_right(_initial_transform, &_board, _speed) && !_path.0.contains(&right_position) {
-                available_directions.push(Direction::Right);
-            }
-            if utils::can_move_down(_initial_transform, &_board, _speed) && !_path.0.contains(&down_position) {
-                available_directions.push(Direction::Down);
-            }
-            if utils::can_move_left(_initial_transform, &_board, _speed) && !_path.0.contains(&left_position) {
-                available_directions.push(Direction::Left);
+            for direction in [Direction::Up, Direction::Right, Direction::Down, Direction::Left] {
+                let (i, j) = _board.neighbour(_tile.0, _tile.1, direction);
+                if position.can_move(_board, direction) && !_path.0.contains(&TilePosition::centred(i, j)) {
+                    available_directions.push(direction);
+                }
             }
 
-            let should_move_up = _initial_transform.translation.y < _target_transform.translation.y;
-            let should_move_right = _initial_transform.translation.x < _target_transform.translation.x;
-            let should_move_down = _initial_transform.translation.y > _target_transform.translation.y;
-            let should_move_left = _initial_transform.translation.x > _target_transform.translation.x;
+            // Measured across the edges of the board so that tunnels are taken when they are the shorter way round
+            let (dx, dy) = position.delta(&TilePosition::centred(_target.0, _target.1), _board);
+            let should_move_up = dy > 0;
+            let should_move_right = dx > 0;
+            let should_move_down = dy < 0;
+            let should_move_left = dx < 0;
 
             let mut move_forward = |direction: Direction| {
-                match direction {
-                    Direction::Up => _initial_transform.translation.y = up_position.1,
-                    Direction::Right => _initial_transform.translation.x = right_position.0,
-                    Direction::Down => _initial_transform.translation.y = down_position.1,
-                    Direction::Left => _initial_transform.translation.x = left_position.0,
-                }
-
-                _path.push_back((_initial_transform.translation.x, _initial_transform.translation.y));
-                shortest_to_transform_helper(
-                    _path, 
-                    _initial_transform, 
-                    _target_transform, 
-                    _board, 
-                    _speed,
-                    _collision_type
+                let (i, j) = _board.neighbour(_tile.0, _tile.1, direction);
+                _path.push_back(TilePosition::centred(i, j));
+                shortest_to_tile_helper(
+                    _path,
+                    (i, j),
+                    _target,
+                    _board
                 );
             };
 
@@ -83,52 +66,46 @@ impl Path {
             }
         }
 
+        // Actors between two tiles first return to the centre of the one they are in
         let mut path = Self::new();
-        shortest_to_transform_helper(&mut path, &mut initial_transform.clone(), target_transform, board, speed, collision_type);
+        if !initial_position.is_centered() {
+            path.push_back(TilePosition::centred(initial_position.i, initial_position.j));
+        }
+        shortest_to_tile_helper(&mut path, initial_position.tile(), target, board);
         path
     }
 
-    pub fn shortest_to_ghost_spawn(initial_transform: &Transform, board: &Board, speed: f32) -> Self {
-        // Step 1: Move to directly above the ghost gate
-        let (target_x, target_y) = utils::get_ghost_spawn_coordinates(board);
-        let (_, temp_y) = board.indeces_to_coordinates(11, 0);
-        let mut path = Self::shortest_to_transform(
-            initial_transform,
-            &Transform {
-                translation: Vec3::new(target_x, temp_y, initial_transform.translation.z),
-                ..Default::default()
-            },
-            board,
-            speed,
-            CollisionType::Exact
-        );
+    /// Walks `position` along the waypoints for up to `distance` units, dropping each one as it is reached. Returns the
+    /// distance left over if the path runs out.
+    pub fn follow(&mut self, position: &mut TilePosition, board: &Board, mut distance: i32) -> i32 {
+        while distance > 0 {
+            let target = match self.0.front() {
+                Some(target) => *target,
+                None => break
+            };
 
-        // Step 2: Move through the ghost gate
-        while path.peek_back().unwrap().0 == target_x && path.peek_back().unwrap().1 > target_y {
-            let (x, y) = path.peek_back().unwrap().clone();
-            path.push_back((x, y - speed));
+            distance = position.move_towards(board, &target, distance);
+            if *position == target {
+                self.0.pop_front();
+            }
         }
 
-        path
+        distance
     }
 
-    pub fn push_back(&mut self, position: (f32, f32)) {
+    pub fn push_back(&mut self, position: TilePosition) {
         self.0.push_back(position);
     }
 
-    pub fn pop_front(&mut self) -> Option<(f32, f32)> {
-        self.0.pop_front()
-    }
-
     pub fn clear(&mut self) {
         self.0.clear();
     }
 
-    pub fn peek_back(&self) -> Option<&(f32, f32)> {
-        if self.0.len() > 0 {
-            self.0.get(self.0.len() - 1)
-        } else {
-            None
-        }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn waypoints(&self) -> impl Iterator<Item = &TilePosition> {
+        self.0.iter()
     }
 }
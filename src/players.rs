@@ -0,0 +1,144 @@
+use std::collections::HashSet;
+use bevy::prelude::*;
+use crate::ben::{Ben, BenLives};
+use crate::board::{Board, BoardTile};
+use crate::constants;
+use crate::dot::{Dot, DotMaterial};
+use crate::events::CueEvent;
+use crate::misc::Level;
+use crate::movement::TilePosition;
+use crate::power_up::{PowerUp, PowerUpSpriteSheet};
+use crate::score::Score;
+use crate::sequence::{self, Cue};
+use crate::utils;
+
+/// Everything of a player's game that has to be put aside while the other player takes their turn.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerState {
+    pub score: u64,
+    pub lives: u8,
+    pub level: u32,
+    /// Tiles whose dot or power-up has been eaten.
+    pub eaten: HashSet<(usize, usize)>
+}
+
+impl Default for PlayerState {
+    fn default() -> Self {
+        Self {
+            score: 0,
+            lives: constants::BEN_LIVES,
+            level: 1,
+            eaten: HashSet::new()
+        }
+    }
+}
+
+/// The players of the current game, who take turns on the one board. The state of the player whose turn it is lives
+/// in the game itself and is only stored here when the turn passes.
+pub struct Players {
+    pub current: usize,
+    pub states: Vec<PlayerState>
+}
+
+impl Default for Players {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl Players {
+    pub fn new(count: usize) -> Self {
+        Self {
+            current: 0,
+            states: vec![PlayerState::default(); count]
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Who plays after the current player has lost a life, leaving them with `lives`. Turns alternate on every death
+    /// for as long as the other player has lives left. `None` once everyone is out.
+    pub fn next_player(&self, lives: u8) -> Option<usize> {
+        let other = (self.current + 1) % self.count();
+        if other != self.current && self.states[other].lives > 0 {
+            Some(other)
+        } else if lives > 0 {
+            Some(self.current)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn player_name(player: usize) -> &'static str {
+    match player {
+        0 => "PLAYER ONE",
+        _ => "PLAYER TWO"
+    }
+}
+
+/// Puts the current player's game aside and brings back the next player's.
+pub fn switch_players_cue_system(
+    mut commands: Commands,
+    mut cue_event: EventReader<CueEvent>,
+    mut players: ResMut<Players>,
+    mut level: ResMut<Level>,
+    mut query_set: QuerySet<(
+        Query<&mut Score, With<Ben>>,
+        Query<&mut BenLives, With<Ben>>,
+        Query<(Entity, &TilePosition), Or<(With<Dot>, With<PowerUp>)>>
+    )>,
+    board: Res<Board>,
+    dot_material: Res<DotMaterial>,
+    power_up_sprite_sheet: Res<PowerUpSpriteSheet>
+) {
+    if !sequence::was_cued(&mut cue_event, Cue::SwitchPlayers) {
+        return;
+    }
+
+    let lives = query_set.q1_mut().single_mut().unwrap().0;
+    let next = match players.next_player(lives) {
+        Some(next) if next != players.current => next,
+        _ => return
+    };
+
+    // Whatever is still on the board hasn't been eaten
+    let mut eaten = HashSet::new();
+    for i in 0..board.height() {
+        for j in 0..board.width() {
+            if let Some(BoardTile::Dot) | Some(BoardTile::PowerUp) = board.try_get(i, j) {
+                eaten.insert((i, j));
+            }
+        }
+    }
+    for (entity, position) in query_set.q2().iter() {
+        eaten.remove(&position.tile());
+        commands.entity(entity).despawn();
+    }
+
+    let current = players.current;
+    players.states[current] = PlayerState {
+        score: query_set.q0_mut().single_mut().unwrap().0,
+        lives,
+        level: level.0,
+        eaten
+    };
+
+    players.current = next;
+    let state = players.states[next].clone();
+    query_set.q0_mut().single_mut().unwrap().0 = state.score;
+    query_set.q1_mut().single_mut().unwrap().0 = state.lives;
+    level.0 = state.level;
+    utils::init_dots_and_power_ups(&mut commands, &board, dot_material.handle.clone(), power_up_sprite_sheet.atlas.clone(), &state.eaten);
+}
+
+/// A new game, or a restarted one, starts with player one and everyone's progress wiped.
+pub fn reset_players_system(
+    mut players: ResMut<Players>,
+    mut level: ResMut<Level>
+) {
+    *players = Players::new(players.count());
+    level.0 = 1;
+}
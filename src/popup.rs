@@ -0,0 +1,61 @@
+use bevy::prelude::*;
+use crate::board::Board;
+use crate::events::ScorePopupEvent;
+use crate::maze::MazeTheme;
+use crate::misc;
+
+const POPUP_SECONDS: f32 = 1.;
+// In tiles per second
+const POPUP_RISE: f32 = 0.5;
+
+/// Points shown where they were scored, which float up a little and disappear.
+pub struct ScorePopup(pub Timer);
+
+pub fn spawn_score_popup_system(
+    mut commands: Commands,
+    mut score_popup_event: EventReader<ScorePopupEvent>,
+    font_material: Res<misc::FontMaterial>,
+    maze_theme: Res<MazeTheme>,
+    board: Res<Board>
+) {
+    for event in score_popup_event.iter() {
+        spawn_popup(&mut commands, &event.points.to_string(), event.position.to_coordinates(&board), &font_material, &maze_theme);
+    }
+}
+
+/// Floats `text` up from `(x, y)` like the points of a score popup.
+pub fn spawn_popup(commands: &mut Commands, text: &str, (x, y): (f32, f32), font_material: &misc::FontMaterial, maze_theme: &MazeTheme) {
+    let text_style = TextStyle {
+        font: font_material.handle.clone(),
+        font_size: maze_theme.popup_size,
+        color: maze_theme.popup
+    };
+    let text_alignment = TextAlignment {
+        vertical: VerticalAlign::Center,
+        horizontal: HorizontalAlign::Center
+    };
+    commands.spawn_bundle(Text2dBundle {
+        text: Text::with_section(text, text_style, text_alignment),
+        transform: Transform {
+            translation: Vec3::new(x, y, 50.),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .insert(ScorePopup(Timer::from_seconds(POPUP_SECONDS, false)));
+}
+
+/// Runs in every state, so popups keep rising through the freeze after a ghost is eaten.
+pub fn score_popup_system(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut ScorePopup, &mut Transform)>,
+    board: Res<Board>,
+    time: Res<Time>
+) {
+    for (popup_entity, mut score_popup, mut transform) in query.iter_mut() {
+        transform.translation.y += POPUP_RISE * board.cell_size() * time.delta_seconds();
+        if score_popup.0.tick(time.delta()).finished() {
+            commands.entity(popup_entity).despawn();
+        }
+    }
+}
@@ -1,29 +1,38 @@
 use bevy::prelude::*;
+use crate::movement::TilePosition;
+use crate::animation::{self, Animation, AnimationClip};
 
 pub struct PowerUp;
 
-pub struct PowerUpAnimationTimer(pub Timer);
+pub struct PowerUpSpriteSheet {
+    pub atlas: Handle<TextureAtlas>
+}
+
+pub const SHEET_COLUMNS: usize = 2;
 
-pub struct PowerUpMaterials {
-    pub material_1: Handle<ColorMaterial>,
-    pub material_2: Handle<ColorMaterial>
+pub fn power_up_animation() -> Animation {
+    Animation::new(vec![
+        (animation::BLINK, AnimationClip::new(&[0, 1], 1., true))
+    ], animation::BLINK)
 }
 
 #[derive(Bundle)]
 pub struct PowerUpBundle {
     pub power_up: PowerUp,
-    pub animation_timer: PowerUpAnimationTimer,
+    pub position: TilePosition,
+    pub animation: Animation,
 
     #[bundle]
-    pub sprite_bundle: SpriteBundle
+    pub sprite_sheet_bundle: SpriteSheetBundle
 }
 
 impl Default for PowerUpBundle {
     fn default() -> Self {
         Self {
             power_up: PowerUp,
-            animation_timer: PowerUpAnimationTimer(Timer::from_seconds(1., true)),
-            sprite_bundle: SpriteBundle::default()
+            position: TilePosition::default(),
+            animation: power_up_animation(),
+            sprite_sheet_bundle: SpriteSheetBundle::default()
         } 
     }
 }
@@ -0,0 +1,236 @@
+use bevy::prelude::*;
+use crate::ben::{Ben, BenSpeed};
+use crate::board::{Board, BoardTile};
+use crate::constants;
+use crate::dot::Dot;
+use crate::enums::GameState;
+use crate::fruit::{self, Fruit, FruitPickup};
+use crate::ghost::{Ghost, GhostSpeed};
+use crate::hud::HudMaterials;
+use crate::misc::{self, Level};
+use crate::movement::TilePosition;
+use crate::power_up::{PowerUp, PowerUpSpriteSheet};
+use crate::utils;
+use crate::view::BoardView;
+
+// How far one press of a speed key changes a speed, and the range it stays in
+const SPEED_STEP: f32 = 5.;
+const SPEED_MIN: f32 = 10.;
+const SPEED_MAX: f32 = 200.;
+
+/// Practice mode, toggled from the main menu. Its cheats are only there while it is on, and nothing scored in it counts
+/// towards the high score.
+pub struct Practice {
+    pub active: bool,
+    pub invincible: bool,
+    pub infinite_power_ups: bool,
+    pub ghosts_frozen: bool,
+    pub ben_speed: f32,
+    pub ghost_speed: f32
+}
+
+impl Default for Practice {
+    fn default() -> Self {
+        Self {
+            active: false,
+            invincible: false,
+            infinite_power_ups: false,
+            ghosts_frozen: false,
+            ben_speed: constants::BEN_SPEED_DEFAULT,
+            ghost_speed: constants::GHOST_SPEED_DEFAULT
+        }
+    }
+}
+
+impl Practice {
+    pub fn is_invincible(&self) -> bool {
+        self.active && self.invincible
+    }
+
+    pub fn has_infinite_power_ups(&self) -> bool {
+        self.active && self.infinite_power_ups
+    }
+
+    pub fn are_ghosts_frozen(&self) -> bool {
+        self.active && self.ghosts_frozen
+    }
+
+    fn panel_text(&self) -> String {
+        let on_off = |on: bool| if on { "ON" } else { "OFF" };
+        format!(
+            "PRACTICE\nF1 invincible: {}\nF2 infinite power-ups: {}\nF3 freeze ghosts: {}\nF4 skip level\n[ ] Ben speed: {}\n- = ghost speed: {}\nLeft click power-up, right click fruit",
+            on_off(self.invincible),
+            on_off(self.infinite_power_ups),
+            on_off(self.ghosts_frozen),
+            self.ben_speed,
+            self.ghost_speed
+        )
+    }
+}
+
+fn step_speed(speed: f32, keys: &Input<KeyCode>, down: KeyCode, up: KeyCode) -> f32 {
+    let mut speed = speed;
+    if keys.just_pressed(down) {
+        speed -= SPEED_STEP;
+    }
+    if keys.just_pressed(up) {
+        speed += SPEED_STEP;
+    }
+    speed.clamp(SPEED_MIN, SPEED_MAX)
+}
+
+pub struct PracticePanel;
+
+/// The cheat keys, and dropping pickups under the cursor. Runs after the systems that end play, so a death or a
+/// cleared maze on the same frame wins over skipping the level.
+pub fn practice_controls_system(
+    mut commands: Commands,
+    mut practice: ResMut<Practice>,
+    mut game_state: ResMut<State<GameState>>,
+    pickup_query: Query<&TilePosition, Or<(With<Dot>, With<PowerUp>, With<FruitPickup>)>>,
+    (keys, mouse, windows, board_view): (Res<Input<KeyCode>>, Res<Input<MouseButton>>, Res<Windows>, Res<BoardView>),
+    (level, board, power_up_sprite_sheet, hud_materials): (Res<Level>, Res<Board>, Res<PowerUpSpriteSheet>, Res<HudMaterials>)
+) {
+    if !practice.active {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::F1) {
+        practice.invincible = !practice.invincible;
+    }
+    if keys.just_pressed(KeyCode::F2) {
+        practice.infinite_power_ups = !practice.infinite_power_ups;
+    }
+    if keys.just_pressed(KeyCode::F3) {
+        practice.ghosts_frozen = !practice.ghosts_frozen;
+    }
+    // Only written when they change, so the panel isn't redrawn every frame
+    let ben_speed = step_speed(practice.ben_speed, &keys, KeyCode::LBracket, KeyCode::RBracket);
+    if ben_speed != practice.ben_speed {
+        practice.ben_speed = ben_speed;
+    }
+    let ghost_speed = step_speed(practice.ghost_speed, &keys, KeyCode::Minus, KeyCode::Equals);
+    if ghost_speed != practice.ghost_speed {
+        practice.ghost_speed = ghost_speed;
+    }
+
+    // Skipping a level goes on to the next one just as clearing the maze does
+    if keys.just_pressed(KeyCode::F4) {
+        let _ = game_state.set(GameState::NextLevel);
+        return;
+    }
+
+    let left = mouse.just_pressed(MouseButton::Left);
+    if !left && !mouse.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let cursor_position = match windows.get_primary().and_then(|window| window.cursor_position()) {
+        Some(position) => position,
+        None => return
+    };
+    let cursor_position = board_view.to_board(cursor_position);
+    let (i, j) = match utils::pick_indeces(cursor_position.x, cursor_position.y, &board) {
+        Some(indeces) => indeces,
+        None => return
+    };
+
+    // Only onto an open tile without a pickup already on it
+    if !board.try_get(i, j).is_some_and(BoardTile::is_walkable) {
+        return;
+    }
+    if pickup_query.iter().any(|position| position.tile() == (i, j)) {
+        return;
+    }
+    if left {
+        utils::spawn_power_up(&mut commands, &board, power_up_sprite_sheet.atlas.clone(), i, j);
+    } else {
+        let fruit = Fruit::for_level(level.0);
+        fruit::spawn_fruit(&mut commands, &board, hud_materials.fruit(fruit), fruit, i, j);
+    }
+}
+
+/// Puts practice's speeds on Ben and the ghosts, and the usual ones back once practice is off.
+pub fn practice_speed_system(
+    mut query_set: QuerySet<(
+        Query<&mut BenSpeed, With<Ben>>,
+        Query<&mut GhostSpeed, With<Ghost>>
+    )>,
+    practice: Res<Practice>
+) {
+    let (ben_speed, ghost_speed) = if practice.active {
+        (practice.ben_speed, practice.ghost_speed)
+    } else {
+        (constants::BEN_SPEED_DEFAULT, constants::GHOST_SPEED_DEFAULT)
+    };
+    for mut speed in query_set.q0_mut().iter_mut() {
+        if speed.0 != ben_speed {
+            speed.0 = ben_speed;
+        }
+    }
+    for mut speed in query_set.q1_mut().iter_mut() {
+        if speed.0 != ghost_speed {
+            speed.0 = ghost_speed;
+        }
+    }
+}
+
+/// Lists the cheats and their states above the maze while practice is on.
+pub fn practice_panel_system(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Text), With<PracticePanel>>,
+    practice: Res<Practice>,
+    font_material: Res<misc::FontMaterial>,
+    board: Res<Board>
+) {
+    match query.single_mut() {
+        Ok((panel_entity, mut text)) => {
+            if !practice.active {
+                commands.entity(panel_entity).despawn();
+            } else if practice.is_changed() {
+                text.sections[0].value = practice.panel_text();
+            }
+        },
+        Err(_) => {
+            if !practice.active {
+                return;
+            }
+
+            let text_style = TextStyle {
+                font: font_material.handle.clone(),
+                font_size: 16.,
+                color: Color::YELLOW
+            };
+            let text_alignment = TextAlignment {
+                vertical: VerticalAlign::Top,
+                horizontal: HorizontalAlign::Left
+            };
+            commands.spawn_bundle(Text2dBundle {
+                text: Text::with_section(practice.panel_text(), text_style, text_alignment),
+                transform: Transform {
+                    translation: Vec3::new(board.cell_size(), board.height() as f32 * board.cell_size(), 100.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(PracticePanel);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cheats_only_apply_while_practicing() {
+        let mut practice = Practice {
+            invincible: true,
+            infinite_power_ups: true,
+            ghosts_frozen: true,
+            ..Default::default()
+        };
+        assert!(!practice.is_invincible() && !practice.has_infinite_power_ups() && !practice.are_ghosts_frozen());
+        practice.active = true;
+        assert!(practice.is_invincible() && practice.has_infinite_power_ups() && practice.are_ghosts_frozen());
+    }
+}
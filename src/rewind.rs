@@ -0,0 +1,278 @@
+use std::collections::VecDeque;
+use bevy::prelude::*;
+use crate::ben::{Ben, BenDirection, BenLives, BenNextDirection, PlayerId};
+use crate::board::Board;
+use crate::constants;
+use crate::dot::Dot;
+use crate::editor::EditorSession;
+use crate::enums::GameState;
+use crate::ghost::{Ghost, AttackState, ReleaseState, GhostPath, GhostReleasePriority, GhostReviveTimer, GhostChain, GhostScareTimer, GhostReleaseTimer, GhostHouse};
+use crate::misc::{self, Level};
+use crate::movement::TilePosition;
+use crate::power_up::PowerUp;
+use crate::practice::Practice;
+use crate::save::{self, SaveState, PendingLoad};
+use crate::score::Score;
+use crate::sim;
+use crate::utils;
+
+pub const REWIND_KEY: KeyCode = KeyCode::Back;
+
+/// A set of tiles as one bit per tile of the board, row by row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TileBits {
+    width: usize,
+    words: Vec<u64>
+}
+
+impl TileBits {
+    pub fn new(board: &Board) -> Self {
+        Self {
+            width: board.width(),
+            words: vec![0; (board.width() * board.height()).div_ceil(64)]
+        }
+    }
+
+    pub fn insert(&mut self, (i, j): (usize, usize)) {
+        let bit = i * self.width + j;
+        self.words[bit / 64] |= 1 << (bit % 64);
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let width = self.width;
+        self.words.iter()
+            .enumerate()
+            .flat_map(|(word_index, word)| (0..64)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| word_index * 64 + bit))
+            .map(move |bit| (bit / width, bit % width))
+    }
+}
+
+/// One frame of play. The actors are kept as in a `SaveState`, but the pickups as bitsets and without the board's
+/// hash, since a snapshot never leaves the level it was taken on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    /// Has no pickups or board hash of its own.
+    pub actors: SaveState,
+    pub dots: TileBits,
+    pub power_ups: TileBits
+}
+
+impl Snapshot {
+    pub fn into_save_state(self, board: &Board) -> SaveState {
+        SaveState {
+            board: board.hash(),
+            dots: self.dots.tiles().collect(),
+            power_ups: self.power_ups.tiles().collect(),
+            ..self.actors
+        }
+    }
+}
+
+/// Snapshots of the last `constants::REWIND_SECONDS` of play, one per frame, newest at the back.
+pub struct Rewind {
+    snapshots: VecDeque<Snapshot>
+}
+
+impl Default for Rewind {
+    fn default() -> Self {
+        Self {
+            snapshots: VecDeque::new()
+        }
+    }
+}
+
+impl Rewind {
+    pub fn capacity() -> usize {
+        sim::ticks(constants::REWIND_SECONDS) as usize
+    }
+
+    pub fn record(&mut self, snapshot: Snapshot) {
+        if self.snapshots.len() == Self::capacity() {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    /// The latest snapshot, which stops being kept. `None` once the buffer has been rewound all the way.
+    pub fn step_back(&mut self) -> Option<Snapshot> {
+        self.snapshots.pop_back()
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
+
+/// Rewinding would make a score meaningless, so it's only there when nothing is scored: in playtests from the editor
+/// and in practice.
+pub fn can_rewind(editor_session: &EditorSession, practice: &Practice) -> bool {
+    editor_session.playtesting || practice.active
+}
+
+pub struct RewindMessage;
+
+/// Snapshots the game at the start of every frame of play.
+pub fn record_rewind_system(
+    mut rewind: ResMut<Rewind>,
+    ben_query: Query<(&PlayerId, &TilePosition, &BenDirection, &BenNextDirection, &BenLives, &Score), With<Ben>>,
+    ghost_query: Query<(&GhostReleasePriority, &TilePosition, &AttackState, &ReleaseState, &GhostPath, &GhostReviveTimer), With<Ghost>>,
+    pickup_query: Query<(&TilePosition, Option<&Dot>), Or<(With<Dot>, With<PowerUp>)>>,
+    (ghost_chain, ghost_scare_timer, ghost_release_timer, ghost_house): (Res<GhostChain>, Res<GhostScareTimer>, Res<GhostReleaseTimer>, Res<GhostHouse>),
+    (level, editor_session, practice, board): (Res<Level>, Res<EditorSession>, Res<Practice>, Res<Board>)
+) {
+    if !can_rewind(&editor_session, &practice) {
+        rewind.clear();
+        return;
+    }
+
+    let mut dots = TileBits::new(&board);
+    let mut power_ups = TileBits::new(&board);
+    for (position, dot) in pickup_query.iter() {
+        match dot {
+            Some(_) => dots.insert(position.tile()),
+            None => power_ups.insert(position.tile())
+        }
+    }
+    rewind.record(Snapshot {
+        actors: save::capture_actors(&ben_query, &ghost_query, &ghost_chain, &ghost_scare_timer, &ghost_release_timer, &ghost_house, &level),
+        dots,
+        power_ups
+    });
+}
+
+/// Starts rewinding while `REWIND_KEY` is held. Runs after the systems that end play, so a death or a cleared maze on
+/// the same frame wins and the key is ignored until play resumes.
+pub fn start_rewind_system(
+    mut game_state: ResMut<State<GameState>>,
+    rewind: Res<Rewind>,
+    keys: Res<Input<KeyCode>>
+) {
+    if keys.pressed(REWIND_KEY) && !rewind.snapshots.is_empty() {
+        let _ = game_state.set(GameState::Rewind);
+    }
+}
+
+pub fn enter_rewind_system(
+    mut commands: Commands,
+    font_material: Res<misc::FontMaterial>,
+    board: Res<Board>
+) {
+    let (x, y) = utils::get_ghost_spawn_coordinates(&board);
+    let text_style = TextStyle {
+        font: font_material.handle.clone(),
+        font_size: 28.,
+        color: Color::CYAN
+    };
+    let text_alignment = TextAlignment {
+        vertical: VerticalAlign::Center,
+        horizontal: HorizontalAlign::Center
+    };
+    commands.spawn_bundle(Text2dBundle {
+        text: Text::with_section("<< REWIND", text_style, text_alignment),
+        transform: Transform {
+            translation: Vec3::new(x, y - board.cell_size() * 3., 100.),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .insert(RewindMessage);
+}
+
+/// Steps back a frame for every frame `REWIND_KEY` stays held, and plays on from there once it is let go. The snapshots
+/// are put into the game by `save::apply_save_system`.
+pub fn rewind_system(
+    mut commands: Commands,
+    mut rewind: ResMut<Rewind>,
+    mut game_state: ResMut<State<GameState>>,
+    keys: Res<Input<KeyCode>>,
+    board: Res<Board>
+) {
+    if !keys.pressed(REWIND_KEY) {
+        game_state.set(GameState::Default).unwrap();
+        return;
+    }
+
+    // Holding on past the oldest snapshot just stays there
+    if let Some(snapshot) = rewind.step_back() {
+        commands.insert_resource(PendingLoad(snapshot.into_save_state(&board)));
+    }
+}
+
+/// A restarted game can't be rewound into the last one.
+pub fn reset_rewind_system(mut rewind: ResMut<Rewind>) {
+    rewind.clear();
+}
+
+pub fn exit_rewind_system(
+    mut commands: Commands,
+    query: Query<Entity, With<RewindMessage>>
+) {
+    for rewind_message_entity in query.iter() {
+        commands.entity(rewind_message_entity).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use super::*;
+
+    fn board() -> Board {
+        Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET)
+    }
+
+    fn snapshot(board: &Board, level: u32) -> Snapshot {
+        Snapshot {
+            actors: SaveState {
+                board: 0,
+                level,
+                bens: Vec::new(),
+                ghosts: Vec::new(),
+                dots: HashSet::new(),
+                power_ups: HashSet::new(),
+                ghost_chain: 0,
+                scare_seconds: 0.,
+                release_seconds: 0.,
+                dot_counters: [0; 4],
+                global_dot_counter: None
+            },
+            dots: TileBits::new(board),
+            power_ups: TileBits::new(board)
+        }
+    }
+
+    #[test]
+    fn full_buffer_forgets_the_oldest_snapshot() {
+        let board = board();
+        let mut rewind = Rewind::default();
+        for level in 0..Rewind::capacity() as u32 + 5 {
+            rewind.record(snapshot(&board, level));
+        }
+
+        let mut levels = Vec::new();
+        while let Some(snapshot) = rewind.step_back() {
+            levels.push(snapshot.actors.level);
+        }
+        assert_eq!(levels.len(), Rewind::capacity());
+        assert_eq!(levels.first(), Some(&(Rewind::capacity() as u32 + 4)));
+        assert_eq!(levels.last(), Some(&5));
+    }
+
+    #[test]
+    fn snapshot_restores_its_pickups_on_the_current_board() {
+        let board = board();
+        let mut snapshot = snapshot(&board, 1);
+        let last = (board.height() - 1, board.width() - 1);
+        for tile in [(0, 0), (1, 2), last] {
+            snapshot.dots.insert(tile);
+        }
+        snapshot.power_ups.insert((3, 1));
+
+        let save_state = snapshot.into_save_state(&board);
+        assert_eq!(save_state.board, board.hash());
+        assert_eq!(save_state.dots, [(0, 0), (1, 2), last].into_iter().collect());
+        assert_eq!(save_state.power_ups, [(3, 1)].into_iter().collect());
+    }
+}
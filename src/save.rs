@@ -0,0 +1,638 @@
+use std::collections::HashSet;
+use std::fs;
+use std::str::FromStr;
+use std::time::Duration;
+use bevy::prelude::*;
+use crate::animation::{self, Animation};
+use crate::autopilot::Autopilot;
+use crate::ben::{Ben, BenDirection, BenLives, BenNextDirection, Caught, PlayerId};
+use crate::board::{Board, BoardTile};
+use crate::dot::{Dot, DotMaterial};
+use crate::enums::{Direction, GameState};
+use crate::ghost::{Ghost, AttackState, ReleaseState, GhostPath, GhostReleasePriority, GhostReviveTimer, GhostChain, GhostScareTimer, GhostReleaseTimer, GhostHouse};
+use crate::maze::MazeTheme;
+use crate::misc::{self, Level};
+use crate::movement::{self, TilePosition, PreviousTilePosition};
+use crate::path::Path;
+use crate::players::Players;
+use crate::popup;
+use crate::power_up::{PowerUp, PowerUpSpriteSheet};
+use crate::practice::Practice;
+use crate::score::Score;
+use crate::sequence::Sequence;
+use crate::utils;
+use crate::versus::Versus;
+
+pub const SAVE_PATH: &str = "save.txt";
+/// Bumped whenever the format changes; saves of any other version are turned down. Version 2 hashes boards with
+/// `utils::StableHasher`.
+pub const SAVE_VERSION: u32 = 2;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenSave {
+    pub player: usize,
+    pub position: TilePosition,
+    pub direction: Direction,
+    pub next_direction: Option<Direction>,
+    pub lives: u8,
+    pub score: u64
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GhostSave {
+    pub release_priority: usize,
+    pub position: TilePosition,
+    pub attack_state: AttackState,
+    pub release_state: ReleaseState,
+    pub path: Path,
+    pub revive_seconds: f32
+}
+
+/// A game in progress, stored as `key value` lines at `SAVE_PATH`, relative to the working directory. Only single player
+/// and co-op games can be saved; the other modes keep state that lives outside the maze.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveState {
+    /// `Board::hash` of the level the game was saved on.
+    pub board: u64,
+    pub level: u32,
+    pub bens: Vec<BenSave>,
+    pub ghosts: Vec<GhostSave>,
+    pub dots: HashSet<(usize, usize)>,
+    pub power_ups: HashSet<(usize, usize)>,
+    pub ghost_chain: u8,
+    pub scare_seconds: f32,
+    pub release_seconds: f32,
+    /// `GhostHouse` counters; its limits come from the level.
+    pub dot_counters: [u32; 4],
+    pub global_dot_counter: Option<u32>
+}
+
+impl SaveState {
+    /// Fails for saves of another version or of another level, including the same level since edited.
+    pub fn load(path: &str, board: &Board) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| format!("Could not read {}: {}", path, err))?;
+        let save_state = Self::from_save_str(&contents)?;
+        save_state.check(board).map_err(|err| format!("{}: {}", path, err))?;
+        Ok(save_state)
+    }
+
+    /// Whether the save fits `board`: it was saved on it and everyone stands somewhere on it.
+    pub fn check(&self, board: &Board) -> Result<(), String> {
+        if self.board != board.hash() {
+            return Err("Saved on another level".to_string());
+        }
+
+        let on_board = |position: &TilePosition| board.try_get(position.i, position.j).is_some()
+            && [position.offset_x, position.offset_y].iter().all(|offset| offset.abs() <= movement::TILE_UNITS / 2);
+        let mut positions = self.bens.iter().map(|ben| &ben.position)
+            .chain(self.ghosts.iter().flat_map(|ghost| std::iter::once(&ghost.position).chain(ghost.path.waypoints())));
+        match positions.find(|position| !on_board(position)) {
+            Some(position) => Err(format!("Malformed save: {} is off the level", position_string(position))),
+            None => Ok(())
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_save_string()).map_err(|err| format!("Could not write {}: {}", path, err))
+    }
+
+    pub fn from_save_str(contents: &str) -> Result<Self, String> {
+        let mut lines = contents.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with(';'));
+        match lines.next().and_then(|line| line.strip_prefix("version ")) {
+            Some(version) if version.trim() == SAVE_VERSION.to_string() => { },
+            Some(version) => return Err(format!("Unsupported save version {}", version.trim())),
+            None => return Err("Not a save file".to_string())
+        }
+
+        let mut save_state = Self {
+            board: 0,
+            level: 1,
+            bens: Vec::new(),
+            ghosts: Vec::new(),
+            dots: HashSet::new(),
+            power_ups: HashSet::new(),
+            ghost_chain: 0,
+            scare_seconds: 0.,
+            release_seconds: 0.,
+            dot_counters: [0; 4],
+            global_dot_counter: None
+        };
+        for line in lines {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let fields: Vec<&str> = value.split_whitespace().collect();
+            let malformed = || format!("Malformed save line: {}", line);
+            match key {
+                "board" => save_state.board = number(&fields, 0).ok_or_else(malformed)?,
+                "level" => save_state.level = number(&fields, 0).ok_or_else(malformed)?,
+                "ben" => save_state.bens.push(BenSave {
+                    player: number(&fields, 0).ok_or_else(malformed)?,
+                    position: fields.get(1).and_then(|field| parse_position(field)).ok_or_else(malformed)?,
+                    direction: fields.get(2).and_then(|field| parse_direction(field)).ok_or_else(malformed)?,
+                    next_direction: match fields.get(3) {
+                        Some(&"-") => None,
+                        Some(field) => Some(parse_direction(field).ok_or_else(malformed)?),
+                        None => return Err(malformed())
+                    },
+                    lives: number(&fields, 4).ok_or_else(malformed)?,
+                    score: number(&fields, 5).ok_or_else(malformed)?
+                }),
+                "ghost" => {
+                    let mut path = Path::new();
+                    for field in fields.iter().skip(5) {
+                        path.push_back(parse_position(field).ok_or_else(malformed)?);
+                    }
+                    save_state.ghosts.push(GhostSave {
+                        release_priority: number(&fields, 0).ok_or_else(malformed)?,
+                        position: fields.get(1).and_then(|field| parse_position(field)).ok_or_else(malformed)?,
+                        attack_state: match fields.get(2) {
+                            Some(&"attacking") => AttackState::Attacking,
+                            Some(&"scared") => AttackState::Scared,
+                            _ => return Err(malformed())
+                        },
+                        release_state: fields.get(3).and_then(|field| parse_release_state(field)).ok_or_else(malformed)?,
+                        path,
+                        revive_seconds: fields.get(4).and_then(|field| field.parse().ok()).ok_or_else(malformed)?
+                    });
+                },
+                "dots" | "power_ups" => {
+                    let tiles = fields.iter()
+                        .map(|field| parse_tile(field))
+                        .collect::<Option<HashSet<_>>>()
+                        .ok_or_else(malformed)?;
+                    if key == "dots" {
+                        save_state.dots = tiles;
+                    } else {
+                        save_state.power_ups = tiles;
+                    }
+                },
+                "ghost_chain" => save_state.ghost_chain = number(&fields, 0).ok_or_else(malformed)?,
+                "scare_timer" => save_state.scare_seconds = value.trim().parse().map_err(|_| malformed())?,
+                "release_timer" => save_state.release_seconds = value.trim().parse().map_err(|_| malformed())?,
+                "dot_counters" => {
+                    for (priority, counter) in save_state.dot_counters.iter_mut().enumerate() {
+                        *counter = number(&fields, priority).ok_or_else(malformed)?;
+                    }
+                },
+                "global_dot_counter" => save_state.global_dot_counter = match value.trim() {
+                    "-" => None,
+                    _ => Some(number(&fields, 0).ok_or_else(malformed)?)
+                },
+                _ => { }
+            }
+        }
+
+        if save_state.bens.is_empty() || save_state.ghosts.len() != 4 {
+            return Err("Save is missing Ben or a ghost".to_string());
+        }
+        Ok(save_state)
+    }
+
+    pub fn to_save_string(&self) -> String {
+        let mut lines = vec![
+            "; Benja-Man save".to_string(),
+            format!("version {}", SAVE_VERSION),
+            format!("board {}", self.board),
+            format!("level {}", self.level)
+        ];
+        for ben in self.bens.iter() {
+            lines.push(format!(
+                "ben {} {} {} {} {} {}",
+                ben.player,
+                position_string(&ben.position),
+                direction_key(ben.direction),
+                ben.next_direction.map_or("-", direction_key),
+                ben.lives,
+                ben.score
+            ));
+        }
+        for ghost in self.ghosts.iter() {
+            let mut line = format!(
+                "ghost {} {} {} {} {}",
+                ghost.release_priority,
+                position_string(&ghost.position),
+                match ghost.attack_state {
+                    AttackState::Attacking => "attacking",
+                    AttackState::Scared => "scared"
+                },
+                release_state_key(ghost.release_state),
+                ghost.revive_seconds
+            );
+            for waypoint in ghost.path.waypoints() {
+                line.push(' ');
+                line.push_str(&position_string(waypoint));
+            }
+            lines.push(line);
+        }
+
+        // Sorted, so saving the same game twice gives the same file
+        let tiles = |tiles: &HashSet<(usize, usize)>| {
+            let mut tiles: Vec<&(usize, usize)> = tiles.iter().collect();
+            tiles.sort();
+            tiles.iter().map(|(i, j)| format!(" {},{}", i, j)).collect::<String>()
+        };
+        lines.push(format!("dots{}", tiles(&self.dots)));
+        lines.push(format!("power_ups{}", tiles(&self.power_ups)));
+        lines.push(format!("ghost_chain {}", self.ghost_chain));
+        lines.push(format!("scare_timer {}", self.scare_seconds));
+        lines.push(format!("release_timer {}", self.release_seconds));
+        let counters: Vec<String> = self.dot_counters.iter().map(|counter| counter.to_string()).collect();
+        lines.push(format!("dot_counters {}", counters.join(" ")));
+        lines.push(format!(
+            "global_dot_counter {}",
+            self.global_dot_counter.map_or("-".to_string(), |counter| counter.to_string())
+        ));
+
+        let mut contents = lines.join("\n");
+        contents.push('\n');
+        contents
+    }
+
+    /// Tiles of the level whose dot or power-up is gone, as `utils::init_dots_and_power_ups` takes them.
+    pub fn eaten(&self, board: &Board) -> HashSet<(usize, usize)> {
+        let mut eaten = HashSet::new();
+        for i in 0..board.height() {
+            for j in 0..board.width() {
+                let remaining = match board.try_get(i, j) {
+                    Some(BoardTile::Dot) => self.dots.contains(&(i, j)),
+                    Some(BoardTile::PowerUp) => self.power_ups.contains(&(i, j)),
+                    _ => true
+                };
+                if !remaining {
+                    eaten.insert((i, j));
+                }
+            }
+        }
+        eaten
+    }
+}
+
+fn direction_key(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => "up",
+        Direction::Right => "right",
+        Direction::Down => "down",
+        Direction::Left => "left"
+    }
+}
+
+fn parse_direction(key: &str) -> Option<Direction> {
+    match key {
+        "up" => Some(Direction::Up),
+        "right" => Some(Direction::Right),
+        "down" => Some(Direction::Down),
+        "left" => Some(Direction::Left),
+        _ => None
+    }
+}
+
+fn release_state_key(release_state: ReleaseState) -> &'static str {
+    match release_state {
+        ReleaseState::Caged => "caged",
+        ReleaseState::Releasing => "releasing",
+        ReleaseState::Released => "released",
+        ReleaseState::Eyes => "eyes",
+        ReleaseState::Entering => "entering",
+        ReleaseState::Reviving => "reviving"
+    }
+}
+
+fn parse_release_state(key: &str) -> Option<ReleaseState> {
+    match key {
+        "caged" => Some(ReleaseState::Caged),
+        "releasing" => Some(ReleaseState::Releasing),
+        "released" => Some(ReleaseState::Released),
+        "eyes" => Some(ReleaseState::Eyes),
+        "entering" => Some(ReleaseState::Entering),
+        "reviving" => Some(ReleaseState::Reviving),
+        _ => None
+    }
+}
+
+/// `i,j,offset_x,offset_y`
+fn position_string(position: &TilePosition) -> String {
+    format!("{},{},{},{}", position.i, position.j, position.offset_x, position.offset_y)
+}
+
+fn parse_position(field: &str) -> Option<TilePosition> {
+    let values: Vec<&str> = field.split(',').collect();
+    match values.as_slice() {
+        [i, j, offset_x, offset_y] => Some(TilePosition {
+            i: i.parse().ok()?,
+            j: j.parse().ok()?,
+            offset_x: offset_x.parse().ok()?,
+            offset_y: offset_y.parse().ok()?
+        }),
+        _ => None
+    }
+}
+
+/// The field at `index` as a `T`, so a value out of `T`'s range is an error rather than being cut down to fit.
+fn number<T: FromStr>(fields: &[&str], index: usize) -> Option<T> {
+    fields.get(index)?.parse().ok()
+}
+
+fn parse_tile(field: &str) -> Option<(usize, usize)> {
+    let (i, j) = field.split_once(',')?;
+    Some((i.parse().ok()?, j.parse().ok()?))
+}
+
+/// Whether the game being played is one `SaveState` can hold. Practice isn't, as its cheats aren't saved.
+pub fn can_save(players: &Players, versus: &Versus, practice: &Practice, autopilots: usize) -> bool {
+    players.count() == 1 && !versus.active && !practice.active && autopilots == 0
+}
+
+/// A save waiting to be applied by `apply_save_system`, once every Ben it needs has been spawned.
+pub struct PendingLoad(pub SaveState);
+
+/// The game as it is now, for a save.
+pub fn capture(
+    ben_query: &Query<(&PlayerId, &TilePosition, &BenDirection, &BenNextDirection, &BenLives, &Score), With<Ben>>,
+    ghost_query: &Query<(&GhostReleasePriority, &TilePosition, &AttackState, &ReleaseState, &GhostPath, &GhostReviveTimer), With<Ghost>>,
+    pickup_query: &Query<(&TilePosition, Option<&Dot>), Or<(With<Dot>, With<PowerUp>)>>,
+    ghost_chain: &GhostChain,
+    ghost_scare_timer: &GhostScareTimer,
+    ghost_release_timer: &GhostReleaseTimer,
+    ghost_house: &GhostHouse,
+    level: &Level,
+    board: &Board
+) -> SaveState {
+    let pickups = |dots: bool| pickup_query.iter()
+        .filter(|(_, dot)| dot.is_some() == dots)
+        .map(|(position, _)| position.tile())
+        .collect();
+    SaveState {
+        board: board.hash(),
+        dots: pickups(true),
+        power_ups: pickups(false),
+        ..capture_actors(ben_query, ghost_query, ghost_chain, ghost_scare_timer, ghost_release_timer, ghost_house, level)
+    }
+}
+
+/// Everything `capture` does but the pickups and the board's hash, which are left empty. `rewind::Snapshot` keeps
+/// those its own way.
+pub fn capture_actors(
+    ben_query: &Query<(&PlayerId, &TilePosition, &BenDirection, &BenNextDirection, &BenLives, &Score), With<Ben>>,
+    ghost_query: &Query<(&GhostReleasePriority, &TilePosition, &AttackState, &ReleaseState, &GhostPath, &GhostReviveTimer), With<Ghost>>,
+    ghost_chain: &GhostChain,
+    ghost_scare_timer: &GhostScareTimer,
+    ghost_release_timer: &GhostReleaseTimer,
+    ghost_house: &GhostHouse,
+    level: &Level
+) -> SaveState {
+    SaveState {
+        board: 0,
+        level: level.0,
+        bens: ben_query.iter()
+            .map(|(player_id, position, ben_direction, ben_next_direction, ben_lives, score)| BenSave {
+                player: player_id.0,
+                position: *position,
+                direction: ben_direction.0,
+                next_direction: ben_next_direction.0,
+                lives: ben_lives.0,
+                score: score.0
+            })
+            .collect(),
+        ghosts: ghost_query.iter()
+            .map(|(release_priority, position, attack_state, release_state, ghost_path, revive_timer)| GhostSave {
+                release_priority: release_priority.0,
+                position: *position,
+                attack_state: *attack_state,
+                release_state: *release_state,
+                path: ghost_path.0.clone(),
+                revive_seconds: revive_timer.0.elapsed_secs()
+            })
+            .collect(),
+        dots: HashSet::new(),
+        power_ups: HashSet::new(),
+        ghost_chain: ghost_chain.0,
+        scare_seconds: ghost_scare_timer.0.elapsed_secs(),
+        release_seconds: ghost_release_timer.0.elapsed_secs(),
+        dot_counters: ghost_house.dot_counters,
+        global_dot_counter: ghost_house.global_dot_counter
+    }
+}
+
+/// F5 saves the game over `SAVE_PATH`.
+pub fn quick_save_system(
+    mut commands: Commands,
+    ben_query: Query<(&PlayerId, &TilePosition, &BenDirection, &BenNextDirection, &BenLives, &Score), With<Ben>>,
+    ghost_query: Query<(&GhostReleasePriority, &TilePosition, &AttackState, &ReleaseState, &GhostPath, &GhostReviveTimer), With<Ghost>>,
+    pickup_query: Query<(&TilePosition, Option<&Dot>), Or<(With<Dot>, With<PowerUp>)>>,
+    autopilot_query: Query<&Autopilot>,
+    keys: Res<Input<KeyCode>>,
+    (ghost_chain, ghost_scare_timer, ghost_release_timer, ghost_house): (Res<GhostChain>, Res<GhostScareTimer>, Res<GhostReleaseTimer>, Res<GhostHouse>),
+    (level, players, versus, practice): (Res<Level>, Res<Players>, Res<Versus>, Res<Practice>),
+    (board, font_material, maze_theme): (Res<Board>, Res<misc::FontMaterial>, Res<MazeTheme>)
+) {
+    if !keys.just_pressed(KeyCode::F5) {
+        return;
+    }
+
+    let message_position = utils::get_ghost_spawn_coordinates(&board);
+    if !can_save(&players, &versus, &practice, autopilot_query.iter().count()) {
+        popup::spawn_popup(&mut commands, "CAN'T SAVE", message_position, &font_material, &maze_theme);
+        return;
+    }
+
+    let save_state = capture(&ben_query, &ghost_query, &pickup_query, &ghost_chain, &ghost_scare_timer, &ghost_release_timer, &ghost_house, &level, &board);
+    match save_state.save(SAVE_PATH) {
+        Ok(()) => popup::spawn_popup(&mut commands, "SAVED", message_position, &font_material, &maze_theme),
+        Err(err) => {
+            eprintln!("{}", err);
+            popup::spawn_popup(&mut commands, "SAVE FAILED", message_position, &font_material, &maze_theme);
+        }
+    }
+}
+
+/// F9 goes back to the game saved at `SAVE_PATH`.
+pub fn quick_load_system(
+    mut commands: Commands,
+    mut game_state: ResMut<State<GameState>>,
+    mut sequence: ResMut<Sequence>,
+    autopilot_query: Query<&Autopilot>,
+    ben_query: Query<&PlayerId, With<Ben>>,
+    keys: Res<Input<KeyCode>>,
+    (players, versus, practice): (Res<Players>, Res<Versus>, Res<Practice>),
+    (board, font_material, maze_theme): (Res<Board>, Res<misc::FontMaterial>, Res<MazeTheme>)
+) {
+    if !keys.just_pressed(KeyCode::F9) {
+        return;
+    }
+
+    // Only into a game of the same kind; the main menu's "Continue" starts one of the right kind
+    let message_position = utils::get_ghost_spawn_coordinates(&board);
+    let save_state = match SaveState::load(SAVE_PATH, &board) {
+        Ok(save_state) => save_state,
+        Err(err) => {
+            eprintln!("{}", err);
+            popup::spawn_popup(&mut commands, "NO SAVE", message_position, &font_material, &maze_theme);
+            return;
+        }
+    };
+    if !can_save(&players, &versus, &practice, autopilot_query.iter().count()) || save_state.bens.len() != ben_query.iter().count() {
+        popup::spawn_popup(&mut commands, "CAN'T LOAD", message_position, &font_material, &maze_theme);
+        return;
+    }
+
+    // Runs after the systems that end play, so a death or a cleared maze on the same frame wins and the key is ignored
+    if game_state.set(GameState::Sequence).is_err() {
+        return;
+    }
+    commands.insert_resource(PendingLoad(save_state));
+    *sequence = Sequence::resume();
+}
+
+/// Puts a `PendingLoad` save into the game.
+pub fn apply_save_system(
+    mut commands: Commands,
+    mut ben_query: Query<(Entity, &PlayerId, &mut TilePosition, &mut PreviousTilePosition, &mut BenDirection, &mut BenNextDirection, &mut BenLives, &mut Score, &mut Animation, &mut Visible), With<Ben>>,
+    mut ghost_query: Query<(&GhostReleasePriority, &mut TilePosition, &mut PreviousTilePosition, &mut AttackState, &mut ReleaseState, &mut GhostPath, &mut GhostReviveTimer, &mut Visible), (With<Ghost>, Without<Ben>)>,
+    pickup_query: Query<(Entity, &TilePosition, Option<&Dot>), (Or<(With<Dot>, With<PowerUp>)>, Without<Ben>, Without<Ghost>)>,
+    (mut ghost_chain, mut ghost_scare_timer, mut ghost_release_timer, mut ghost_house, mut level): (ResMut<GhostChain>, ResMut<GhostScareTimer>, ResMut<GhostReleaseTimer>, ResMut<GhostHouse>, ResMut<Level>),
+    pending_load: Option<Res<PendingLoad>>,
+    board: Res<Board>,
+    dot_material: Res<DotMaterial>,
+    power_up_sprite_sheet: Res<PowerUpSpriteSheet>
+) {
+    let save_state = match pending_load.as_ref() {
+        Some(pending_load) => &pending_load.0,
+        None => return
+    };
+
+    // A co-op Ben spawned for the save only shows up a frame later
+    if ben_query.iter_mut().count() != save_state.bens.len() {
+        return;
+    }
+
+    for (ben_entity, player_id, mut position, mut previous_position, mut ben_direction, mut ben_next_direction, mut ben_lives, mut score, mut animation, mut visible) in ben_query.iter_mut() {
+        let ben = match save_state.bens.iter().find(|ben| ben.player == player_id.0) {
+            Some(ben) => ben,
+            None => continue
+        };
+        *position = ben.position;
+        previous_position.0 = ben.position;
+        ben_direction.0 = ben.direction;
+        ben_next_direction.0 = ben.next_direction;
+        ben_lives.0 = ben.lives;
+        score.0 = ben.score;
+        animation.play(animation::walk_clip(ben.direction));
+        visible.is_visible = ben.lives > 0;
+        commands.entity(ben_entity).remove::<Caught>();
+    }
+
+    for (release_priority, mut position, mut previous_position, mut attack_state, mut release_state, mut ghost_path, mut revive_timer, mut visible) in ghost_query.iter_mut() {
+        let ghost = match save_state.ghosts.iter().find(|ghost| ghost.release_priority == release_priority.0) {
+            Some(ghost) => ghost,
+            None => continue
+        };
+        *position = ghost.position;
+        previous_position.0 = ghost.position;
+        *attack_state = ghost.attack_state;
+        *release_state = ghost.release_state;
+        ghost_path.0 = ghost.path.clone();
+        revive_timer.0.set_elapsed(Duration::from_secs_f32(ghost.revive_seconds));
+        visible.is_visible = true;
+    }
+
+    ghost_chain.0 = save_state.ghost_chain;
+    ghost_scare_timer.0.set_elapsed(Duration::from_secs_f32(save_state.scare_seconds));
+    ghost_release_timer.0.set_elapsed(Duration::from_secs_f32(save_state.release_seconds));
+    ghost_house.dot_counters = save_state.dot_counters;
+    ghost_house.global_dot_counter = save_state.global_dot_counter;
+    level.0 = save_state.level;
+
+    // Rewinding applies a snapshot every frame, and the dots rarely change between them
+    let pickups_match = pickup_query.iter().count() == save_state.dots.len() + save_state.power_ups.len()
+        && pickup_query.iter().all(|(_, position, dot)| match dot {
+            Some(_) => save_state.dots.contains(&position.tile()),
+            None => save_state.power_ups.contains(&position.tile())
+        });
+    if !pickups_match {
+        for (entity, _, _) in pickup_query.iter() {
+            commands.entity(entity).despawn();
+        }
+        utils::init_dots_and_power_ups(&mut commands, &board, dot_material.handle.clone(), power_up_sprite_sheet.atlas.clone(), &save_state.eaten(&board));
+    }
+    commands.remove_resource::<PendingLoad>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants;
+
+    fn save_state() -> SaveState {
+        let mut path = Path::new();
+        path.push_back(TilePosition::centred(5, 6));
+        path.push_back(TilePosition::centred(5, 7));
+        SaveState {
+            board: 42,
+            level: 3,
+            bens: vec![BenSave {
+                player: 0,
+                position: TilePosition { i: 23, j: 13, offset_x: -160, offset_y: 0 },
+                direction: Direction::Left,
+                next_direction: Some(Direction::Up),
+                lives: 2,
+                score: 1230
+            }],
+            ghosts: (0..4)
+                .map(|release_priority| GhostSave {
+                    release_priority,
+                    position: TilePosition::centred(14, 11 + release_priority),
+                    attack_state: AttackState::Scared,
+                    release_state: ReleaseState::Released,
+                    path: if release_priority == 0 { path.clone() } else { Path::new() },
+                    revive_seconds: 0.25
+                })
+                .collect(),
+            dots: [(1, 1), (1, 2)].into_iter().collect(),
+            power_ups: [(3, 1)].into_iter().collect(),
+            ghost_chain: 2,
+            scare_seconds: 4.5,
+            release_seconds: 1.75,
+            dot_counters: [0, 0, 12, 0],
+            global_dot_counter: Some(7)
+        }
+    }
+
+    #[test]
+    fn save_survives_writing_and_reading() {
+        let save_state = save_state();
+        assert_eq!(SaveState::from_save_str(&save_state.to_save_string()), Ok(save_state));
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let contents = save_state().to_save_string().replace(&format!("version {}", SAVE_VERSION), "version 999");
+        assert!(SaveState::from_save_str(&contents).is_err());
+        assert!(SaveState::from_save_str("level 1\n").is_err());
+    }
+
+    #[test]
+    fn numbers_out_of_range_are_rejected() {
+        let contents = save_state().to_save_string().replace("-160,0 left up 2 1230", "-160,0 left up 258 1230");
+        assert!(SaveState::from_save_str(&contents).is_err());
+        let contents = save_state().to_save_string().replace("ghost_chain 2", "ghost_chain -1");
+        assert!(SaveState::from_save_str(&contents).is_err());
+    }
+
+    #[test]
+    fn positions_off_the_level_are_rejected() {
+        let board = Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET);
+        let fits = |change: fn(&mut SaveState)| {
+            let mut save_state = save_state();
+            save_state.board = board.hash();
+            change(&mut save_state);
+            save_state.check(&board)
+        };
+
+        assert_eq!(fits(|_| { }), Ok(()));
+        assert!(fits(|save_state| save_state.board ^= 1).is_err());
+        assert!(fits(|save_state| save_state.bens[0].position.i = 1000).is_err());
+        assert!(fits(|save_state| save_state.ghosts[1].position.offset_y = movement::TILE_UNITS).is_err());
+        assert!(fits(|save_state| save_state.ghosts[0].path.push_back(TilePosition::centred(0, 1000))).is_err());
+    }
+}
@@ -1,24 +1,8 @@
-use bevy::prelude::*;
+use crate::fruit::Fruit;
 
 pub struct Score(pub u64);
 
-#[derive(Bundle)]
-pub struct ScoreBundle {
-    pub score: Score,
-
-    #[bundle]
-    pub text_bundle: Text2dBundle
-}
-
-impl Default for ScoreBundle {
-    fn default() -> Self {
-        Self {
-            score: Score(0),
-            text_bundle: Text2dBundle::default()
-        }
-    }
-}
-
+#[derive(Clone, Debug)]
 pub struct PointValues {
     pub dot: u64,
     pub power_up: u64,
@@ -56,3 +40,28 @@ impl Default for PointValues {
         } 
     }
 }
+
+impl PointValues {
+    /// What eating a ghost is worth, given how many were eaten before it on the same power-up.
+    pub fn ghost(&self, chain: u8) -> u64 {
+        match chain {
+            0 => self.first_ghost,
+            1 => self.second_ghost,
+            2 => self.third_ghost,
+            _ => self.fourth_ghost
+        }
+    }
+
+    pub fn fruit(&self, fruit: Fruit) -> u64 {
+        match fruit {
+            Fruit::Cherry => self.cherry,
+            Fruit::Strawberry => self.strawberry,
+            Fruit::Orange => self.orange,
+            Fruit::Apple => self.apple,
+            Fruit::Melon => self.melon,
+            Fruit::Flagship => self.flagship,
+            Fruit::Bell => self.bell,
+            Fruit::Key => self.key
+        }
+    }
+}
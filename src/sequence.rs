@@ -0,0 +1,153 @@
+use std::collections::VecDeque;
+use bevy::prelude::*;
+use crate::enums::GameState;
+use crate::events::CueEvent;
+
+/// Something that happens at the start of a sequence step. Gameplay systems don't run while a sequence plays, so
+/// everything stays frozen unless a cue moves it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Cue {
+    Freeze,
+    BenDeath,
+    HideGhosts,
+    ResetPositions,
+    Ready,
+    FlashMaze,
+    SwitchPlayers
+}
+
+pub struct SequenceStep {
+    pub cue: Cue,
+    pub seconds: f32
+}
+
+impl SequenceStep {
+    pub fn new(cue: Cue, seconds: f32) -> Self {
+        Self {
+            cue,
+            seconds
+        }
+    }
+}
+
+/// A scripted run of timed steps, played in `GameState::Sequence`. Once the last step has run its course the game
+/// moves on to `then`.
+pub struct Sequence {
+    steps: VecDeque<SequenceStep>,
+    timer: Timer,
+    then: GameState
+}
+
+impl Default for Sequence {
+    fn default() -> Self {
+        Self::new(Vec::new(), GameState::Default)
+    }
+}
+
+impl Sequence {
+    pub fn new(steps: Vec<SequenceStep>, then: GameState) -> Self {
+        Self {
+            steps: steps.into(),
+            timer: Timer::from_seconds(0., false),
+            then
+        }
+    }
+
+    /// "READY!" before control is handed to the player.
+    pub fn level_start() -> Self {
+        Self::new(vec![
+            SequenceStep::new(Cue::ResetPositions, 0.),
+            SequenceStep::new(Cue::Ready, 2.)
+        ], GameState::Default)
+    }
+
+    /// "READY!" before a loaded game carries on from where it was saved.
+    pub fn resume() -> Self {
+        Self::new(vec![
+            SequenceStep::new(Cue::Ready, 2.)
+        ], GameState::Default)
+    }
+
+    /// Ben dies, then either play carries on from the start positions, possibly with the other player's game, or the
+    /// game is over.
+    pub fn death(game_over: bool, switch_players: bool) -> Self {
+        let mut steps = vec![
+            SequenceStep::new(Cue::Freeze, 1.),
+            SequenceStep::new(Cue::BenDeath, 1.5),
+            SequenceStep::new(Cue::HideGhosts, 0.5)
+        ];
+        if game_over {
+            return Self::new(steps, GameState::End);
+        }
+
+        if switch_players {
+            steps.push(SequenceStep::new(Cue::SwitchPlayers, 0.));
+        }
+        steps.push(SequenceStep::new(Cue::ResetPositions, 0.));
+        steps.push(SequenceStep::new(Cue::Ready, 2.));
+        Self::new(steps, GameState::Default)
+    }
+
+    /// The arcade's brief pause while the points for an eaten ghost are shown.
+    pub fn ghost_eaten() -> Self {
+        Self::new(vec![
+            SequenceStep::new(Cue::Freeze, 1.)
+        ], GameState::Default)
+    }
+
+    /// The maze flashes, then play goes on to the next level, unless clearing it was the end of a versus round.
+    pub fn level_complete(round_over: bool) -> Self {
+        Self::new(vec![
+            SequenceStep::new(Cue::Freeze, 1.),
+            SequenceStep::new(Cue::FlashMaze, 2.)
+        ], if round_over { GameState::End } else { GameState::NextLevel })
+    }
+}
+
+/// Whether `cue` was among the cues sent since the last call. Reads every pending cue, so none linger into the next frame.
+pub fn was_cued(cue_event: &mut EventReader<CueEvent>, cue: Cue) -> bool {
+    cue_event.iter().fold(false, |cued, event| cued || event.0 == cue)
+}
+
+pub fn sequence_system(
+    mut sequence: ResMut<Sequence>,
+    mut game_state: ResMut<State<GameState>>,
+    mut cue_event: EventWriter<CueEvent>,
+    time: Res<Time>
+) {
+    sequence.timer.tick(time.delta());
+
+    // Steps lasting no time at all run back to back within the same frame
+    while sequence.timer.finished() {
+        match sequence.steps.pop_front() {
+            Some(step) => {
+                cue_event.send(CueEvent(step.cue));
+                sequence.timer = Timer::from_seconds(step.seconds, false);
+                sequence.timer.tick(std::time::Duration::ZERO);
+            },
+            None => {
+                game_state.set(sequence.then).unwrap();
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cues(sequence: &Sequence) -> Vec<Cue> {
+        sequence.steps.iter().map(|step| step.cue).collect()
+    }
+
+    #[test]
+    fn ben_dies_before_the_ghosts_are_hidden() {
+        assert_eq!(cues(&Sequence::death(false, false)), vec![Cue::Freeze, Cue::BenDeath, Cue::HideGhosts, Cue::ResetPositions, Cue::Ready]);
+        assert_eq!(cues(&Sequence::death(false, true)), vec![Cue::Freeze, Cue::BenDeath, Cue::HideGhosts, Cue::SwitchPlayers, Cue::ResetPositions, Cue::Ready]);
+
+        let game_over = Sequence::death(true, false);
+        assert_eq!(cues(&game_over), vec![Cue::Freeze, Cue::BenDeath, Cue::HideGhosts]);
+        assert_eq!(game_over.then, GameState::End);
+    }
+}
@@ -0,0 +1,193 @@
+use std::fs;
+use bevy::prelude::*;
+use crate::board::Board;
+use crate::enums::GameState;
+use crate::misc;
+use crate::view::ScaleMode;
+
+pub const SETTINGS_PATH: &str = "settings.txt";
+
+const INPUT_BUFFER_STEP_SECONDS: f32 = 0.05;
+const INPUT_BUFFER_MAX_SECONDS: f32 = 1.;
+
+/// Player preferences, stored as `key value` lines in `settings.txt` next to the executable.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// Lets Ben turn a little before or after a tile centre, cutting the corner.
+    pub cornering: bool,
+    /// Remembers a pressed direction until it can be taken, for up to `input_buffer_seconds`.
+    pub input_buffer: bool,
+    pub input_buffer_seconds: f32,
+    /// Lets Ben turn around between tile centres instead of only at them.
+    pub instant_reversal: bool,
+    pub scale_mode: ScaleMode,
+    pub fullscreen: bool
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            cornering: true,
+            input_buffer: true,
+            input_buffer_seconds: 0.3,
+            instant_reversal: true,
+            scale_mode: ScaleMode::Smooth,
+            fullscreen: false
+        }
+    }
+}
+
+impl Settings {
+    /// Missing files and unknown or malformed lines fall back to the defaults, so old settings files keep working.
+    pub fn load(path: &str) -> Self {
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::from_settings_str(&contents),
+            Err(_) => Self::default()
+        }
+    }
+
+    pub fn from_settings_str(contents: &str) -> Self {
+        let mut settings = Self::default();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let value = value.trim();
+            let flag = match value {
+                "on" => Some(true),
+                "off" => Some(false),
+                _ => None
+            };
+            match key {
+                "cornering" => settings.cornering = flag.unwrap_or(settings.cornering),
+                "input_buffer" => settings.input_buffer = flag.unwrap_or(settings.input_buffer),
+                "input_buffer_seconds" => settings.input_buffer_seconds = value.parse().unwrap_or(settings.input_buffer_seconds),
+                "instant_reversal" => settings.instant_reversal = flag.unwrap_or(settings.instant_reversal),
+                "scale_mode" => settings.scale_mode = match value {
+                    "integer" => ScaleMode::Integer,
+                    "smooth" => ScaleMode::Smooth,
+                    _ => settings.scale_mode
+                },
+                "fullscreen" => settings.fullscreen = flag.unwrap_or(settings.fullscreen),
+                _ => { }
+            }
+        }
+        settings
+    }
+
+    pub fn to_settings_string(&self) -> String {
+        let flag = |value: bool| if value { "on" } else { "off" };
+        let scale_mode = match self.scale_mode {
+            ScaleMode::Integer => "integer",
+            ScaleMode::Smooth => "smooth"
+        };
+        format!(
+            "; Benja-Man settings\ncornering {}\ninput_buffer {}\ninput_buffer_seconds {}\ninstant_reversal {}\nscale_mode {}\nfullscreen {}\n",
+            flag(self.cornering),
+            flag(self.input_buffer),
+            self.input_buffer_seconds,
+            flag(self.instant_reversal),
+            scale_mode,
+            flag(self.fullscreen)
+        )
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_settings_string()).map_err(|err| format!("Could not write {}: {}", path, err))
+    }
+}
+
+pub struct SettingsText;
+
+pub fn enter_settings_system(
+    mut commands: Commands,
+    font_material: Res<misc::FontMaterial>,
+    board: Res<Board>
+) {
+    let text_style = TextStyle {
+        font: font_material.handle.clone(),
+        font_size: 28.,
+        color: Color::WHITE
+    };
+    let text_alignment = TextAlignment {
+        vertical: VerticalAlign::Center,
+        horizontal: HorizontalAlign::Center
+    };
+    commands.spawn_bundle(Text2dBundle {
+        text: Text::with_section("", text_style, text_alignment),
+        transform: Transform {
+            translation: Vec3::new(board.width() as f32 * board.cell_size() / 2., board.height() as f32 * board.cell_size() / 2. + 256., 100.),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .insert(SettingsText);
+}
+
+pub fn exit_settings_system(
+    mut commands: Commands,
+    query: Query<Entity, With<SettingsText>>
+) {
+    for settings_text_entity in query.iter() {
+        commands.entity(settings_text_entity).despawn();
+    }
+}
+
+pub fn settings_menu_system(
+    mut settings: ResMut<Settings>,
+    mut game_state: ResMut<State<GameState>>,
+    mut query: Query<&mut Text, With<SettingsText>>,
+    keys: Res<Input<KeyCode>>
+) {
+    if keys.just_pressed(KeyCode::Key1) {
+        settings.cornering = !settings.cornering;
+    }
+    if keys.just_pressed(KeyCode::Key2) {
+        settings.input_buffer = !settings.input_buffer;
+    }
+    if keys.just_pressed(KeyCode::Key3) {
+        settings.instant_reversal = !settings.instant_reversal;
+    }
+    if keys.just_pressed(KeyCode::Key4) {
+        settings.scale_mode = match settings.scale_mode {
+            ScaleMode::Integer => ScaleMode::Smooth,
+            ScaleMode::Smooth => ScaleMode::Integer
+        };
+    }
+    if keys.just_pressed(KeyCode::Key5) {
+        settings.fullscreen = !settings.fullscreen;
+    }
+    if keys.just_pressed(KeyCode::Left) {
+        settings.input_buffer_seconds = (settings.input_buffer_seconds - INPUT_BUFFER_STEP_SECONDS).max(INPUT_BUFFER_STEP_SECONDS);
+    }
+    if keys.just_pressed(KeyCode::Right) {
+        settings.input_buffer_seconds = (settings.input_buffer_seconds + INPUT_BUFFER_STEP_SECONDS).min(INPUT_BUFFER_MAX_SECONDS);
+    }
+
+    if keys.just_pressed(KeyCode::Escape) || keys.just_pressed(KeyCode::Space) {
+        if let Err(err) = settings.save(SETTINGS_PATH) {
+            eprintln!("{}", err);
+        }
+        game_state.set(GameState::Wait).unwrap();
+        return;
+    }
+
+    // The text is spawned through commands when the menu opens, so it may not exist for the first frame
+    if let Ok(mut text) = query.single_mut() {
+        let flag = |value: bool| if value { "ON" } else { "OFF" };
+        text.sections[0].value = format!(
+            "SETTINGS\n\n1  Cornering: {}\n2  Input buffer: {} ({:.2}s, Left/Right)\n3  Instant reversal: {}\n4  Scaling: {}\n5  Fullscreen: {} (F11)\n\nPress space to go back",
+            flag(settings.cornering),
+            flag(settings.input_buffer),
+            settings.input_buffer_seconds,
+            flag(settings.instant_reversal),
+            match settings.scale_mode {
+                ScaleMode::Integer => "INTEGER",
+                ScaleMode::Smooth => "SMOOTH"
+            },
+            flag(settings.fullscreen)
+        );
+    }
+}
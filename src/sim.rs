@@ -0,0 +1,475 @@
+use std::hash::{Hash, Hasher};
+use crate::ben;
+use crate::board::{Board, BoardTile};
+use crate::constants;
+use crate::enums::Direction;
+use crate::ghost::{self, AttackState, ReleaseState, GhostHouse, GhostTunnelSpeed, GhostReleaseTimer, ElroyThresholds};
+use crate::movement::{self, TilePosition, CollisionRadius};
+use crate::path::Path;
+use crate::score::PointValues;
+use crate::utils;
+
+/// The sim advances in fixed ticks, one per frame of the windowed game.
+pub const TICKS_PER_SECOND: f32 = constants::MAX_FRAMERATE as f32;
+
+pub fn ticks(seconds: f32) -> u32 {
+    (seconds * TICKS_PER_SECOND).round() as u32
+}
+
+/// Everything about a game that doesn't change while it is played, read from the level like the windowed game does.
+#[derive(Clone, Debug)]
+pub struct SimConfig {
+    pub point_values: PointValues,
+    pub ben_speed: f32,
+    pub ghost_speed: f32,
+    pub ghost_tunnel_speed: f32,
+    pub elroy_thresholds: ElroyThresholds,
+    pub collision_radius: i32,
+    pub scare_ticks: u32,
+    pub release_timeout_ticks: u32,
+    pub revive_ticks: u32,
+    pub cornering: bool,
+    pub instant_reversal: bool
+}
+
+impl SimConfig {
+    pub fn from_board(board: &Board) -> Self {
+        Self {
+            point_values: PointValues::default(),
+            ben_speed: constants::BEN_SPEED_DEFAULT,
+            ghost_speed: constants::GHOST_SPEED_DEFAULT,
+            ghost_tunnel_speed: GhostTunnelSpeed::from_board(board).0,
+            elroy_thresholds: ElroyThresholds::from_board(board),
+            collision_radius: CollisionRadius::from_board(board).0,
+            scare_ticks: ticks(constants::GHOST_SCARE_SECONDS),
+            release_timeout_ticks: ticks(GhostReleaseTimer::from_board(board).0.duration().as_secs_f32()),
+            revive_ticks: ticks(constants::GHOST_REVIVE_SECONDS),
+            cornering: true,
+            instant_reversal: true
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SimBen {
+    pub position: TilePosition,
+    pub previous_position: TilePosition,
+    pub direction: Direction,
+    pub next_direction: Option<Direction>,
+    pub lives: u8,
+    pub score: u64
+}
+
+/// Ghosts are told apart by release priority: Caleb 0, Claflin 1, Harris 2 and Samson 3.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SimGhost {
+    pub release_priority: usize,
+    pub position: TilePosition,
+    pub previous_position: TilePosition,
+    pub path: Path,
+    pub attack_state: AttackState,
+    pub release_state: ReleaseState,
+    pub revive_ticks: u32
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimEvent {
+    DotEaten { player: usize },
+    PowerUpEaten { player: usize },
+    GhostEaten { player: usize, ghost: usize, chain: u8, points: u64 },
+    BenCaught { player: usize },
+    LevelCleared,
+    GameOver
+}
+
+/// A headless, deterministic game of Benja-Man: the same movement, collision and ghost house rules as the ECS systems,
+/// shared with them through `ben` and `ghost`, but with every timer counted in ticks, so that the same inputs always
+/// lead to the same state. There are no death or level sequences; actors are put straight back at their spawn points.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Sim {
+    pub tick: u64,
+    pub level: u32,
+    pub bens: Vec<SimBen>,
+    pub ghosts: Vec<SimGhost>,
+    /// Dots and power-ups still on the board; every other tile is `Empty`.
+    pub pickups: Vec<Vec<BoardTile>>,
+    pub ghost_house: GhostHouse,
+    pub ghost_chain: u8,
+    /// Ticks left until scared ghosts turn back.
+    pub scare_ticks: u32,
+    /// Ticks since a dot was last eaten, for releasing ghosts when Ben stops eating.
+    pub release_ticks: u32
+}
+
+impl Sim {
+    pub fn new(board: &Board, players: usize) -> Self {
+        let mut sim = Self {
+            tick: 0,
+            level: 1,
+            bens: (0..players)
+                .map(|_| SimBen {
+                    position: TilePosition::default(),
+                    previous_position: TilePosition::default(),
+                    direction: constants::BEN_DIRECTION_DEFAULT,
+                    next_direction: None,
+                    lives: constants::BEN_LIVES,
+                    score: 0
+                })
+                .collect(),
+            ghosts: (0..4)
+                .map(|release_priority| SimGhost {
+                    release_priority,
+                    position: TilePosition::default(),
+                    previous_position: TilePosition::default(),
+                    path: Path::new(),
+                    attack_state: AttackState::Attacking,
+                    release_state: ReleaseState::Caged,
+                    revive_ticks: 0
+                })
+                .collect(),
+            pickups: Vec::new(),
+            ghost_house: GhostHouse::from_board(board),
+            ghost_chain: 0,
+            scare_ticks: 0,
+            release_ticks: 0
+        };
+        sim.refill(board);
+        sim.reset_positions(board);
+        sim
+    }
+
+    fn refill(&mut self, board: &Board) {
+        self.pickups = (0..board.height())
+            .map(|i| (0..board.width())
+                .map(|j| match board.try_get(i, j) {
+                    Some(BoardTile::Dot) => BoardTile::Dot,
+                    Some(BoardTile::PowerUp) => BoardTile::PowerUp,
+                    _ => BoardTile::Empty
+                })
+                .collect())
+            .collect();
+    }
+
+    fn reset_positions(&mut self, board: &Board) {
+        let (x, y) = utils::get_ben_spawn_coordinates(board);
+        for (player, ben) in self.bens.iter_mut().enumerate() {
+            ben.position = TilePosition::from_coordinates(board, x, y);
+            ben.previous_position = ben.position;
+            ben.direction = ben::initial_direction(player);
+            ben.next_direction = None;
+        }
+
+        for ghost in self.ghosts.iter_mut() {
+            let (x, y) = match ghost.release_priority {
+                0 => utils::get_caleb_spawn_coordinates(board),
+                1 => utils::get_claflin_spawn_coordinates(board),
+                2 => utils::get_harris_spawn_coordinates(board),
+                _ => utils::get_samson_spawn_coordinates(board)
+            };
+            ghost.position = TilePosition::from_coordinates(board, x, y);
+            ghost.previous_position = ghost.position;
+            ghost.path.clear();
+            ghost.attack_state = AttackState::Attacking;
+            ghost.release_state = if ghost.release_priority == 0 { ReleaseState::Released } else { ReleaseState::Caged };
+        }
+
+        self.scare_ticks = 0;
+        self.release_ticks = 0;
+    }
+
+    pub fn remaining(&self, tile: BoardTile) -> usize {
+        self.pickups.iter().flatten().filter(|pickup| **pickup == tile).count()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.bens.iter().all(|ben| ben.lives == 0)
+    }
+
+    /// Hash of the whole state, compared between peers to catch desyncs.
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = utils::StableHasher::default();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Advances one tick. `inputs` holds the direction each player is pushing, if any; a direction stays queued until
+    /// it can be taken.
+    pub fn step(&mut self, board: &Board, config: &SimConfig, inputs: &[Option<Direction>]) -> Vec<SimEvent> {
+        let mut events = Vec::new();
+        if self.is_over() {
+            return events;
+        }
+
+        for ben in self.bens.iter_mut() {
+            ben.previous_position = ben.position;
+        }
+        for ghost in self.ghosts.iter_mut() {
+            ghost.previous_position = ghost.position;
+        }
+
+        self.move_bens(board, config, inputs);
+        let ate = self.eat(config, &mut events);
+        self.release_ghosts(board, config, ate);
+        self.move_ghosts(board, config);
+        self.collide(board, config, &mut events);
+        self.scare_timer();
+
+        // Power-ups left over don't hold the level up, just as in `win_system`
+        if self.remaining(BoardTile::Dot) == 0 {
+            self.level += 1;
+            self.refill(board);
+            self.reset_positions(board);
+            self.ghost_house.reset();
+            events.push(SimEvent::LevelCleared);
+        }
+        if self.is_over() {
+            events.push(SimEvent::GameOver);
+        }
+
+        self.tick += 1;
+        events
+    }
+
+    fn move_bens(&mut self, board: &Board, config: &SimConfig, inputs: &[Option<Direction>]) {
+        for (player, ben) in self.bens.iter_mut().enumerate() {
+            if ben.lives == 0 {
+                continue;
+            }
+
+            if let Some(direction) = inputs.get(player).copied().flatten() {
+                ben.next_direction = Some(direction);
+            }
+            ben.direction = ben::advance(
+                board,
+                &mut ben.position,
+                ben.direction,
+                &mut ben.next_direction,
+                movement::speed_units(config.ben_speed),
+                config.cornering,
+                config.instant_reversal
+            );
+        }
+    }
+
+    /// Dots and power-ups under a Ben are eaten. Returns whether a dot was.
+    fn eat(&mut self, config: &SimConfig, events: &mut Vec<SimEvent>) -> bool {
+        let preferred = self.preferred_ghost();
+        let mut ate = false;
+        for (player, ben) in self.bens.iter_mut().enumerate() {
+            if ben.lives == 0 {
+                continue;
+            }
+
+            let (i, j) = ben.position.tile();
+            match self.pickups[i][j] {
+                BoardTile::Dot => {
+                    ben.score += config.point_values.dot;
+                    self.ghost_house.dot_eaten(preferred);
+                    ate = true;
+                    events.push(SimEvent::DotEaten { player });
+                },
+                BoardTile::PowerUp => {
+                    ben.score += config.point_values.power_up;
+                    self.ghost_chain = 0;
+                    self.scare_ticks = config.scare_ticks;
+                    for ghost in self.ghosts.iter_mut() {
+                        ghost::scare(&mut ghost.attack_state, ghost.release_state);
+                    }
+                    events.push(SimEvent::PowerUpEaten { player });
+                },
+                _ => continue
+            }
+            self.pickups[i][j] = BoardTile::Empty;
+        }
+        ate
+    }
+
+    fn preferred_ghost(&self) -> Option<usize> {
+        ghost::next_in_line(self.ghosts.iter().map(|ghost| (ghost.release_priority, ghost.release_state)))
+    }
+
+    fn release_ghosts(&mut self, board: &Board, config: &SimConfig, ate: bool) {
+        if ate {
+            self.release_ticks = 0;
+        } else {
+            self.release_ticks += 1;
+        }
+
+        // One ghost leaves at a time, in priority order
+        if let Some(ghost) = self.ghosts.iter_mut().find(|ghost| ghost.release_state == ReleaseState::Releasing) {
+            if ghost::leave_house(&mut ghost.position, board, config.ghost_speed) {
+                ghost.release_state = ghost.release_state.next();
+            }
+            return;
+        }
+
+        let timed_out = self.release_ticks >= config.release_timeout_ticks;
+        if let Some(priority) = self.ghost_house.to_release(self.preferred_ghost(), timed_out) {
+            let ghost = self.ghosts.iter_mut().find(|ghost| ghost.release_priority == priority).unwrap();
+            ghost.release_state = ghost.release_state.next();
+            self.release_ticks = 0;
+        }
+    }
+
+    fn move_ghosts(&mut self, board: &Board, config: &SimConfig) {
+        let ben_tiles: Vec<(usize, usize)> = self.bens.iter()
+            .filter(|ben| ben.lives > 0)
+            .map(|ben| ben.position.tile())
+            .collect();
+        let cruise_elroy = config.elroy_thresholds.stage(self.remaining(BoardTile::Dot));
+
+        for ghost in self.ghosts.iter_mut() {
+            match ghost.release_state {
+                ReleaseState::Released => {
+                    // Only Caleb turns into Elroy
+                    let speed = ghost::released_speed(
+                        board,
+                        &ghost.position,
+                        ghost.attack_state,
+                        Some(&cruise_elroy).filter(|_| ghost.release_priority == 0),
+                        config.ghost_speed,
+                        config.ghost_tunnel_speed
+                    );
+                    ghost::chase(&mut ghost.position, &mut ghost.path, board, movement::speed_units(speed), &ben_tiles);
+                },
+                ReleaseState::Eyes | ReleaseState::Entering => {
+                    if ghost::return_home(&mut ghost.position, &mut ghost.release_state, &mut ghost.path, board) {
+                        ghost.revive_ticks = config.revive_ticks;
+                    }
+                },
+                ReleaseState::Reviving => {
+                    ghost.revive_ticks = ghost.revive_ticks.saturating_sub(1);
+                    if ghost.revive_ticks == 0 {
+                        ghost.release_state = ghost.release_state.next();
+                    }
+                },
+                _ => continue
+            }
+        }
+    }
+
+    fn collide(&mut self, board: &Board, config: &SimConfig, events: &mut Vec<SimEvent>) {
+        let players: Vec<usize> = (0..self.bens.len()).filter(|player| self.bens[*player].lives > 0).collect();
+        let bens: Vec<ghost::Movement> = players.iter()
+            .map(|player| (self.bens[*player].previous_position, self.bens[*player].position))
+            .collect();
+        let ghosts: Vec<(ghost::Movement, ReleaseState)> = self.ghosts.iter()
+            .map(|ghost| ((ghost.previous_position, ghost.position), ghost.release_state))
+            .collect();
+
+        let mut caught = Vec::new();
+        for (index, ben) in ghost::touches(board, config.collision_radius, &bens, &ghosts) {
+            let player = players[ben];
+            let ghost = &mut self.ghosts[index];
+            match ghost.attack_state {
+                AttackState::Attacking => if !caught.contains(&player) {
+                    caught.push(player);
+                },
+                AttackState::Scared => {
+                    let chain = self.ghost_chain;
+                    let points = ghost::eat(&mut ghost.release_state, &mut ghost.attack_state, &mut ghost.path, &mut self.ghost_chain, &config.point_values);
+                    self.bens[player].score += points;
+                    events.push(SimEvent::GhostEaten {
+                        player,
+                        ghost: ghost.release_priority,
+                        chain,
+                        points
+                    });
+                }
+            }
+        }
+
+        if caught.is_empty() {
+            return;
+        }
+        for player in caught {
+            self.bens[player].lives -= 1;
+            events.push(SimEvent::BenCaught { player });
+        }
+        self.ghost_house.life_lost();
+        self.reset_positions(board);
+    }
+
+    fn scare_timer(&mut self) {
+        if !self.ghosts.iter().any(|ghost| ghost.attack_state == AttackState::Scared) {
+            return;
+        }
+
+        self.scare_ticks = self.scare_ticks.saturating_sub(1);
+        if self.scare_ticks == 0 {
+            for ghost in self.ghosts.iter_mut() {
+                ghost.attack_state = AttackState::Attacking;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eating_the_last_dot_clears_the_level_with_power_ups_left() {
+        let board = Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET);
+        let config = SimConfig::from_board(&board);
+        let mut sim = Sim::new(&board, 1);
+        let dots = sim.remaining(BoardTile::Dot);
+
+        // Leave only the power-ups and one dot where Ben will be after a tick
+        let mut ahead = sim.clone();
+        ahead.step(&board, &config, &[None]);
+        let (i, j) = ahead.bens[0].position.tile();
+        for pickup in sim.pickups.iter_mut().flatten() {
+            if *pickup == BoardTile::Dot {
+                *pickup = BoardTile::Empty;
+            }
+        }
+        sim.pickups[i][j] = BoardTile::Dot;
+        assert!(sim.remaining(BoardTile::PowerUp) > 0);
+
+        let events = sim.step(&board, &config, &[None]);
+        assert_eq!(events, vec![SimEvent::DotEaten { player: 0 }, SimEvent::LevelCleared]);
+        assert_eq!(sim.level, 2);
+        assert_eq!(sim.bens[0].score, config.point_values.dot);
+        assert_eq!(sim.remaining(BoardTile::Dot), dots);
+    }
+
+    #[test]
+    fn release_timer_lets_a_ghost_out_once_ben_stops_eating() {
+        let board = Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET);
+        let mut config = SimConfig::from_board(&board);
+        config.release_timeout_ticks = 5;
+        let mut sim = Sim::new(&board, 1);
+        sim.ghost_house.dot_limits = [0, 1000, 1000, 1000];
+
+        // Only a dot out of Ben's reach is left, so the level goes on but nothing is eaten
+        for pickup in sim.pickups.iter_mut().flatten() {
+            *pickup = BoardTile::Empty;
+        }
+        sim.pickups[1][1] = BoardTile::Dot;
+
+        for _ in 0..4 {
+            sim.step(&board, &config, &[None]);
+        }
+        assert_eq!(sim.ghosts[1].release_state, ReleaseState::Caged);
+        sim.step(&board, &config, &[None]);
+        assert_eq!(sim.ghosts[1].release_state, ReleaseState::Releasing);
+        assert_eq!(sim.ghosts[2].release_state, ReleaseState::Caged);
+    }
+
+    #[test]
+    fn caleb_leaves_the_house_at_the_usual_speed_as_elroy() {
+        let board = Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET);
+        let mut config = SimConfig::from_board(&board);
+        config.elroy_thresholds = ElroyThresholds([10000, 10000]);
+        let mut sim = Sim::new(&board, 1);
+        let spawn = utils::get_ghost_spawn_position(&board);
+        sim.ghosts.truncate(1);
+        sim.ghosts[0].release_state = ReleaseState::Releasing;
+        sim.ghosts[0].position = spawn;
+
+        sim.step(&board, &config, &[None]);
+        let (dx, dy) = spawn.delta(&sim.ghosts[0].position, &board);
+        assert_eq!(dx.abs() + dy.abs(), movement::speed_units(config.ghost_speed));
+    }
+}
@@ -0,0 +1,310 @@
+use std::fmt::Write as _;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use crate::autopilot::{self, View};
+use crate::board::Board;
+use crate::constants;
+use crate::enums::Direction;
+use crate::sim::{self, Sim, SimConfig, SimEvent};
+
+// Chance each tick that Ben pushes a random direction instead of the strategy's, so that games with the same strategy
+// differ by seed
+const RANDOM_TURN_CHANCE: f64 = 0.005;
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Json
+}
+
+/// What the `simulate` subcommand runs: `simulate [level] [--games <n>] [--seed <first>] [--strategy <name>]
+/// [--format csv|json] [--max-seconds <s>]`, with `--scare <s>`, `--ben-speed <%>`, `--ghost-speed <%>`,
+/// `--dot-points <n>`, `--power-up-points <n>` and `--ghost-points <n>` overriding the level's settings.
+#[derive(Clone, Debug)]
+pub struct SimulateConfig {
+    pub level_path: Option<String>,
+    pub games: u64,
+    pub first_seed: u64,
+    pub strategy: String,
+    pub format: Format,
+    pub max_seconds: f32,
+    pub scare_seconds: Option<f32>,
+    pub ben_speed: Option<f32>,
+    pub ghost_speed: Option<f32>,
+    pub dot_points: Option<u64>,
+    pub power_up_points: Option<u64>,
+    /// What the first ghost of a chain is worth; each after it is worth twice the one before.
+    pub ghost_points: Option<u64>
+}
+
+impl Default for SimulateConfig {
+    fn default() -> Self {
+        Self {
+            level_path: None,
+            games: 100,
+            first_seed: 0,
+            strategy: "cautious".to_string(),
+            format: Format::Csv,
+            max_seconds: 600.,
+            scare_seconds: None,
+            ben_speed: None,
+            ghost_speed: None,
+            dot_points: None,
+            power_up_points: None,
+            ghost_points: None
+        }
+    }
+}
+
+impl SimulateConfig {
+    /// Reads the config from the arguments following `simulate`.
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut config = Self::default();
+        let mut index = 0;
+        while index < args.len() {
+            let flag = args[index].as_str();
+            if !flag.starts_with("--") {
+                config.level_path = Some(flag.to_string());
+                index += 1;
+                continue;
+            }
+
+            let value = args.get(index + 1).ok_or(format!("{} needs a value", flag))?;
+            let number = || value.parse::<f32>().map_err(|_| format!("{} needs a number, not {}", flag, value));
+            let count = || value.parse::<u64>().map_err(|_| format!("{} needs a whole number, not {}", flag, value));
+            match flag {
+                "--games" => config.games = count()?,
+                "--seed" => config.first_seed = count()?,
+                "--strategy" => {
+                    if autopilot::by_name(value).is_none() {
+                        return Err(format!("Unknown strategy {}; try one of {}", value, autopilot::STRATEGY_NAMES.join(", ")));
+                    }
+                    config.strategy = value.clone();
+                },
+                "--format" => config.format = match value.as_str() {
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    _ => return Err(format!("Unknown format {}; try csv or json", value))
+                },
+                "--max-seconds" => config.max_seconds = number()?,
+                "--scare" => config.scare_seconds = Some(number()?),
+                "--ben-speed" => config.ben_speed = Some(number()?),
+                "--ghost-speed" => config.ghost_speed = Some(number()?),
+                "--dot-points" => config.dot_points = Some(count()?),
+                "--power-up-points" => config.power_up_points = Some(count()?),
+                "--ghost-points" => config.ghost_points = Some(count()?),
+                _ => return Err(format!("Unknown option {}", flag))
+            }
+            index += 2;
+        }
+        Ok(config)
+    }
+
+    /// The level's own `SimConfig` with this config's overrides applied.
+    pub fn sim_config(&self, board: &Board) -> SimConfig {
+        let mut sim_config = SimConfig::from_board(board);
+        if let Some(seconds) = self.scare_seconds {
+            sim_config.scare_ticks = sim::ticks(seconds);
+        }
+        if let Some(speed) = self.ben_speed {
+            sim_config.ben_speed = speed;
+        }
+        if let Some(speed) = self.ghost_speed {
+            sim_config.ghost_speed = speed;
+        }
+        if let Some(points) = self.dot_points {
+            sim_config.point_values.dot = points;
+        }
+        if let Some(points) = self.power_up_points {
+            sim_config.point_values.power_up = points;
+        }
+        if let Some(points) = self.ghost_points {
+            sim_config.point_values.first_ghost = points;
+            sim_config.point_values.second_ghost = points * 2;
+            sim_config.point_values.third_ghost = points * 4;
+            sim_config.point_values.fourth_ghost = points * 8;
+        }
+        sim_config
+    }
+}
+
+/// How one simulated game went.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GameReport {
+    pub seed: u64,
+    pub score: u64,
+    pub level: u32,
+    /// Ticks until the game was over, or until it was cut off.
+    pub ticks: u64,
+    /// Whether Ben still had lives left when the game was cut off.
+    pub timed_out: bool,
+    /// Ghosts eaten first, second, third and fourth on a power-up.
+    pub ghosts_eaten: [u32; 4],
+    pub power_ups_eaten: u32,
+    /// Power-ups that wore off without a single ghost eaten.
+    pub power_ups_wasted: u32
+}
+
+/// Plays one game with an autopilot strategy. The seed decides when Ben takes a random turn.
+pub fn play(board: &Board, config: &SimConfig, strategy: &str, seed: u64, max_seconds: f32) -> GameReport {
+    let mut strategy = autopilot::by_name(strategy).unwrap();
+    let mut sim = Sim::new(board, 1);
+    let mut report = GameReport { seed, ..Default::default() };
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    // Ghosts eaten on the power-up in effect, if there is one
+    let mut scare: Option<u32> = None;
+    let max_ticks = sim::ticks(max_seconds) as u64;
+    while sim.tick < max_ticks && !sim.is_over() {
+        let input = if rng.gen_bool(RANDOM_TURN_CHANCE) {
+            Some(DIRECTIONS[rng.gen_range(0..DIRECTIONS.len())])
+        } else {
+            strategy.choose(&View::from_sim(&sim, board, 0))
+        };
+        for event in sim.step(board, config, &[input]) {
+            match event {
+                SimEvent::PowerUpEaten { .. } => {
+                    if scare == Some(0) {
+                        report.power_ups_wasted += 1;
+                    }
+                    report.power_ups_eaten += 1;
+                    scare = Some(0);
+                },
+                SimEvent::GhostEaten { chain, .. } => {
+                    report.ghosts_eaten[(chain as usize).min(3)] += 1;
+                    scare = scare.map(|eaten| eaten + 1);
+                },
+                _ => { }
+            }
+        }
+
+        if sim.scare_ticks == 0 {
+            if scare == Some(0) {
+                report.power_ups_wasted += 1;
+            }
+            scare = None;
+        }
+    }
+    if scare == Some(0) {
+        report.power_ups_wasted += 1;
+    }
+
+    report.score = sim.bens[0].score;
+    report.level = sim.level;
+    report.ticks = sim.tick;
+    report.timed_out = !sim.is_over();
+    report
+}
+
+const CSV_HEADER: &str = "seed,score,level,ticks,timed_out,ghosts_eaten_1,ghosts_eaten_2,ghosts_eaten_3,ghosts_eaten_4,power_ups_eaten,power_ups_wasted";
+
+fn csv_row(report: &GameReport) -> String {
+    format!(
+        "{},{},{},{},{},{},{},{},{},{},{}",
+        report.seed, report.score, report.level, report.ticks, report.timed_out,
+        report.ghosts_eaten[0], report.ghosts_eaten[1], report.ghosts_eaten[2], report.ghosts_eaten[3],
+        report.power_ups_eaten, report.power_ups_wasted
+    )
+}
+
+fn json_object(report: &GameReport) -> String {
+    format!(
+        "{{\"seed\":{},\"score\":{},\"level\":{},\"ticks\":{},\"timed_out\":{},\"ghosts_eaten\":[{},{},{},{}],\"power_ups_eaten\":{},\"power_ups_wasted\":{}}}",
+        report.seed, report.score, report.level, report.ticks, report.timed_out,
+        report.ghosts_eaten[0], report.ghosts_eaten[1], report.ghosts_eaten[2], report.ghosts_eaten[3],
+        report.power_ups_eaten, report.power_ups_wasted
+    )
+}
+
+/// Formats the reports as a CSV table with a header, or as a JSON array with one game per line.
+pub fn format_reports(reports: &[GameReport], format: Format) -> String {
+    let mut output = String::new();
+    match format {
+        Format::Csv => {
+            writeln!(output, "{}", CSV_HEADER).unwrap();
+            for report in reports {
+                writeln!(output, "{}", csv_row(report)).unwrap();
+            }
+        },
+        Format::Json => {
+            output.push_str("[\n");
+            for (index, report) in reports.iter().enumerate() {
+                let separator = if index + 1 < reports.len() { "," } else { "" };
+                writeln!(output, "  {}{}", json_object(report), separator).unwrap();
+            }
+            output.push_str("]\n");
+        }
+    }
+    output
+}
+
+/// Runs the `simulate` subcommand: plays the games, prints them to stdout and a summary to stderr. Returns the process
+/// exit code.
+pub fn run(args: &[String]) -> i32 {
+    let config = match SimulateConfig::from_args(args) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 2;
+        }
+    };
+    let board = match &config.level_path {
+        Some(path) => match Board::from_file(path, constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET) {
+            Ok(board) => board,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 2;
+            }
+        },
+        None => Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET)
+    };
+
+    let sim_config = config.sim_config(&board);
+    let reports: Vec<GameReport> = (config.first_seed..config.first_seed + config.games)
+        .map(|seed| play(&board, &sim_config, &config.strategy, seed, config.max_seconds))
+        .collect();
+    print!("{}", format_reports(&reports, config.format));
+
+    if !reports.is_empty() {
+        let mean = |value: fn(&GameReport) -> u64| reports.iter().map(value).sum::<u64>() as f64 / reports.len() as f64;
+        eprintln!(
+            "{} game(s) with {}: mean score {:.0}, mean level {:.2}, mean survival {:.1}s",
+            reports.len(), config.strategy,
+            mean(|report| report.score),
+            mean(|report| report.level as u64),
+            mean(|report| report.ticks) / sim::TICKS_PER_SECOND as f64
+        );
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn options_are_parsed() {
+        let config = SimulateConfig::from_args(&args("levels/small.txt --games 5 --seed 10 --strategy bait --format json --scare 4")).unwrap();
+        assert_eq!(config.level_path.as_deref(), Some("levels/small.txt"));
+        assert_eq!((config.games, config.first_seed), (5, 10));
+        assert_eq!(config.strategy, "bait");
+        assert_eq!(config.format, Format::Json);
+        assert_eq!(config.scare_seconds, Some(4.));
+        assert!(SimulateConfig::from_args(&args("--strategy nope")).is_err());
+        assert!(SimulateConfig::from_args(&args("--games")).is_err());
+    }
+
+    #[test]
+    fn games_are_repeatable_by_seed() {
+        let board = Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET);
+        let config = SimConfig::from_board(&board);
+        let first = play(&board, &config, "greedy", 7, 60.);
+        assert_eq!(first, play(&board, &config, "greedy", 7, 60.));
+        assert!(first.score > 0);
+        assert!(first.power_ups_wasted <= first.power_ups_eaten);
+    }
+}
@@ -1,119 +1,206 @@
+use std::collections::HashSet;
+use std::hash::Hasher;
 use bevy::prelude::*;
-use crate::ben::BenMaterials;
-use crate::board::{Board, BoardTile};
-use crate::enums::{CollisionType, Direction};
+use crate::board::{Board, BoardTile, SpawnAnchor};
+use crate::enums::CollisionType;
+use crate::movement::TilePosition;
 use crate::dot::DotBundle;
 use crate::power_up::PowerUpBundle;
+use crate::wall::{WallBundle, WallMaterials};
 
-pub fn is_centered_horizontally(transform: &Transform, board: &Board) -> bool {
-    (transform.translation.x - board.offset()) % board.cell_size() == 0.
-}
+/// Converts a point anywhere inside a tile (e.g. the cursor) to that tile's indeces, or `None` off the board.
+pub fn pick_indeces(x: f32, y: f32, board: &Board) -> Option<(usize, usize)> {
+    let board_width = board.width() as f32 * board.cell_size();
+    let board_height = board.height() as f32 * board.cell_size();
+    if x < 0. || y < 0. || x >= board_width || y >= board_height {
+        return None;
+    }
 
-pub fn is_centered_vertically(transform: &Transform, board: &Board) -> bool {
-    (transform.translation.y - board.offset()) % board.cell_size() == 0.
+    // `coordinates_to_indeces` expects tile centres, so snap the point first
+    let center_x = (x / board.cell_size()).floor() * board.cell_size() + board.offset();
+    let center_y = (y / board.cell_size()).floor() * board.cell_size() + board.offset();
+    Some(board.coordinates_to_indeces(center_x, center_y))
 }
 
-pub fn can_move_up(transform: &Transform, board: &Board, speed: f32) -> bool {
-    let new_y = transform.translation.y + speed;
-    let (i, j) = board.coordinates_to_indeces(transform.translation.x, new_y);
-    let new_tile = board.try_get(i, j);
-    is_centered_horizontally(&transform, &board) 
-        && new_tile.is_some() 
-        && new_tile.unwrap() != BoardTile::Wall
-        && new_tile.unwrap() != BoardTile::GhostGate
-}
+/// Whether two actors touched at any point during a tick, given where each of them started and ended it. Checking only
+/// the end positions lets fast actors pass through each other between frames.
+pub fn did_collide(
+    a_from: &TilePosition,
+    a_to: &TilePosition,
+    b_from: &TilePosition,
+    b_to: &TilePosition,
+    board: &Board,
+    collision_type: CollisionType
+) -> bool {
+    match collision_type {
+        CollisionType::Swept(radius) => {
+            // Passed each other on the boundary between two tiles
+            if a_from.tile() == b_to.tile() && b_from.tile() == a_to.tile() && a_to.tile() != b_to.tile() {
+                return true;
+            }
 
-pub fn can_move_right(transform: &Transform, board: &Board, speed: f32) -> bool {
-    let new_x = transform.translation.x + speed;
-    let (i, j) = board.coordinates_to_indeces(new_x - speed + board.cell_size(), transform.translation.y);
-    let new_tile = board.try_get(i, j);
-    is_centered_vertically(&transform, &board) 
-        && new_tile.is_some() 
-        && new_tile.unwrap() != BoardTile::Wall
-        && new_tile.unwrap() != BoardTile::GhostGate
+            // Closest approach, treating both movements as straight lines over the tick
+            let (from_x, from_y) = a_from.delta(b_from, board);
+            let (to_x, to_y) = a_to.delta(b_to, board);
+            let (from_x, from_y, to_x, to_y) = (from_x as f64, from_y as f64, to_x as f64, to_y as f64);
+            let (change_x, change_y) = (to_x - from_x, to_y - from_y);
+            let change = change_x * change_x + change_y * change_y;
+            let t = if change == 0. {
+                0.
+            } else {
+                (-(from_x * change_x + from_y * change_y) / change).clamp(0., 1.)
+            };
+            let (closest_x, closest_y) = (from_x + change_x * t, from_y + change_y * t);
+            closest_x * closest_x + closest_y * closest_y <= (radius as f64) * (radius as f64)
+        }
+    }
 }
 
-pub fn can_move_down(transform: &Transform, board: &Board, speed: f32) -> bool {
-    let new_y = transform.translation.y - speed;
-    let (i, j) = board.coordinates_to_indeces(transform.translation.x, new_y + speed - board.cell_size());
-    let new_tile = board.try_get(i, j);
-    is_centered_horizontally(&transform, &board) 
-        && new_tile.is_some() 
-        && new_tile.unwrap() != BoardTile::Wall
-        && new_tile.unwrap() != BoardTile::GhostGate
-}
+/// 64-bit FNV-1a, for hashes that are compared between machines or stored in files. Unlike `DefaultHasher`, its output
+/// is fixed: integers are hashed as little-endian bytes, and `usize` as a `u64`, whatever the platform or toolchain.
+pub struct StableHasher(u64);
 
-pub fn can_move_left(transform: &Transform, board: &Board, speed: f32) -> bool {
-    let new_x = transform.translation.x - speed;
-    let (i, j) = board.coordinates_to_indeces(new_x, transform.translation.y);
-    let new_tile = board.try_get(i, j);
-    is_centered_vertically(&transform, &board) 
-        && new_tile.is_some() 
-        && new_tile.unwrap() != BoardTile::Wall
-        && new_tile.unwrap() != BoardTile::GhostGate
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
 }
 
-pub fn did_collide(a_transform: &Transform, b_transform: &Transform, board: &Board, collision_type: CollisionType) -> bool {
-    match collision_type {
-        CollisionType::Approximate => {
-            a_transform.translation.x == b_transform.translation.x && (a_transform.translation.y - b_transform.translation.y).abs() <= board.cell_size()
-            || a_transform.translation.y == b_transform.translation.y && (a_transform.translation.x - b_transform.translation.x).abs() <= board.cell_size()
-        },
-        CollisionType::Exact => {
-            a_transform.translation.x == b_transform.translation.x && a_transform.translation.y == b_transform.translation.y
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
         }
     }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+}
+
+pub fn get_anchor_coordinates(board: &Board, anchor: SpawnAnchor) -> (f32, f32) {
+    let (i, j) = board.anchor(anchor);
+    let (x, y) = board.indeces_to_coordinates(i, j);
+    (x + board.cell_size() / 2., y)
 }
 
 pub fn get_ghost_spawn_coordinates(board: &Board) -> (f32, f32) {
-    let x = board.width() as f32 * board.cell_size() / 2.;
-    let (_, y) = board.indeces_to_coordinates(14, 0);
-    (x, y)
+    get_anchor_coordinates(board, SpawnAnchor::GhostHouse)
+}
+
+pub fn get_ghost_exit_coordinates(board: &Board) -> (f32, f32) {
+    get_anchor_coordinates(board, SpawnAnchor::GhostExit)
+}
+
+pub fn get_ghost_spawn_position(board: &Board) -> TilePosition {
+    let (x, y) = get_ghost_spawn_coordinates(board);
+    TilePosition::from_coordinates(board, x, y)
+}
+
+pub fn get_ghost_exit_position(board: &Board) -> TilePosition {
+    let (x, y) = get_ghost_exit_coordinates(board);
+    TilePosition::from_coordinates(board, x, y)
 }
 
 pub fn get_ben_spawn_coordinates(board: &Board) -> (f32, f32) {
-    let x = board.width() as f32 * board.cell_size() / 2.;
-    let (_, y) = board.indeces_to_coordinates(23, 0);
-    (x, y)
+    get_anchor_coordinates(board, SpawnAnchor::Ben)
 }
 
 pub fn get_caleb_spawn_coordinates(board: &Board) -> (f32, f32) {
-    let x = board.width() as f32 * board.cell_size() / 2.;
-    let (_, y) = board.indeces_to_coordinates(11, 0);
-    (x, y)
+    get_anchor_coordinates(board, SpawnAnchor::GhostExit)
 }
 
 pub fn get_harris_spawn_coordinates(board: &Board) -> (f32, f32) {
-
-    let x = board.cell_size() * board.width() as f32 / 2. - board.cell_size() * 2.;
-    let (_, y) = board.indeces_to_coordinates(14, 0);
-    (x, y)
+    let (x, y) = get_anchor_coordinates(board, SpawnAnchor::GhostHouse);
+    (x - board.cell_size() * 2., y)
 }
 
 pub fn get_claflin_spawn_coordinates(board: &Board) -> (f32, f32) {
-    let x = board.width() as f32 * board.cell_size() / 2.;
-    let (_, y) = board.indeces_to_coordinates(14, 0);
-    (x, y)
+    get_anchor_coordinates(board, SpawnAnchor::GhostHouse)
 }
 
 pub fn get_samson_spawn_coordinates(board: &Board) -> (f32, f32) {
-    let x = board.cell_size() * board.width() as f32 / 2. + board.cell_size() * 2.;
-    let (_, y) = board.indeces_to_coordinates(14, 0);
-    (x, y)
+    let (x, y) = get_anchor_coordinates(board, SpawnAnchor::GhostHouse);
+    (x + board.cell_size() * 2., y)
 }
 
+pub fn init_walls(commands: &mut Commands, board: &Board, wall_materials: &WallMaterials) {
+    // The outlines are pre-rendered into a single texture covering the whole board
+    commands.spawn_bundle(WallBundle {
+        sprite_bundle: SpriteBundle {
+            material: wall_materials.maze.clone(),
+            transform: Transform {
+                translation: Vec3::new(board.width() as f32 * board.cell_size() / 2., board.height() as f32 * board.cell_size() / 2., 1.),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    for i in 0..board.height() {
+        for j in 0..board.width() {
+            if board.try_get(i, j).unwrap() != BoardTile::GhostGate {
+                continue;
+            }
+
+            let (x, y) = board.indeces_to_coordinates(i, j);
+            commands.spawn_bundle(WallBundle {
+                sprite_bundle: SpriteBundle {
+                    material: wall_materials.ghost_gate.clone(),
+                    sprite: Sprite::new(Vec2::new(board.cell_size(), board.cell_size() / 6.)),
+                    transform: Transform {
+                        translation: Vec3::new(x, y, 1.),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+        }
+    }
+}
+
+/// Spawns the level's dots and power-ups, leaving out the tiles in `eaten`.
 pub fn init_dots_and_power_ups(
     commands: &mut Commands,
     board: &Board,
     dot_material: Handle<ColorMaterial>,
-    power_up_material: Handle<ColorMaterial>
+    power_up_atlas: Handle<TextureAtlas>,
+    eaten: &HashSet<(usize, usize)>
 ) {
     for i in 0..board.height() {
         for j in 0..board.width() {
+            if eaten.contains(&(i, j)) {
+                continue;
+            }
+
             let (x, y) = board.indeces_to_coordinates(i, j);
             match board.try_get(i, j).unwrap() {
                 BoardTile::Dot => {
                     commands.spawn_bundle(DotBundle {
+                        position: TilePosition::centred(i, j),
                         sprite_bundle: SpriteBundle {
                             material: dot_material.clone(),
                             transform: Transform {
@@ -126,35 +213,113 @@ pub fn init_dots_and_power_ups(
                         ..Default::default()
                     });
                 },
-                BoardTile::PowerUp => {
-                    commands.spawn_bundle(PowerUpBundle {
-                        sprite_bundle: SpriteBundle {
-                            material: power_up_material.clone(),
-                            transform: Transform {
-                                translation: Vec3::new(x, y, 2.),
-                                scale: Vec3::new(1./24., 1./24., 1.),
-                                ..Default::default()
-                            },
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    });
-                },
+                BoardTile::PowerUp => spawn_power_up(commands, board, power_up_atlas.clone(), i, j),
                 _ => continue
             }
         }
     }
 }
 
-pub fn update_ben_sprite(
-    material_handle: &mut Handle<ColorMaterial>,
-    direction: Direction,
-    ben_materials: &BenMaterials
-) {
-    *material_handle = match direction {
-        Direction::Up => ben_materials.ben_up.clone(),
-        Direction::Right => ben_materials.ben_right.clone(), 
-        Direction::Down => ben_materials.ben_down.clone(),
-        Direction::Left => ben_materials.ben_left.clone(),
-    };
+pub fn spawn_power_up(commands: &mut Commands, board: &Board, power_up_atlas: Handle<TextureAtlas>, i: usize, j: usize) {
+    let (x, y) = board.indeces_to_coordinates(i, j);
+    commands.spawn_bundle(PowerUpBundle {
+        position: TilePosition::centred(i, j),
+        sprite_sheet_bundle: SpriteSheetBundle {
+            texture_atlas: power_up_atlas,
+            transform: Transform {
+                translation: Vec3::new(x, y, 2.),
+                scale: Vec3::new(1./24., 1./24., 1.),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants;
+    use crate::enums::Direction;
+    use crate::movement::TILE_UNITS;
+    use crate::sim::{Sim, SimConfig, SimEvent};
+
+    /// Puts Ben and Caleb in place, leaves the other ghosts out and plays the headless sim with no input. Returns the
+    /// first tick on which Ben was caught.
+    fn catch_tick(board: &Board, ben: TilePosition, ghost: TilePosition, ghost_speed: f32, radius: i32, ticks: u64) -> Option<u64> {
+        let mut config = SimConfig::from_board(board);
+        config.ghost_speed = ghost_speed;
+        config.collision_radius = radius;
+        let mut sim = Sim::new(board, 1);
+        sim.bens[0].position = ben;
+        sim.bens[0].direction = Direction::Right;
+        sim.ghosts.truncate(1);
+        sim.ghosts[0].position = ghost;
+
+        while sim.tick < ticks {
+            let tick = sim.tick;
+            if sim.step(board, &config, &[None]).contains(&SimEvent::BenCaught { player: 0 }) {
+                return Some(tick);
+            }
+        }
+        None
+    }
+
+    /// Row and first column of the first straight horizontal corridor with at least `length` open tiles.
+    fn corridor(board: &Board, length: usize) -> (usize, usize) {
+        for i in 0..board.height() {
+            let mut run = 0;
+            for j in 0..board.width() {
+                run = if board.try_get(i, j).unwrap().is_walkable() { run + 1 } else { 0 };
+                if run == length {
+                    return (i, j + 1 - length);
+                }
+            }
+        }
+        panic!("no corridor of length {}", length);
+    }
+
+    #[test]
+    fn stable_hasher_is_fnv_1a() {
+        let hash = |bytes: &[u8]| {
+            let mut hasher = StableHasher::default();
+            hasher.write(bytes);
+            hasher.finish()
+        };
+        assert_eq!(hash(b""), 0xcbf29ce484222325);
+        assert_eq!(hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(hash(b"foobar"), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn respawning_ghost_cannot_pass_through_ben() {
+        let board = Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET);
+        let (i, j) = corridor(&board, 8);
+
+        // Caleb comes at Ben as fast as eyes go home. With no radius at all, only the sweep can notice that they crossed
+        let tick = catch_tick(&board, TilePosition::centred(i, j + 1), TilePosition::centred(i, j + 6), constants::GHOST_SPEED_RESPAWNING, 0, 20);
+        assert!(tick.is_some());
+    }
+
+    #[test]
+    fn ghost_trailing_a_tile_behind_does_not_collide() {
+        let board = Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET);
+        let (i, j) = corridor(&board, 8);
+        let radius = (constants::COLLISION_RADIUS_TILES * TILE_UNITS as f32) as i32;
+        assert_eq!(catch_tick(&board, TilePosition::centred(i, j + 1), TilePosition::centred(i, j), constants::BEN_SPEED_DEFAULT, radius, 40), None);
+    }
+
+    #[test]
+    fn diagonal_overlap_at_a_corner_collides() {
+        let board = Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET);
+        let (i, j) = corridor(&board, 8);
+
+        // Ben is just past the centre of a tile while a ghost cuts into it from above: neither axis lines up
+        let ben = TilePosition { offset_x: 200, ..TilePosition::centred(i, j + 1) };
+        let ghost = TilePosition { offset_y: 400, ..TilePosition::centred(i, j + 1) };
+        let radius = (constants::COLLISION_RADIUS_TILES * TILE_UNITS as f32) as i32;
+        assert!(did_collide(&ben, &ben, &ghost, &ghost, &board, CollisionType::Swept(radius)));
+        assert!(!did_collide(&ben, &ben, &ghost, &ghost, &board, CollisionType::Swept(radius / 2)));
+    }
 }
@@ -0,0 +1,384 @@
+use std::collections::VecDeque;
+use std::fmt;
+use crate::autopilot::{self, Strategy};
+use crate::board::{Board, BoardTile, SpawnAnchor};
+use crate::constants;
+use crate::enums::Direction;
+use crate::sim::{self, Sim, SimConfig, SimEvent};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GhostHouseConnection {
+    ThroughGate,
+    BypassesGate,
+    Disconnected
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TunnelLine {
+    Row(usize),
+    Column(usize)
+}
+
+pub struct ValidationReport {
+    pub unreachable: Vec<(usize, usize, BoardTile)>,
+    pub ghost_house: GhostHouseConnection,
+    pub mismatched_tunnels: Vec<TunnelLine>,
+    pub dead_ends: Vec<(usize, usize)>,
+    pub dots: usize,
+    pub power_ups: usize,
+    pub fruit: usize
+}
+
+impl ValidationReport {
+    /// Dead ends are reported but tolerated; everything else makes the level unplayable.
+    pub fn is_valid(&self) -> bool {
+        self.unreachable.is_empty()
+            && self.ghost_house == GhostHouseConnection::ThroughGate
+            && self.mismatched_tunnels.is_empty()
+            && self.dots > 0
+    }
+
+    pub fn problem_count(&self) -> usize {
+        self.unreachable.len()
+            + self.mismatched_tunnels.len()
+            + (self.ghost_house != GhostHouseConnection::ThroughGate) as usize
+            + (self.dots == 0) as usize
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Dots: {}", self.dots)?;
+        writeln!(f, "Power-ups: {}", self.power_ups)?;
+        writeln!(f, "Fruit: {}", self.fruit)?;
+
+        if self.dots == 0 {
+            writeln!(f, "ERROR: the level has no dots, so it can never be completed")?;
+        }
+
+        for (i, j, tile) in self.unreachable.iter() {
+            writeln!(f, "ERROR: {:?} at ({}, {}) cannot be reached from Ben's spawn", tile, i, j)?;
+        }
+
+        match self.ghost_house {
+            GhostHouseConnection::ThroughGate => { },
+            GhostHouseConnection::BypassesGate => writeln!(f, "ERROR: the ghost house reaches its exit without passing through a ghost gate")?,
+            GhostHouseConnection::Disconnected => writeln!(f, "ERROR: the ghost house is not connected to its exit")?
+        }
+
+        for line in self.mismatched_tunnels.iter() {
+            match line {
+                TunnelLine::Row(i) => writeln!(f, "ERROR: row {} is open on only one edge, so its tunnel cannot wrap", i)?,
+                TunnelLine::Column(j) => writeln!(f, "ERROR: column {} is open on only one edge, so its tunnel cannot wrap", j)?
+            }
+        }
+
+        for (i, j) in self.dead_ends.iter() {
+            writeln!(f, "WARNING: dead-end corridor at ({}, {})", i, j)?;
+        }
+
+        if self.is_valid() {
+            write!(f, "OK")
+        } else {
+            write!(f, "FAILED with {} problem(s)", self.problem_count())
+        }
+    }
+}
+
+pub fn validate(board: &Board) -> ValidationReport {
+    let mut dots = 0;
+    let mut power_ups = 0;
+    let mut fruit = 0;
+    for i in 0..board.height() {
+        for j in 0..board.width() {
+            match board.try_get(i, j).unwrap() {
+                BoardTile::Dot => dots += 1,
+                BoardTile::PowerUp => power_ups += 1,
+                BoardTile::Fruit => fruit += 1,
+                _ => { }
+            }
+        }
+    }
+
+    // Ben spawns on the seam between two tiles, so he can start from either of them
+    let (ben_i, ben_j) = board.anchor(SpawnAnchor::Ben);
+    let reachable = flood_fill(board, &[(ben_i, ben_j), (ben_i, ben_j + 1)], BoardTile::is_walkable);
+    let mut unreachable = Vec::new();
+    let mut dead_ends = Vec::new();
+    for i in 0..board.height() {
+        for j in 0..board.width() {
+            let tile = board.try_get(i, j).unwrap();
+            if matches!(tile, BoardTile::Dot | BoardTile::PowerUp | BoardTile::Fruit) && !reachable[i][j] {
+                unreachable.push((i, j, tile));
+            }
+            if reachable[i][j] && neighbours(board, i, j).filter(|(ni, nj)| board.try_get(*ni, *nj).unwrap().is_walkable()).count() == 1 {
+                dead_ends.push((i, j));
+            }
+        }
+    }
+
+    ValidationReport {
+        unreachable,
+        ghost_house: check_ghost_house(board),
+        mismatched_tunnels: find_mismatched_tunnels(board),
+        dead_ends,
+        dots,
+        power_ups,
+        fruit
+    }
+}
+
+/// How an autopilot fared playing a level headlessly.
+pub struct SoakReport {
+    pub strategy: &'static str,
+    pub ticks: u64,
+    pub levels_cleared: u32,
+    pub lives_lost: u32,
+    pub score: u64,
+    /// The tick from which Ben ate nothing for `SOAK_STALL_SECONDS`, which usually means he can't get to what's left.
+    pub stalled_at: Option<u64>
+}
+
+impl fmt::Display for SoakReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Soak ({}): {} level(s) cleared, {} lives lost, {} points in {} ticks",
+            self.strategy, self.levels_cleared, self.lives_lost, self.score, self.ticks
+        )?;
+        match self.stalled_at {
+            Some(tick) => write!(f, "ERROR: Ben ate nothing for {} seconds from tick {}", SOAK_STALL_SECONDS, tick),
+            None => write!(f, "OK")
+        }
+    }
+}
+
+const SOAK_SECONDS: f32 = 600.;
+const SOAK_STALL_SECONDS: f32 = 30.;
+
+/// Lets an autopilot play the level for up to ten minutes of game time, or until it runs out of lives.
+pub fn soak(board: &Board, mut strategy: Box<dyn Strategy>) -> SoakReport {
+    let config = SimConfig::from_board(board);
+    let mut sim = Sim::new(board, 1);
+    let mut report = SoakReport {
+        strategy: strategy.name(),
+        ticks: 0,
+        levels_cleared: 0,
+        lives_lost: 0,
+        score: 0,
+        stalled_at: None
+    };
+
+    let mut last_eaten = 0;
+    let strategies = std::slice::from_mut(&mut strategy);
+    while sim.tick < sim::ticks(SOAK_SECONDS) as u64 && !sim.is_over() {
+        for event in autopilot::step(&mut sim, board, &config, strategies) {
+            match event {
+                SimEvent::DotEaten { .. } | SimEvent::PowerUpEaten { .. } => last_eaten = sim.tick,
+                SimEvent::LevelCleared => report.levels_cleared += 1,
+                SimEvent::BenCaught { .. } => {
+                    report.lives_lost += 1;
+                    last_eaten = sim.tick;
+                },
+                _ => { }
+            }
+        }
+
+        if sim.tick - last_eaten > sim::ticks(SOAK_STALL_SECONDS) as u64 {
+            report.stalled_at = Some(last_eaten);
+            break;
+        }
+    }
+
+    report.ticks = sim.tick;
+    report.score = sim.bens[0].score;
+    report
+}
+
+/// Loads and validates a level for the `validate` subcommand, printing the report. With `soak`, the named autopilot
+/// strategy also plays it. Returns the process exit code.
+pub fn run(level_path: Option<&str>, soak_strategy: Option<&str>) -> i32 {
+    let board = match level_path {
+        Some(path) => match Board::from_file(path, constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET) {
+            Ok(board) => board,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 2;
+            }
+        },
+        None => Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET)
+    };
+
+    let report = validate(&board);
+    println!("{}", report);
+    if !report.is_valid() {
+        return 1;
+    }
+
+    if let Some(name) = soak_strategy {
+        let strategy = match autopilot::by_name(name) {
+            Some(strategy) => strategy,
+            None => {
+                eprintln!("Unknown strategy {}; try one of {}", name, autopilot::STRATEGY_NAMES.join(", "));
+                return 2;
+            }
+        };
+        let soak_report = soak(&board, strategy);
+        println!("{}", soak_report);
+        if soak_report.stalled_at.is_some() {
+            return 1;
+        }
+    }
+    0
+}
+
+fn check_ghost_house(board: &Board) -> GhostHouseConnection {
+    let house = board.anchor(SpawnAnchor::GhostHouse);
+    let (exit_i, exit_j) = board.anchor(SpawnAnchor::GhostExit);
+    let reaches_exit = |reachable: &Vec<Vec<bool>>| {
+        reachable[exit_i][exit_j] || (exit_j + 1 < board.width() && reachable[exit_i][exit_j + 1])
+    };
+
+    let with_gate = flood_fill(board, &[house], |tile| tile != BoardTile::Wall);
+    let without_gate = flood_fill(board, &[house], BoardTile::is_walkable);
+    if !reaches_exit(&with_gate) {
+        GhostHouseConnection::Disconnected
+    } else if reaches_exit(&without_gate) {
+        GhostHouseConnection::BypassesGate
+    } else {
+        GhostHouseConnection::ThroughGate
+    }
+}
+
+/// The board wraps around like a torus, so a row or column open on one edge must be open on the opposite one too.
+fn find_mismatched_tunnels(board: &Board) -> Vec<TunnelLine> {
+    let rows = (0..board.height())
+        .filter(|i| board.try_get(*i, 0).unwrap().is_walkable() != board.try_get(*i, board.width() - 1).unwrap().is_walkable())
+        .map(TunnelLine::Row);
+    let columns = (0..board.width())
+        .filter(|j| board.try_get(0, *j).unwrap().is_walkable() != board.try_get(board.height() - 1, *j).unwrap().is_walkable())
+        .map(TunnelLine::Column);
+    rows.chain(columns).collect()
+}
+
+fn neighbours(board: &Board, i: usize, j: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+    [Direction::Up, Direction::Right, Direction::Down, Direction::Left]
+        .into_iter()
+        .map(move |direction| board.neighbour(i, j, direction))
+}
+
+fn flood_fill(board: &Board, starts: &[(usize, usize)], passable: impl Fn(BoardTile) -> bool) -> Vec<Vec<bool>> {
+    let mut visited = vec![vec![false; board.width()]; board.height()];
+    let mut queue: VecDeque<(usize, usize)> = starts.iter()
+        .copied()
+        .filter(|(i, j)| *i < board.height() && *j < board.width())
+        .collect();
+
+    while let Some((i, j)) = queue.pop_front() {
+        if visited[i][j] || !passable(board.try_get(i, j).unwrap()) {
+            continue;
+        }
+        visited[i][j] = true;
+        queue.extend(neighbours(board, i, j));
+    }
+
+    visited
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A ring of dots around a ghost house, whose gate opens onto the top corridor
+    const ANCHORS: &str = "@ben_spawn 5 2\n@ghost_house 3 3\n@ghost_exit 1 3\n";
+    const ROWS: [&str; 7] = [
+        "#######",
+        "#.....#",
+        "#.#|#.#",
+        "#.#_#.#",
+        "#.###.#",
+        "#.....#",
+        "#######"
+    ];
+
+    /// The ring, with some of its rows swapped out.
+    fn level(changes: &[(usize, &str)]) -> Board {
+        let mut rows = ROWS;
+        for (i, row) in changes {
+            rows[*i] = row;
+        }
+        let contents = format!("{}{}\n", ANCHORS, rows.join("\n"));
+        Board::from_level_str(&contents, constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET).unwrap()
+    }
+
+    #[test]
+    fn ring_is_valid() {
+        let report = validate(&level(&[]));
+        assert!(report.is_valid());
+        assert_eq!(report.dots, 16);
+        assert!(report.dead_ends.is_empty());
+    }
+
+    #[test]
+    fn default_level_is_valid() {
+        let report = validate(&Board::new(constants::BOARD_CELL_SIZE, constants::BOARD_OFFSET));
+        assert!(report.is_valid(), "{}", report);
+    }
+
+    #[test]
+    fn dot_behind_the_gate_is_unreachable() {
+        let report = validate(&level(&[(3, "#.#.#.#")]));
+        assert_eq!(report.unreachable, vec![(3, 3, BoardTile::Dot)]);
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn house_open_at_the_bottom_bypasses_the_gate() {
+        let report = validate(&level(&[(4, "#.#_#.#")]));
+        assert_eq!(report.ghost_house, GhostHouseConnection::BypassesGate);
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn house_without_a_gate_is_disconnected() {
+        let report = validate(&level(&[(2, "#.###.#")]));
+        assert_eq!(report.ghost_house, GhostHouseConnection::Disconnected);
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn tunnels_open_on_one_edge_are_mismatched() {
+        let report = validate(&level(&[(0, "#_#####"), (5, "_.....#")]));
+        assert_eq!(report.mismatched_tunnels, vec![TunnelLine::Row(5), TunnelLine::Column(1)]);
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn dead_ends_are_reported_but_tolerated() {
+        let report = validate(&level(&[(4, "#####.#")]));
+        assert_eq!(report.dead_ends, vec![(3, 1), (5, 1)]);
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn level_without_dots_cannot_be_completed() {
+        let rows: Vec<String> = ROWS.iter().map(|row| row.replace('.', "_")).collect();
+        let changes: Vec<(usize, &str)> = rows.iter().map(|row| row.as_str()).enumerate().collect();
+        let report = validate(&level(&changes));
+        assert_eq!(report.dots, 0);
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn run_exits_by_outcome() {
+        let path = std::env::temp_dir().join(format!("benjaman-validate-{}.txt", std::process::id()));
+        level(&[(2, "#.###.#")]).save(path.to_str().unwrap()).unwrap();
+        let invalid = run(path.to_str(), None);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(run(None, None), 0);
+        assert_eq!(invalid, 1);
+        assert_eq!(run(Some("no/such/level.txt"), None), 2);
+        assert_eq!(run(None, Some("no-such-strategy")), 2);
+    }
+}
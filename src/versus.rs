@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use crate::ben;
+use crate::board::Board;
+use crate::constants;
+use crate::dot::Dot;
+use crate::enums::Direction;
+use crate::ghost::{self, Ghost, AttackState, GhostSpeed, GhostTunnelSpeed, ReleaseState};
+use crate::misc;
+use crate::movement::{self, TilePosition};
+use crate::practice::Practice;
+
+/// A ghost steered by a player instead of `ghost_movement_system`'s pathfinding. It keeps to the same walls and gates
+/// as Ben, and never waits in the ghost house.
+pub struct HumanControlled {
+    pub direction: Direction,
+    pub next_direction: Option<Direction>
+}
+
+impl Default for HumanControlled {
+    fn default() -> Self {
+        Self {
+            direction: Direction::Left,
+            next_direction: None
+        }
+    }
+}
+
+/// The tally of a versus match. Each round is a whole game of Ben's: he takes it by clearing the board, the ghost by
+/// catching him until he's out of lives.
+pub struct Versus {
+    pub active: bool,
+    pub best_of: u32,
+    pub ben_wins: u32,
+    pub ghost_wins: u32,
+    /// Points the ghost player has earned by catching Ben, over the whole match.
+    pub ghost_score: u64
+}
+
+impl Default for Versus {
+    fn default() -> Self {
+        Self {
+            active: false,
+            best_of: constants::VERSUS_BEST_OF,
+            ben_wins: 0,
+            ghost_wins: 0,
+            ghost_score: 0
+        }
+    }
+}
+
+impl Versus {
+    pub fn new(best_of: u32) -> Self {
+        Self {
+            active: true,
+            best_of,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_decided(&self) -> bool {
+        let needed = self.best_of / 2 + 1;
+        self.ben_wins >= needed || self.ghost_wins >= needed
+    }
+}
+
+/// The ghost player's keys: the arrows, as Ben keeps WASD to himself in versus.
+pub fn ghost_direction_keys() -> Vec<(Direction, KeyCode)> {
+    ben::direction_keys(1, true)
+}
+
+pub fn human_ghost_movement_system(
+    mut query: Query<(&mut TilePosition, &mut HumanControlled, &GhostSpeed, &ReleaseState, &AttackState), With<Ghost>>,
+    keys: Res<Input<KeyCode>>,
+    board: Res<Board>,
+    ghost_tunnel_speed: Res<GhostTunnelSpeed>,
+    practice: Res<Practice>
+) {
+    if practice.are_ghosts_frozen() {
+        return;
+    }
+
+    for (mut position, mut human_controlled, ghost_speed, release_state, attack_state) in query.iter_mut() {
+        if *release_state != ReleaseState::Released {
+            continue;
+        }
+
+        if let Some(&(direction, _)) = ghost_direction_keys().iter().find(|&&(_, key)| keys.pressed(key)) {
+            human_controlled.next_direction = Some(direction);
+        }
+
+        // Turning around works anywhere, other turns wait for a tile centre
+        if human_controlled.next_direction == Some(movement::opposite(human_controlled.direction)) {
+            human_controlled.direction = movement::opposite(human_controlled.direction);
+            human_controlled.next_direction = None;
+        }
+
+        let speed = ghost::released_speed(&board, &position, *attack_state, None, ghost_speed.0, ghost_tunnel_speed.0);
+        let HumanControlled { direction, next_direction } = &mut *human_controlled;
+        *direction = position.travel(&board, *direction, movement::speed_units(speed), |position, direction| {
+            if let Some(turn) = *next_direction {
+                if position.can_move(&board, turn) {
+                    *next_direction = None;
+                    return Some(turn);
+                }
+            }
+
+            if position.can_move(&board, direction) {
+                Some(direction)
+            } else {
+                None
+            }
+        });
+    }
+}
+
+/// Scores the round that just ended, and announces the winner of the match once there is one.
+pub fn tally_round_system(
+    mut versus: ResMut<Versus>,
+    mut end_message_text: ResMut<misc::EndMessageText>,
+    dot_query: Query<&Dot>
+) {
+    if !versus.active {
+        return;
+    }
+
+    let ben_won = dot_query.iter().next().is_none();
+    if ben_won {
+        versus.ben_wins += 1;
+    } else {
+        versus.ghost_wins += 1;
+    }
+
+    end_message_text.0 = format!(
+        "{}\n\nROUND TO {}\nBEN {} - {} GHOST",
+        end_message_text.0,
+        if ben_won { "BEN" } else { "GHOST" },
+        versus.ben_wins,
+        versus.ghost_wins
+    );
+    if versus.is_decided() {
+        let winner = if versus.ben_wins > versus.ghost_wins { "BEN" } else { "GHOST" };
+        end_message_text.0 = format!("{}\n{} WINS THE MATCH", end_message_text.0, winner);
+    }
+}
+
+/// A decided match starts over from nothing; otherwise the next round keeps the tally.
+pub fn reset_versus_system(mut versus: ResMut<Versus>) {
+    if versus.active && versus.is_decided() {
+        *versus = Versus::new(versus.best_of);
+    }
+}
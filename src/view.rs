@@ -0,0 +1,140 @@
+use bevy::{
+    prelude::*,
+    render::camera::OrthographicProjection,
+    window::WindowMode
+};
+use crate::board::Board;
+use crate::constants;
+use crate::settings::{self, Settings};
+
+/// How the board is scaled to fit the window. Whatever space is left over is letterboxed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScaleMode {
+    /// Whole multiples (or whole fractions) of the board's size, so every tile covers the same number of pixels.
+    Integer,
+    /// As large as fits.
+    Smooth
+}
+
+/// The camera the maze is drawn with, as opposed to the UI camera of the HUD.
+pub struct BoardCamera;
+
+/// Where the board is drawn in the window: `origin` is the window position of the board's bottom left corner, and
+/// `scale` the window pixels per board pixel.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoardView {
+    pub scale: f32,
+    pub origin: Vec2
+}
+
+impl Default for BoardView {
+    fn default() -> Self {
+        Self {
+            scale: 1.,
+            origin: Vec2::new(0., constants::HUD_BOTTOM_HEIGHT)
+        }
+    }
+}
+
+impl BoardView {
+    /// Centres the board in the part of the window between the HUD bars.
+    pub fn fit(board: &Board, window_width: f32, window_height: f32, scale_mode: ScaleMode) -> Self {
+        let board_width = board.width() as f32 * board.cell_size();
+        let board_height = board.height() as f32 * board.cell_size();
+        let available_height = window_height - constants::HUD_TOP_HEIGHT - constants::HUD_BOTTOM_HEIGHT;
+
+        let fit = (window_width / board_width).min(available_height / board_height);
+        let scale = match scale_mode {
+            ScaleMode::Smooth => fit,
+            ScaleMode::Integer if fit >= 1. => fit.floor(),
+            ScaleMode::Integer => 1. / (1. / fit).ceil()
+        };
+        Self {
+            scale,
+            origin: Vec2::new(
+                (window_width - board_width * scale) / 2.,
+                constants::HUD_BOTTOM_HEIGHT + (available_height - board_height * scale) / 2.
+            )
+        }
+    }
+
+    /// Turns a window position, e.g. the cursor's, into board coordinates.
+    pub fn to_board(&self, window_position: Vec2) -> Vec2 {
+        (window_position - self.origin) / self.scale
+    }
+}
+
+/// The window size the game opens at: the board at its natural size, shrunk to fit `constants::WINDOW_MAX_HEIGHT`.
+pub fn initial_window_size(board: &Board) -> (f32, f32) {
+    let hud_height = constants::HUD_TOP_HEIGHT + constants::HUD_BOTTOM_HEIGHT;
+    let board_width = board.width() as f32 * board.cell_size();
+    let board_height = board.height() as f32 * board.cell_size();
+    let scale = ((constants::WINDOW_MAX_HEIGHT - hud_height) / board_height).min(1.);
+    (board_width * scale, board_height * scale + hud_height)
+}
+
+pub fn window_mode(fullscreen: bool) -> WindowMode {
+    if fullscreen {
+        WindowMode::BorderlessFullscreen
+    } else {
+        WindowMode::Windowed
+    }
+}
+
+/// Keeps the board camera fitted to the window, which may be resized, or the board, which the editor may resize.
+pub fn board_view_system(
+    mut board_view: ResMut<BoardView>,
+    mut query: Query<(&mut OrthographicProjection, &mut Transform), With<BoardCamera>>,
+    windows: Res<Windows>,
+    settings: Res<Settings>,
+    board: Res<Board>
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return
+    };
+    // A minimised window has no size to fit into
+    if window.width() <= 0. || window.height() <= constants::HUD_TOP_HEIGHT + constants::HUD_BOTTOM_HEIGHT {
+        return;
+    }
+
+    let fitted = BoardView::fit(&board, window.width(), window.height(), settings.scale_mode);
+    if fitted == *board_view {
+        return;
+    }
+
+    *board_view = fitted;
+    let (mut projection, mut transform) = query.single_mut().unwrap();
+    projection.scale = 1. / fitted.scale;
+    transform.translation.x = -fitted.origin.x / fitted.scale;
+    transform.translation.y = -fitted.origin.y / fitted.scale;
+}
+
+/// F11 toggles fullscreen from anywhere, and remembers it.
+pub fn fullscreen_toggle_system(
+    mut settings: ResMut<Settings>,
+    keys: Res<Input<KeyCode>>
+) {
+    if keys.just_pressed(KeyCode::F11) {
+        settings.fullscreen = !settings.fullscreen;
+        if let Err(err) = settings.save(settings::SETTINGS_PATH) {
+            eprintln!("{}", err);
+        }
+    }
+}
+
+pub fn window_mode_system(
+    mut windows: ResMut<Windows>,
+    settings: Res<Settings>
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    if let Some(window) = windows.get_primary_mut() {
+        let mode = window_mode(settings.fullscreen);
+        if window.mode() != mode {
+            window.set_mode(mode);
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+
+pub struct Wall;
+
+pub struct WallMaterials {
+    pub maze: Handle<ColorMaterial>,
+    pub maze_texture: Handle<Texture>,
+    pub ghost_gate: Handle<ColorMaterial>
+}
+
+/// Flashes the maze white at the end of a level. `remaining` counts colour changes still to be made.
+pub struct MazeFlash {
+    pub timer: Timer,
+    pub remaining: u8
+}
+
+impl Default for MazeFlash {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(0.25, true),
+            remaining: 0
+        }
+    }
+}
+
+#[derive(Bundle)]
+pub struct WallBundle {
+    pub wall: Wall,
+
+    #[bundle]
+    pub sprite_bundle: SpriteBundle
+}
+
+impl Default for WallBundle {
+    fn default() -> Self {
+        Self {
+            wall: Wall,
+            sprite_bundle: SpriteBundle::default()
+        }
+    }
+}